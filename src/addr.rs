@@ -25,6 +25,7 @@ fn as_u8(slice: &[c_char]) -> &[u8] {
     unsafe { &*(slice as *const[c_char] as *const[u8]) }
 }
 
+#[allow(clippy::redundant_static_lifetimes)]
 const TOO_LONG_DESC: &'static str = "address is too long";
 
 /// A unix domain socket address.
@@ -259,6 +260,7 @@ impl UnixSocketAddr {
     /// This function will return an error if the path is too long for the
     /// underlying `sockaddr_un` type, or contains NUL (`'\0'`) bytes.
     pub fn from_path<P: AsRef<Path>+?Sized>(path: &P) -> Result<Self, io::Error> {
+        #[allow(clippy::manual_contains)]
         fn from_path_inner(path: &[u8]) -> Result<UnixSocketAddr, io::Error> {
             let mut addr = UnixSocketAddr::new_unspecified();
             let capacity = UnixSocketAddr::max_path_len();
@@ -444,6 +446,7 @@ impl UnixSocketAddr {
     ///     );
     /// }
     /// ```
+    #[allow(unknown_lints, mismatched_lifetime_syntaxes)]
     pub fn name(&self) -> AddrName {
         AddrName::from(self)
     }
@@ -489,6 +492,7 @@ impl UnixSocketAddr {
     /// assert_eq!(addr.as_ref(), UnixSocketAddrRef::Unnamed);
     /// # std::fs::remove_file("dgram.socket").expect("clean up socket file");
     /// ```
+    #[allow(unknown_lints, mismatched_lifetime_syntaxes)]
     pub fn as_ref(&self) -> UnixSocketAddrRef {
         UnixSocketAddrRef::from(self)
     }
@@ -625,41 +629,49 @@ impl UnixSocketAddr {
     pub fn new_from_ffi<R, F>(call: F) -> Result<(R, Self), io::Error>
     where F: FnOnce(&mut sockaddr, &mut socklen_t) -> Result<R, io::Error> {
         let mut addr = Self::new_unspecified();
-        let capacity = mem::size_of_val(&addr.addr) as socklen_t;
-        addr.len = capacity;
+        addr.len = mem::size_of_val(&addr.addr) as socklen_t;
         unsafe {
             let (addr_ptr, addr_len_ptr) = addr.as_raw_mut_general();
             let ret = call(addr_ptr, addr_len_ptr)?;
-            if addr.addr.sun_family != AF_UNIX as sa_family_t {
+            addr.normalize_from_ffi()?;
+            Ok((ret, addr))
+        }
+    }
+
+    /// Validates and normalizes an address (and length) written by the OS
+    /// into an address created with `new_unspecified()`,
+    /// as described for [`new_from_ffi()`](#method.new_from_ffi).
+    pub(crate) fn normalize_from_ffi(&mut self) -> Result<(), io::Error> {
+        let capacity = mem::size_of_val(&self.addr) as socklen_t;
+        if self.addr.sun_family != AF_UNIX as sa_family_t {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "file descriptor did not correspond to a Unix socket" // identical to std's
+            ));
+        }
+        if self.is_abstract() {
+            if self.len > capacity {
                 return Err(io::Error::new(
                     ErrorKind::InvalidData,
-                    "file descriptor did not correspond to a Unix socket" // identical to std's
+                    "abstract name was too long"
                 ));
             }
-            if addr.is_abstract() {
-                if addr.len > capacity {
-                    return Err(io::Error::new(
-                        ErrorKind::InvalidData,
-                        "abstract name was too long"
-                    ));
-                }
-            } else if addr.is_path() {
-                if addr.len > capacity+1 {
-                    return Err(io::Error::new(ErrorKind::InvalidData, "path was too long"));
-                    // accept lengths one too big; assume the truncated byte was NUL
-                } else {
-                    // normalize addr.len to include terminating NUL byte if possible
-                    // and not be greater than capacity
-                    if addr.len >= capacity {
-                        addr.len = capacity;
-                    } else if addr.addr.sun_path[(addr.len-1-path_offset()) as usize] != 0 {
-                        addr.len += 1;
-                        addr.addr.sun_path[(addr.len-1-path_offset()) as usize] = 0;
-                    }
+        } else if self.is_path() {
+            if self.len > capacity+1 {
+                return Err(io::Error::new(ErrorKind::InvalidData, "path was too long"));
+                // accept lengths one too big; assume the truncated byte was NUL
+            } else {
+                // normalize self.len to include terminating NUL byte if possible
+                // and not be greater than capacity
+                if self.len >= capacity {
+                    self.len = capacity;
+                } else if self.addr.sun_path[(self.len-1-path_offset()) as usize] != 0 {
+                    self.len += 1;
+                    self.addr.sun_path[(self.len-1-path_offset()) as usize] = 0;
                 }
             }
-            Ok((ret, addr))
         }
+        Ok(())
     }
 
     /// Creates an `UnixSocketAddr` from a pointer to a generic `sockaddr` and
@@ -1,29 +1,48 @@
+//! Types for receiving ancillary messages (also known as control messages)
+//! along with packets, datagrams or stream bytes.
+//!
+//! The socket types and extension traits of this crate have
+//! `recv_ancillary()` methods which return an [`Ancillary`](struct.Ancillary.html)
+//! iterator over the received messages.
+
 use std::ops::{Deref, DerefMut};
 use std::borrow::{Borrow, BorrowMut};
-use std::os::unix::io::RawFd;
+use std::os::unix::io::{RawFd, FromRawFd, AsRawFd, AsFd, OwnedFd};
 use std::io::{self, ErrorKind, IoSlice, IoSliceMut};
 use std::alloc::{self, Layout};
 use std::convert::TryInto;
 use std::{mem, ptr, slice};
 use std::marker::PhantomData;
+use std::fmt::{self, Debug};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use libc::{c_int, c_uint, c_void};
 use libc::{msghdr, iovec, cmsghdr, sockaddr, sockaddr_un};
 use libc::{sendmsg, recvmsg, close};
 //#[cfg(not(any(target_os="illumos", target_os="solaris")))]
-use libc::{MSG_TRUNC, MSG_CTRUNC};
+use libc::{MSG_TRUNC, MSG_CTRUNC, MSG_PEEK};
 #[cfg(not(any(target_os="illumos", target_os="solaris")))]
 use libc::{CMSG_SPACE, CMSG_LEN, CMSG_DATA, CMSG_FIRSTHDR, CMSG_NXTHDR};
 //#[cfg(not(any(target_os="illumos", target_os="solaris")))]
-use libc::{SOL_SOCKET, SCM_RIGHTS};
+use libc::{SOL_SOCKET, SCM_RIGHTS, SCM_TIMESTAMP, timeval};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{SCM_TIMESTAMPNS, timespec};
 #[cfg(any(target_os="linux", target_os="android"))]
 use libc::SCM_CREDENTIALS;
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::recv;
+/// Not in libc, value is from linux/socket.h
+#[cfg(any(target_os="linux", target_os="android"))]
+const SCM_SECURITY: c_int = 0x03;
 #[cfg(not(any(target_vendor="apple", target_os="illumos", target_os="solaris")))]
 use libc::MSG_CMSG_CLOEXEC;
 
 use crate::helpers::*;
 use crate::UnixSocketAddr;
 use crate::credentials::{SendCredentials, ReceivedCredentials};
+use crate::pidfd::PidFd;
+#[cfg(any(target_os="linux", target_os="android"))]
+use crate::pidfd::SCM_PIDFD;
 #[cfg(any(target_os="linux", target_os="android"))]
 use crate::credentials::RawReceivedCredentials;
 
@@ -45,13 +64,207 @@ type ControlLen = usize;
 )))]
 type ControlLen = libc::socklen_t;
 
+/// Ancillary messages to send along with bytes.
+///
+/// Any number of messages can be added, and they will be sent in the order
+/// they were added.
+/// The messages are encoded when added, so the builder can be reused for
+/// multiple sends.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::{UnixSeqpacketConn, SendCredentials};
+/// use uds::ancillary::{SendAncillary, AncillaryBuf, AncillaryItem};
+/// use std::io::{IoSlice, IoSliceMut};
+/// use std::os::unix::io::AsRawFd;
+///
+/// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+/// let mut ancillary = SendAncillary::new();
+/// ancillary.add_fds(&[a.as_raw_fd()]).add_credentials(SendCredentials::Effective);
+/// a.send_ancillary(&[IoSlice::new(b"fd")], &ancillary).unwrap();
+///
+/// let mut buf = [0u8; 10];
+/// let mut ancillary_buf = AncillaryBuf::with_capacity(128);
+/// let (bytes, received) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+///     .unwrap();
+/// assert_eq!(&buf[..bytes], b"fd");
+/// for message in received {
+///     if let AncillaryItem::Fds(fds) = message {
+///         assert_eq!(fds.len(), 1);
+///         unsafe { libc::close(fds[0]) };
+///     }
+/// }
+/// ```
+pub struct SendAncillary {
+    buf: AncillaryBuf,
+    len: usize,
+    invalid: Option<(ErrorKind, &'static str)>,
+}
+impl Default for SendAncillary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Debug for SendAncillary {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("SendAncillary")
+            .field("bytes", &self.len)
+            .field("invalid", &self.invalid.map(|(_, desc)| desc ))
+            .finish()
+    }
+}
+impl SendAncillary {
+    /// Creates an empty builder, which won't allocate until more than
+    /// [`AncillaryBuf::MAX_STACK_CAPACITY`](struct.AncillaryBuf.html#associatedconstant.MAX_STACK_CAPACITY)
+    /// bytes are added.
+    pub fn new() -> Self {
+        SendAncillary {
+            buf: AncillaryBuf::with_capacity(AncillaryBuf::MAX_STACK_CAPACITY),
+            len: 0,
+            invalid: None,
+        }
+    }
+
+    /// Returns `true` if no messages have been added.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0  &&  self.invalid.is_none()
+    }
+
+    /// Removes all added messages so the builder can be reused.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.invalid = None;
+    }
+
+    /// Adds an `SCM_RIGHTS` message with the file descriptors.
+    ///
+    /// The file descriptors are not duplicated or closed,
+    /// so they must remain open until sent.
+    /// An empty slice adds nothing.
+    pub fn add_fds(&mut self,  fds: &[RawFd]) -> &mut Self {
+        if fds.is_empty() {
+            return self;
+        }
+        if fds.len() > 0xff_ff_ff {
+            // need to prevent truncation.
+            // I use a lower limit in case the macros don't handle overflow.
+            self.set_invalid(ErrorKind::InvalidInput, "too many file descriptors");
+            return self;
+        }
+        let payload = unsafe {
+            slice::from_raw_parts(fds.as_ptr() as *const u8, mem::size_of_val(fds))
+        };
+        self.add_raw(SOL_SOCKET, SCM_RIGHTS, payload)
+    }
+
+    /// Adds an `SCM_CREDENTIALS` message.
+    ///
+    /// Only on Linux (& Android) does one need to send credentials,
+    /// and on other operating systems nothing is added.
+    pub fn add_credentials(&mut self,  creds: SendCredentials) -> &mut Self {
+        #[cfg(any(target_os="linux", target_os="android"))] {
+            let creds = creds.into_raw();
+            let payload = unsafe {
+                slice::from_raw_parts(&creds as *const _ as *const u8, mem::size_of_val(&creds))
+            };
+            self.add_raw(SOL_SOCKET, SCM_CREDENTIALS, payload)
+        }
+        #[cfg(not(any(target_os="linux", target_os="android")))] {
+            let _ = creds; // silence `unused` warning
+            self
+        }
+    }
+
+    /// Adds an arbitrary ancillary message.
+    ///
+    /// `level` and `kind` become `cmsg_level` and `cmsg_type` respectively,
+    /// and `payload` is copied into the message unmodified.
+    ///
+    /// No validation is performed, so the OS might reject the message
+    /// when sending, and it's up to the caller to keep any file descriptors
+    /// referenced by the payload open.
+    #[allow(clippy::absurd_extreme_comparisons, clippy::unnecessary_min_or_max)] // depends on OS
+    pub fn add_raw(&mut self,  level: c_int,  kind: c_int,  payload: &[u8]) -> &mut Self {
+        #[cfg(not(any(target_os="illumos", target_os="solaris")))]
+        unsafe {
+            let max_payload = AncillaryBuf::MAX_CAPACITY.min(c_uint::MAX as usize) / 2;
+            if payload.len() > max_payload {
+                self.set_invalid(ErrorKind::InvalidInput, "too long ancillary message");
+                return self;
+            }
+            let space = CMSG_SPACE(payload.len() as c_uint) as usize;
+            let needed = match self.len.checked_add(space) {
+                Some(needed) if needed <= AncillaryBuf::MAX_CAPACITY => needed,
+                _ => {
+                    self.set_invalid(ErrorKind::InvalidInput, "too much ancillary data");
+                    return self;
+                }
+            };
+            if needed > self.buf.len() {
+                let capacity = needed.max(self.buf.len() * 2).min(AncillaryBuf::MAX_CAPACITY);
+                let mut grown = AncillaryBuf::with_capacity(capacity);
+                grown[..self.len].copy_from_slice(&self.buf[..self.len]);
+                self.buf = grown;
+            }
+            // zero padding
+            for b in &mut self.buf[self.len..needed] {
+                *b = 0;
+            }
+            let header = self.buf.as_mut_ptr().add(self.len) as *mut cmsghdr;
+            (*header).cmsg_level = level;
+            (*header).cmsg_type = kind;
+            (*header).cmsg_len = CMSG_LEN(payload.len() as c_uint) as ControlLen;
+            let data = CMSG_DATA(header);
+            ptr::copy_nonoverlapping(payload.as_ptr(), data, payload.len());
+            self.len = needed;
+        }
+        #[cfg(any(target_os="illumos", target_os="solaris"))] {
+            let _ = (level, kind, payload); // silence `unused` warning
+            self.set_invalid(
+                ErrorKind::Other,
+                "ancillary data support is not implemented yet for Illumos or Solaris"
+            );
+        }
+        self
+    }
+
+    fn set_invalid(&mut self,  kind: ErrorKind,  desc: &'static str) {
+        if self.invalid.is_none() {
+            self.invalid = Some((kind, desc));
+        }
+    }
+
+    /// The encoded messages, or the error from adding one.
+    pub(crate) fn encoded(&self) -> Result<&[u8], io::Error> {
+        match self.invalid {
+            Some((kind, desc)) => Err(io::Error::new(kind, desc)),
+            None => Ok(&self.buf[..self.len]),
+        }
+    }
+}
+
 /// Safe wrapper around `sendmsg()`.
-pub fn send_ancillary(
+pub(crate) fn send_ancillary(
     socket: RawFd,  to: Option<&UnixSocketAddr>,  flags: c_int,
     bytes: &[IoSlice],  fds: &[RawFd],  creds: Option<SendCredentials>
 ) -> Result<usize, io::Error> {
-    #[cfg(not(any(target_os="linux", target_os="android")))]
-    let _ = creds; // silence `unused` warning
+    let mut ancillary = SendAncillary::new();
+    if let Some(creds) = creds {
+        ancillary.add_credentials(creds);
+    }
+    ancillary.add_fds(fds);
+    send_ancillary_msgs(socket, to, flags, bytes, &ancillary)
+}
+
+/// Safe wrapper around `sendmsg()` for any ancillary messages.
+#[allow(clippy::useless_conversion)] // msg_iovlen is usize on some OSes
+pub(crate) fn send_ancillary_msgs(
+    socket: RawFd,  to: Option<&UnixSocketAddr>,  flags: c_int,
+    bytes: &[IoSlice],  ancillary: &SendAncillary,
+) -> Result<usize, io::Error> {
+    let control = ancillary.encoded()?;
     unsafe {
         let mut msg: msghdr = mem::zeroed();
         msg.msg_name = ptr::null_mut();
@@ -73,75 +286,13 @@ pub fn send_ancillary(
             msg.msg_namelen = len;
         }
 
-        let mut needed_capacity = 0;
-        #[cfg(any(target_os="linux", target_os="android"))]
-        let creds = creds.map(|creds| {
-            let creds = creds.into_raw();
-            needed_capacity += CMSG_LEN(mem::size_of_val(&creds) as u32);
-            creds
-        });
-        if fds.len() > 0 {
-            if fds.len() > 0xff_ff_ff {
-                // need to prevent truncation.
-                // I use a lower limit in case the macros don't handle overflow.
-                return Err(io::Error::new(ErrorKind::InvalidInput, "too many file descriptors"));
-            }
-            #[cfg(not(any(target_os="illumos", target_os="solaris")))] {
-                needed_capacity += CMSG_LEN(mem::size_of_val::<[RawFd]>(fds) as u32);
-            }
-            #[cfg(any(target_os="illumos", target_os="solaris"))] {
-                return Err(io::Error::new(
-                    ErrorKind::Other,
-                    "ancillary data support is not implemented yet for Illumos or Solaris"
-                ))
-            }
-        }
-        // stack buffer which should be big enough for most scenarios
-        struct AncillaryFixedBuf(/*for alignment*/[cmsghdr; 0], [u8; 256]);
-        let mut ancillary_buf = AncillaryFixedBuf([], [0; 256]);
-
-        msg.msg_controllen = needed_capacity as ControlLen;
-        if needed_capacity != 0 {
-            if needed_capacity as usize <= mem::size_of::<AncillaryFixedBuf>() {
-                msg.msg_control = &mut ancillary_buf.1 as *mut [u8; 256] as *mut c_void;
-            } else {
-                let layout = Layout::from_size_align(
-                    needed_capacity as usize,
-                    mem::align_of::<cmsghdr>()
-                ).unwrap();
-                msg.msg_control = alloc::alloc(layout) as *mut c_void;
-            }
-
-            #[cfg(not(any(target_os="illumos", target_os="solaris")))] {
-                let mut header = &mut*CMSG_FIRSTHDR(&mut msg);
-                #[cfg(any(target_os="linux", target_os="android"))] {
-                    if let Some(creds) = creds {
-                        header.cmsg_level = SOL_SOCKET;
-                        header.cmsg_type = SCM_CREDENTIALS;
-                        header.cmsg_len = CMSG_LEN(mem::size_of_val(&creds) as u32) as ControlLen;
-                        *(CMSG_DATA(header) as *mut _) = creds;
-                        header = &mut*CMSG_NXTHDR(&mut msg, header);
-                    }
-                }
-
-                if fds.len() > 0 {
-                    header.cmsg_level = SOL_SOCKET;
-                    header.cmsg_type = SCM_RIGHTS;
-                    header.cmsg_len = CMSG_LEN(mem::size_of_val(fds) as u32) as ControlLen;
-                    let dst = &mut*(CMSG_DATA(header) as *mut RawFd);
-                    ptr::copy_nonoverlapping(fds.as_ptr(), dst, fds.len());
-                }
-            }
+        if !control.is_empty() {
+            // sendmsg() doesn't modify the buffer
+            msg.msg_control = control.as_ptr() as *mut c_void;
+            msg.msg_controllen = control.len() as ControlLen;
         }
 
-        let result = cvt_r!(sendmsg(socket, &msg, flags | MSG_NOSIGNAL));
-
-        if needed_capacity as usize > mem::size_of::<AncillaryFixedBuf>() {
-            let layout = Layout::from_size_align(needed_capacity as usize, mem::align_of::<cmsghdr>()).unwrap();
-            alloc::dealloc(msg.msg_control as *mut u8, layout);
-        }
-
-        result.map(|sent| sent as usize )
+        cvt_r!(sendmsg(socket, &msg, flags | MSG_NOSIGNAL)).map(|sent| sent as usize )
     }
 }
 
@@ -150,6 +301,9 @@ pub fn send_ancillary(
 /// An ancillary data buffer that supports any capacity.
 ///
 /// For reasonable ancillary capacities it uses a stack-based array.
+///
+/// Buffers passed to `recv_ancillary()` must be aligned for `cmsghdr`,
+/// which this type guarantees but a plain `[u8]` array does not.
 #[repr(C)]
 pub struct AncillaryBuf {
     capacity: ControlLen,
@@ -158,6 +312,7 @@ pub struct AncillaryBuf {
     on_stack: [u8; Self::MAX_STACK_CAPACITY],
 }
 impl Drop for AncillaryBuf {
+    #[allow(clippy::unnecessary_cast)] // ControlLen is usize on some OSes
     fn drop(&mut self) {
         unsafe {
             if self.capacity as usize > Self::MAX_STACK_CAPACITY {
@@ -171,8 +326,19 @@ impl Drop for AncillaryBuf {
     }
 }
 impl AncillaryBuf {
+    /// The biggest capacity that is stored inline instead of allocated.
     pub const MAX_STACK_CAPACITY: usize = 256;
-    pub const MAX_CAPACITY: usize = ControlLen::max_value() as usize;
+    /// The biggest capacity supported by `recvmsg()` on this OS.
+    #[allow(clippy::unnecessary_cast)] // ControlLen is usize on some OSes
+    pub const MAX_CAPACITY: usize = ControlLen::MAX as usize;
+    /// Creates a buffer that can hold `bytes` bytes of ancillary messages,
+    /// including headers and padding.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is greater than [`MAX_CAPACITY`](#associatedconstant.MAX_CAPACITY).
+    #[allow(clippy::match_overlapping_arm)]
+    #[allow(clippy::unnecessary_cast)] // ControlLen is usize on some OSes
     pub fn with_capacity(bytes: usize) -> Self {
         Self {
             capacity: bytes as ControlLen,
@@ -183,7 +349,7 @@ impl AncillaryBuf {
                         bytes as usize,
                         mem::align_of::<cmsghdr>()
                     ).unwrap();
-                    alloc::alloc(layout)
+                    alloc::alloc_zeroed(layout)
                 },
                 _ => panic!("capacity is too high"),
             },
@@ -191,6 +357,13 @@ impl AncillaryBuf {
             on_stack: [0; Self::MAX_STACK_CAPACITY],
         }
     }
+    /// Creates a buffer big enough to receive `num_fds` file descriptors
+    /// in one ancillary message.
+    ///
+    /// # Panics
+    ///
+    /// If the needed capacity would be greater than
+    /// [`MAX_CAPACITY`](#associatedconstant.MAX_CAPACITY).
     pub fn with_fd_capacity(num_fds: usize) -> Self {
         #[cfg(not(any(target_os="illumos", target_os="solaris")))]
         unsafe {
@@ -206,7 +379,7 @@ impl AncillaryBuf {
             //  problem. (libc doesn't have a const_fn feature, probably
             //  because old compilers wouldn't be able to even parse it.
             let max_fds =
-                (c_uint::max_value() - CMSG_SPACE(0)) as usize
+                (c_uint::MAX - CMSG_SPACE(0)) as usize
                 / mem::size_of::<RawFd>();
             if num_fds == 0 {
                 Self::with_capacity(0)
@@ -235,6 +408,7 @@ impl Default for AncillaryBuf {
 
 impl Deref for AncillaryBuf {
     type Target = [u8];
+    #[allow(clippy::unnecessary_cast)] // ControlLen is usize on some OSes
     fn deref(&self) -> &[u8] {
         unsafe {
             self.on_stack.get(..self.capacity as usize)
@@ -243,6 +417,7 @@ impl Deref for AncillaryBuf {
     }
 }
 impl DerefMut for AncillaryBuf {
+    #[allow(clippy::unnecessary_cast)] // ControlLen is usize on some OSes
     fn deref_mut(&mut self) -> &mut[u8] {
         unsafe {
             match self.on_stack.get_mut(..self.capacity as usize) {
@@ -253,6 +428,7 @@ impl DerefMut for AncillaryBuf {
     }
 }
 impl Borrow<[u8]> for AncillaryBuf {
+    #[allow(clippy::borrow_deref_ref)]
     fn borrow(&self) -> &[u8] {
         &*self
     }
@@ -263,6 +439,7 @@ impl BorrowMut<[u8]> for AncillaryBuf {
     }
 }
 impl AsRef<[u8]> for AncillaryBuf {
+    #[allow(clippy::borrow_deref_ref)]
     fn as_ref(&self) -> &[u8] {
         &*self
     }
@@ -275,24 +452,51 @@ impl AsMut<[u8]> for AncillaryBuf {
 
 
 
-/// One ancillary message produced by [`Ancillary`](#struct.Ancillary)
+/// One ancillary message produced by [`Ancillary`](struct.Ancillary.html)
+#[derive(Debug)]
 pub enum AncillaryItem<'a> {
     /// One or more file descriptors sent by the peer.
     ///
     /// Consumer of the iterator is responsible for closing them.
+    /// When returned by `peek_ancillary()`, they have already been closed
+    /// and replaced by `-1`, so only the number of them is meaningful.
     Fds(&'a[RawFd]),
     /// Credentials of the sending process.
-    #[allow(unused)]
     Credentials(ReceivedCredentials),
-    //Timestamp(),
-    //SecurityContext(&'a[u8]),
+    /// When the packet or datagram was received by the kernel.
+    ///
+    /// This is only received if `set_receive_timestamps()` has been enabled.
+    Timestamp(SystemTime),
+    /// The security context (label) of the sending process,
+    /// without any trailing NUL.
+    ///
+    /// This is only received on Linux and Android if `set_pass_security()`
+    /// has been enabled and a security module which provides labels
+    /// (such as SELinux or Smack) is active.
+    SecurityContext(&'a[u8]),
+    /// A pidfd referring to the sending process.
+    ///
+    /// This is only received on Linux 6.5 and later, if `set_pass_pidfd()`
+    /// has been enabled.
+    Pidfd(PidFd),
     /// An unknown or unsupported ancillary message type was received.
     ///
-    /// It's up to you whether to ignore or treat as an error.
-    Unsupported
+    /// It's up to you whether to ignore, parse or treat as an error.
+    /// Messages of known types but with too short payloads also end up here.
+    Unsupported {
+        /// `cmsg_level`, usually `SOL_SOCKET`.
+        level: c_int,
+        /// `cmsg_type`.
+        kind: c_int,
+        /// The payload, which is not necessarily aligned.
+        data: &'a[u8],
+    },
 }
 
 /// An iterator over ancillary messages received with `recv_ancillary()`.
+///
+/// File descriptors in messages that haven't been iterated over
+/// when this struct is dropped are closed.
 pub struct Ancillary<'a> {
     // addr and bytes are not used here:
     // * addr is usually placed on the stack by the calling wrapper method,
@@ -308,6 +512,8 @@ pub struct Ancillary<'a> {
 impl<'a> Iterator for Ancillary<'a> {
     type Item = AncillaryItem<'a>;
     #[cfg(not(any(target_os="illumos", target_os="solaris")))]
+    #[allow(clippy::unnecessary_cast)] // cmsg_len and msg_controllen are usize on some OSes
+    #[allow(clippy::unnecessary_mut_passed)]
     fn next(&mut self) -> Option<AncillaryItem<'a>> {
         unsafe {
             if self.next_message.is_null() {
@@ -337,12 +543,48 @@ impl<'a> Iterator for Ancillary<'a> {
                     AncillaryItem::Fds(fds)
                 }
                 #[cfg(any(target_os="linux", target_os="android"))]
-                (SOL_SOCKET, SCM_CREDENTIALS) => {
-                    // FIXME check payload size?
+                (SOL_SOCKET, SCM_CREDENTIALS)
+                if payload_bytes >= mem::size_of::<RawReceivedCredentials>() => {
                     let creds_ptr = CMSG_DATA(self.next_message) as *const RawReceivedCredentials;
                     AncillaryItem::Credentials(ReceivedCredentials::from_raw(*creds_ptr))
                 }
-                _ => AncillaryItem::Unsupported,
+                #[cfg(any(target_os="linux", target_os="android"))]
+                (SOL_SOCKET, SCM_SECURITY) => {
+                    let context = CMSG_DATA(self.next_message) as *const u8;
+                    let mut context = slice::from_raw_parts(context, payload_bytes);
+                    if let Some((&b'\0', without_nul)) = context.split_last() {
+                        context = without_nul;
+                    }
+                    AncillaryItem::SecurityContext(context)
+                }
+                #[cfg(any(target_os="linux", target_os="android"))]
+                (SOL_SOCKET, SCM_TIMESTAMPNS) if payload_bytes >= mem::size_of::<timespec>() => {
+                    let time = ptr::read_unaligned(CMSG_DATA(self.next_message) as *const timespec);
+                    AncillaryItem::Timestamp(system_time(time.tv_sec as i64, time.tv_nsec as u32))
+                }
+                #[cfg(any(target_os="linux", target_os="android"))]
+                (SOL_SOCKET, SCM_PIDFD) if payload_bytes >= mem::size_of::<RawFd>() => {
+                    let fd = ptr::read_unaligned(CMSG_DATA(self.next_message) as *const RawFd);
+                    if fd < 0 {
+                        // closed by peek_ancillary()
+                        self.next_message = CMSG_NXTHDR(&mut self.msg, self.next_message);
+                        return self.next();
+                    }
+                    AncillaryItem::Pidfd(PidFd::from_raw_fd(fd))
+                }
+                (SOL_SOCKET, SCM_TIMESTAMP) if payload_bytes >= mem::size_of::<timeval>() => {
+                    let time = ptr::read_unaligned(CMSG_DATA(self.next_message) as *const timeval);
+                    let nanos = time.tv_usec as u32 * 1000;
+                    AncillaryItem::Timestamp(system_time(time.tv_sec as i64, nanos))
+                }
+                (level, kind) => {
+                    let data = CMSG_DATA(self.next_message) as *const u8;
+                    // don't trust cmsg_len to stay inside the buffer if truncated
+                    let buf_end = self.msg.msg_control as usize + self.msg.msg_controllen as usize;
+                    let available = buf_end.saturating_sub(data as usize);
+                    let data = slice::from_raw_parts(data, payload_bytes.min(available));
+                    AncillaryItem::Unsupported { level,  kind,  data }
+                }
             };
             self.next_message = CMSG_NXTHDR(&mut self.msg, self.next_message);
             Some(item)
@@ -353,6 +595,23 @@ impl<'a> Iterator for Ancillary<'a> {
         None
     }
 }
+/// Converts a timestamp relative to the unix epoch, which might be negative.
+#[cfg(not(any(target_os="illumos", target_os="solaris")))]
+fn system_time(secs: i64,  nanos: u32) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::new(secs as u64, nanos)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.wrapping_neg() as u64) + Duration::new(0, nanos)
+    }
+}
+impl<'a> Debug for Ancillary<'a> {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("Ancillary")
+            .field("message_truncated", &self.message_truncated())
+            .field("ancillary_truncated", &self.ancillary_truncated())
+            .finish()
+    }
+}
 impl<'a> Drop for Ancillary<'a> {
     fn drop(&mut self) {
         // close all remaining file descriptors
@@ -366,6 +625,20 @@ impl<'a> Drop for Ancillary<'a> {
     }
 }
 impl<'a> Ancillary<'a> {
+    /// Wraps a `msghdr` filled in by `recvmsg()` or `recvmmsg()`.
+    ///
+    /// # Safety
+    ///
+    /// `msg.msg_control` must point to a buffer that lives for `'a`,
+    /// and `msg_controllen` must have been set by the kernel.
+    pub(crate) unsafe fn from_received(msg: msghdr) -> Self {
+        Ancillary {
+            msg,
+            _ancillary_buf: PhantomData,
+            #[cfg(not(any(target_os="illumos", target_os="solaris")))]
+            next_message: CMSG_FIRSTHDR(&msg),
+        }
+    }
     /// Returns `true` if the non-ancillary part of the datagram or packet was truncated.
     ///
     /// If the provided byte buffer(s) are shorter than the datagram or packet
@@ -376,14 +649,210 @@ impl<'a> Ancillary<'a> {
         self.msg.msg_flags & MSG_TRUNC != 0
     }
     /// Returns `true` if ancillary messages were dropped due to a too short ancillary buffer.
-    #[allow(unused)] // type is not yet exposed
     pub fn ancillary_truncated(&self) -> bool {
         self.msg.msg_flags & MSG_CTRUNC != 0
     }
 }
 
+/// What was received by `recv_fds()` and `recv_owned_fds()`.
+#[derive(Clone,Copy, Default, PartialEq,Eq, Debug)]
+pub struct RecvFdsInfo {
+    /// The number of bytes received.
+    pub bytes: usize,
+    /// Whether the packet or datagram was truncated due to a too short buffer.
+    ///
+    /// This is not meaningful for streams.
+    pub message_truncated: bool,
+    /// The number of file descriptors that were stored.
+    pub fds: usize,
+    /// The number of received file descriptors that didn't fit
+    /// and were closed.
+    pub fds_discarded: usize,
+    /// Whether the kernel dropped ancillary data,
+    /// which happens when there was no room for it or when it
+    /// couldn't create the file descriptors (for example due to `RLIMIT_NOFILE`).
+    ///
+    /// The number of file descriptors lost that way is unknown.
+    pub ancillary_truncated: bool,
+}
+impl RecvFdsInfo {
+    /// Returns `true` if any file descriptors sent were not stored.
+    pub fn fds_lost(&self) -> bool {
+        self.fds_discarded != 0  ||  self.ancillary_truncated
+    }
+}
+
+/// File descriptors received with `recv_owned_fds()`, which are closed
+/// when dropped.
+///
+/// The capacity limits how many file descriptors will be received:
+/// Receiving appends to the file descriptors already stored,
+/// and any file descriptors beyond the capacity are closed.
+///
+/// The stored file descriptors can be accessed through `Deref`, or
+/// taken out as a `Vec<OwnedFd>`.
+///
+/// In [strict mode](#method.set_strict), receiving fails instead if any
+/// file descriptors would be lost.
+///
+/// Received file descriptors have close-on-exec set, unless
+/// [`set_inheritable()`](#method.set_inheritable) has been enabled.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::{UnixSeqpacketConn, ancillary::ReceivedFds};
+/// use std::os::unix::io::AsFd;
+///
+/// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+/// a.send_borrowed_fds(b"two", &[a.as_fd(), b.as_fd()]).unwrap();
+/// let mut fds = ReceivedFds::with_capacity(1);
+/// let mut buf = [0u8; 10];
+/// let info = b.recv_owned_fds(&mut buf, &mut fds).unwrap();
+/// assert_eq!(&buf[..info.bytes], b"two");
+/// assert!(!info.message_truncated);
+/// assert_eq!(fds.len(), 1);
+/// assert_eq!(info.fds_discarded, 1); // the second one has been closed
+/// let fds = fds.into_vec();
+/// ```
+#[derive(Debug)]
+pub struct ReceivedFds {
+    fds: Vec<OwnedFd>,
+    capacity: usize,
+    strict: bool,
+    inheritable: bool,
+}
+impl ReceivedFds {
+    /// Creates a container that can receive up to `capacity` file descriptors.
+    pub fn with_capacity(capacity: usize) -> Self {
+        ReceivedFds {
+            fds: Vec::with_capacity(capacity),
+            capacity,
+            strict: false,
+            inheritable: false,
+        }
+    }
+    /// The maximum number of file descriptors this container will hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Enables or disables strict mode, where receiving fails with
+    /// `ErrorKind::InvalidData` if any file descriptors were discarded
+    /// or the kernel truncated the ancillary data.
+    ///
+    /// The message is still consumed, and the file descriptors received with
+    /// it are closed instead of stored, so that a partially received message
+    /// can be rejected.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::{UnixSeqpacketConn, ancillary::ReceivedFds};
+    /// use std::io::ErrorKind;
+    /// use std::os::unix::io::AsFd;
+    ///
+    /// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    /// a.send_borrowed_fds(b"three", &[a.as_fd(), a.as_fd(), a.as_fd()]).unwrap();
+    /// let mut fds = ReceivedFds::with_capacity(2);
+    /// fds.set_strict(true);
+    /// let error = b.recv_owned_fds(&mut[0; 10], &mut fds).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::InvalidData);
+    /// assert!(fds.is_empty());
+    /// ```
+    pub fn set_strict(&mut self,  strict: bool) {
+        self.strict = strict;
+    }
+    /// Returns whether strict mode is enabled.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+    /// Enables or disables leaving close-on-exec unset on file descriptors
+    /// received into this container, so that they are inherited by
+    /// programs started with `exec()`.
+    ///
+    /// Close-on-exec is still set atomically while receiving (where supported)
+    /// and then cleared, so file descriptors that are discarded or received
+    /// through other means can't leak into concurrently started programs.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::{UnixSeqpacketConn, ancillary::ReceivedFds};
+    /// use std::os::unix::io::{AsFd, AsRawFd};
+    ///
+    /// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    /// a.send_borrowed_fds(b"for a child", &[a.as_fd()]).unwrap();
+    /// let mut fds = ReceivedFds::with_capacity(1);
+    /// fds.set_inheritable(true);
+    /// b.recv_owned_fds(&mut[0; 16], &mut fds).unwrap();
+    /// let flags = unsafe { libc::fcntl(fds[0].as_raw_fd(), libc::F_GETFD) };
+    /// assert_eq!(flags & libc::FD_CLOEXEC, 0);
+    /// ```
+    pub fn set_inheritable(&mut self,  inheritable: bool) {
+        self.inheritable = inheritable;
+    }
+    /// Returns whether received file descriptors are left without close-on-exec.
+    pub fn is_inheritable(&self) -> bool {
+        self.inheritable
+    }
+    /// Removes and returns the stored file descriptors,
+    /// keeping the capacity so the container can be reused.
+    pub fn take(&mut self) -> Vec<OwnedFd> {
+        mem::replace(&mut self.fds, Vec::with_capacity(self.capacity))
+    }
+    /// Closes all the stored file descriptors.
+    pub fn clear(&mut self) {
+        self.fds.clear();
+    }
+    /// Returns the stored file descriptors.
+    pub fn into_vec(self) -> Vec<OwnedFd> {
+        self.fds
+    }
+
+    /// Stores the file descriptor if there is room for it, and closes it otherwise.
+    /// Close-on-exec is not changed.
+    pub(crate) fn push(&mut self,  fd: OwnedFd) -> bool {
+        if self.fds.len() < self.capacity {
+            self.fds.push(fd);
+            true
+        } else {
+            false
+        }
+    }
+    /// Closes the file descriptors received after the first `len`.
+    pub(crate) fn truncate(&mut self,  len: usize) {
+        self.fds.truncate(len);
+    }
+}
+impl Deref for ReceivedFds {
+    type Target = [OwnedFd];
+    fn deref(&self) -> &[OwnedFd] {
+        &self.fds
+    }
+}
+impl IntoIterator for ReceivedFds {
+    type Item = OwnedFd;
+    type IntoIter = std::vec::IntoIter<OwnedFd>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.fds.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a ReceivedFds {
+    type Item = &'a OwnedFd;
+    type IntoIter = slice::Iter<'a, OwnedFd>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.fds.iter()
+    }
+}
+
 /// A safe (but incomplete) wrapper around `recvmsg()`.
-pub fn recv_ancillary<'ancillary_buf>(
+#[allow(clippy::unnecessary_cast, clippy::useless_conversion)] // ControlLen is usize on some OSes
+#[allow(clippy::manual_is_multiple_of)] // is_multiple_of() requires Rust 1.87
+#[allow(clippy::len_zero)]
+pub(crate) fn recv_ancillary<'ancillary_buf>(
     socket: RawFd,  from: Option<&mut UnixSocketAddr>,  mut flags: c_int,
     bufs: &mut[IoSliceMut],  ancillary_buf: &'ancillary_buf mut[u8],
 ) -> Result<(usize, Ancillary<'ancillary_buf>), io::Error> {
@@ -413,7 +882,7 @@ pub fn recv_ancillary<'ancillary_buf>(
                 let msg = "ancillary buffer is not properly aligned";
                 return Err(io::Error::new(ErrorKind::InvalidInput, msg));
             }
-            if ancillary_buf.len() > ControlLen::max_value() as usize {
+            if ancillary_buf.len() > ControlLen::MAX as usize {
                 let msg = "ancillary buffer is too big";
                 return Err(io::Error::new(ErrorKind::InvalidInput, msg));
             }
@@ -440,36 +909,349 @@ pub fn recv_ancillary<'ancillary_buf>(
             None => cvt_r!(recvmsg(socket, &mut msg, flags))? as usize
         };
 
-        let ancillary_iterator = Ancillary {
-            msg,
-            _ancillary_buf: PhantomData,
-            #[cfg(not(any(target_os="illumos", target_os="solaris")))]
-            next_message: CMSG_FIRSTHDR(&msg),
-        };
-        Ok((received, ancillary_iterator))
+        Ok((received, Ancillary::from_received(msg)))
     }
 }
 
-pub fn recv_fds(
+pub(crate) fn recv_fds(
         fd: RawFd,  from: Option<&mut UnixSocketAddr>,
         bufs: &mut[IoSliceMut],  fd_buf: &mut[RawFd]
-) -> Result<(usize, bool, usize), io::Error> {
+) -> Result<RecvFdsInfo, io::Error> {
+    recv_fds_with_flags(fd, from, 0, bufs, fd_buf)
+}
+
+pub(crate) fn recv_fds_with_flags(
+        fd: RawFd,  from: Option<&mut UnixSocketAddr>,  flags: c_int,
+        bufs: &mut[IoSliceMut],  fd_buf: &mut[RawFd]
+) -> Result<RecvFdsInfo, io::Error> {
     let mut ancillary_buf = AncillaryBuf::with_fd_capacity(fd_buf.len());
-    let (num_bytes, mut ancillary) = recv_ancillary(fd, from, 0, bufs, &mut*ancillary_buf)?;
-    let mut num_fds = 0;
+    let (num_bytes, ancillary) = recv_ancillary(fd, from, flags, bufs, &mut ancillary_buf)?;
+    Ok(store_fds(num_bytes, ancillary, fd_buf))
+}
+
+/// Copies received file descriptors into `fd_buf`, and closes the ones
+/// that don't fit.
+pub(crate) fn store_fds(num_bytes: usize,  mut ancillary: Ancillary,  fd_buf: &mut[RawFd])
+-> RecvFdsInfo {
+    let mut info = RecvFdsInfo { bytes: num_bytes,  ..RecvFdsInfo::default() };
     for message in &mut ancillary {
         if let AncillaryItem::Fds(fds) = message {
             // Due to alignment of cmsg_len in glibc the minimum payload
             // capacity is on Linux (and probably Android) 8 bytes,
             // which means we might receive two file descriptors even though
             // we only want one.
-            let can_keep = fds.len().min(fd_buf.len()-num_fds);
-            fd_buf[num_fds..num_fds+can_keep].copy_from_slice(&fds[..can_keep]);
-            num_fds += can_keep;
+            let can_keep = fds.len().min(fd_buf.len()-info.fds);
+            fd_buf[info.fds..info.fds+can_keep].copy_from_slice(&fds[..can_keep]);
+            info.fds += can_keep;
             for &unwanted in &fds[can_keep..] {
                 unsafe { close(unwanted) };
             }
+            info.fds_discarded += fds.len() - can_keep;
         }
     }
-    Ok((num_bytes, ancillary.message_truncated(), num_fds))
+    info.message_truncated = ancillary.message_truncated();
+    info.ancillary_truncated = ancillary.ancillary_truncated();
+    info
+}
+
+/// Receives into a `ReceivedFds`, closing file descriptors that don't fit.
+pub(crate) fn recv_owned_fds(
+        fd: RawFd,  from: Option<&mut UnixSocketAddr>,
+        bufs: &mut[IoSliceMut],  fds: &mut ReceivedFds
+) -> Result<RecvFdsInfo, io::Error> {
+    let stored_before = fds.fds.len();
+    let wanted = fds.capacity.saturating_sub(stored_before);
+    let mut ancillary_buf = AncillaryBuf::with_fd_capacity(wanted);
+    let (num_bytes, mut ancillary) = recv_ancillary(fd, from, 0, bufs, &mut ancillary_buf)?;
+    let mut info = RecvFdsInfo { bytes: num_bytes,  ..RecvFdsInfo::default() };
+    for message in &mut ancillary {
+        if let AncillaryItem::Fds(received) = message {
+            for &received in received {
+                // the ones that don't fit are closed when dropped
+                let received = unsafe { OwnedFd::from_raw_fd(received) };
+                if fds.fds.len() < fds.capacity {
+                    fds.fds.push(received);
+                    info.fds += 1;
+                } else {
+                    info.fds_discarded += 1;
+                }
+            }
+        }
+    }
+    info.message_truncated = ancillary.message_truncated();
+    info.ancillary_truncated = ancillary.ancillary_truncated();
+    if fds.inheritable {
+        for fd in &fds.fds[stored_before..] {
+            if let Err(e) = set_cloexec(fd.as_raw_fd(), false) {
+                fds.fds.truncate(stored_before);
+                return Err(e);
+            }
+        }
+    }
+    if fds.strict  &&  info.fds_lost() {
+        fds.fds.truncate(stored_before);
+        let msg = if info.ancillary_truncated {
+            "ancillary data was truncated, so file descriptors might have been lost"
+        } else {
+            "received more file descriptors than there was room for"
+        };
+        return Err(io::Error::new(ErrorKind::InvalidData, msg));
+    }
+    Ok(info)
+}
+
+/// `send_ancillary()` with file descriptors that are borrowed for the
+/// duration of the call.
+pub(crate) fn send_borrowed_fds<F: AsFd>(
+    socket: RawFd,  to: Option<&UnixSocketAddr>,  flags: c_int,
+    bytes: &[IoSlice],  fds: &[F]
+) -> Result<usize, io::Error> {
+    let fds = fds.iter().map(|fd| fd.as_fd().as_raw_fd() ).collect::<Vec<RawFd>>();
+    send_ancillary(socket, to, flags, bytes, &fds, None)
+}
+
+/// Sends all the bytes, with the file descriptors attached to the first one.
+///
+/// Only the first `sendmsg()` carries the file descriptors,
+/// and the rest is sent after partial writes without them.
+pub(crate) fn write_all_with_fds(socket: RawFd,  flags: c_int,  bytes: &[u8],  fds: &[RawFd])
+-> Result<(), io::Error> {
+    if bytes.is_empty()  &&  !fds.is_empty() {
+        let msg = "file descriptors must be sent with at least one byte";
+        return Err(io::Error::new(ErrorKind::InvalidInput, msg));
+    }
+    let mut sent = 0;
+    let mut fds = fds;
+    while sent < bytes.len() {
+        match send_ancillary(socket, None, flags, &[IoSlice::new(&bytes[sent..])], fds, None) {
+            Ok(0) => return Err(io::Error::new(ErrorKind::WriteZero, "failed to write whole buffer")),
+            Ok(n) => {
+                sent += n;
+                fds = &[];
+            }
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Fills `buf`, and stores the file descriptors received with any part of it.
+///
+/// If an error occurs, the file descriptors received by earlier reads are
+/// closed too.
+pub(crate) fn read_exact_with_fds(socket: RawFd,  buf: &mut[u8],  fds: &mut ReceivedFds)
+-> Result<(), io::Error> {
+    let stored_before = fds.fds.len();
+    let mut filled = 0;
+    while filled < buf.len() {
+        let result = recv_owned_fds(socket, None, &mut[IoSliceMut::new(&mut buf[filled..])], fds);
+        match result {
+            Ok(info) if info.bytes == 0 => {
+                fds.fds.truncate(stored_before);
+                let msg = "failed to fill whole buffer";
+                return Err(io::Error::new(ErrorKind::UnexpectedEof, msg));
+            }
+            Ok(info) => filled += info.bytes,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => {
+                fds.fds.truncate(stored_before);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `recv_ancillary()` with `MSG_PEEK`.
+///
+/// Peeking duplicates any file descriptors, which are closed and replaced
+/// by `-1` so that they don't leak and can't be mistaken for open ones.
+/// Pidfds are closed too, and are then skipped by the returned iterator.
+pub(crate) fn peek_ancillary<'ancillary_buf>(
+    socket: RawFd,  from: Option<&mut UnixSocketAddr>,
+    bufs: &mut[IoSliceMut],  ancillary_buf: &'ancillary_buf mut[u8],
+) -> Result<(usize, Ancillary<'ancillary_buf>), io::Error> {
+    let (num_bytes, mut ancillary) = recv_ancillary(socket, from, MSG_PEEK, bufs, ancillary_buf)?;
+    let msg = ancillary.msg;
+    let control = msg.msg_control as *mut u8;
+    loop {
+        #[cfg(any(target_os="linux", target_os="android"))]
+        let header = ancillary.next_message;
+        match ancillary.next() {
+            None => break,
+            Some(AncillaryItem::Fds(fds)) => {
+                for &fd in fds {
+                    unsafe { close(fd) };
+                }
+                // write through the control buffer pointer, which was created from
+                // a mutable reference, instead of the shared slice
+                let offset = fds.as_ptr() as usize - control as usize;
+                unsafe {
+                    let first = control.add(offset) as *mut RawFd;
+                    for i in 0..fds.len() {
+                        first.add(i).write_unaligned(-1);
+                    }
+                }
+            }
+            #[cfg(any(target_os="linux", target_os="android"))]
+            Some(AncillaryItem::Pidfd(pidfd)) => {
+                drop(pidfd);
+                unsafe { (CMSG_DATA(header) as *mut RawFd).write_unaligned(-1) };
+            }
+            Some(_) => {}
+        }
+    }
+    Ok((num_bytes, unsafe { Ancillary::from_received(msg) }))
+}
+
+/// Gets the length of the next packet or datagram without receiving it.
+///
+/// Linux returns the full length when `MSG_TRUNC` is passed,
+/// but elsewhere it's necessary to peek with bigger and bigger buffers.
+pub(crate) fn peek_len(socket: RawFd) -> Result<usize, io::Error> {
+    #[cfg(any(target_os="linux", target_os="android"))] {
+        let flags = MSG_PEEK | MSG_TRUNC | MSG_NOSIGNAL;
+        let len = cvt_r!(unsafe { recv(socket, ptr::null_mut(), 0, flags) })?;
+        Ok(len as usize)
+    }
+    #[cfg(not(any(target_os="linux", target_os="android")))] {
+        let mut buf = vec![0; 256];
+        loop {
+            let bufs = &mut[IoSliceMut::new(&mut buf)];
+            let (len, ancillary) = recv_ancillary(socket, None, MSG_PEEK, bufs, &mut[])?;
+            if !ancillary.message_truncated() {
+                return Ok(len);
+            }
+            let new_len = buf.len() * 2;
+            buf.resize(new_len, 0);
+        }
+    }
+}
+
+/// Receives the next packet or datagram into a `Vec` of the right size.
+#[allow(clippy::io_other_error)] // io::Error::other() requires Rust 1.74
+pub(crate) fn recv_to_vec(socket: RawFd) -> Result<Vec<u8>, io::Error> {
+    let mut buf = vec![0; peek_len(socket)?];
+    let bufs = &mut[IoSliceMut::new(&mut buf)];
+    let (len, ancillary) = recv_ancillary(socket, None, 0, bufs, &mut[])?;
+    if ancillary.message_truncated() {
+        // another thread received the peeked packet
+        let msg = "packet became bigger between peeking and receiving it";
+        return Err(io::Error::new(ErrorKind::Other, msg));
+    }
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// Space needed for credentials and timestamps, which are put before
+/// security contexts and file descriptors.
+fn small_messages_space() -> usize {
+    #[cfg(not(any(target_os="illumos", target_os="solaris")))]
+    unsafe {
+        #[cfg(any(target_os="linux", target_os="android"))]
+        let creds_space = CMSG_SPACE(mem::size_of::<RawReceivedCredentials>() as c_uint);
+        #[cfg(not(any(target_os="linux", target_os="android")))]
+        let creds_space = 0;
+        #[cfg(any(target_os="linux", target_os="android"))]
+        let timestamp_space = CMSG_SPACE(mem::size_of::<timespec>() as c_uint);
+        #[cfg(not(any(target_os="linux", target_os="android")))]
+        let timestamp_space = CMSG_SPACE(mem::size_of::<timeval>() as c_uint);
+        creds_space as usize + timestamp_space as usize
+    }
+    #[cfg(any(target_os="illumos", target_os="solaris"))] {
+        0
+    }
+}
+
+/// Receives bytes with an ancillary buffer of `extra_capacity` bytes
+/// in addition to what's needed for credentials and timestamps,
+/// and passes all received messages except file descriptors to `on_message`.
+///
+/// Any received file descriptors are closed.
+/// The returned `bool`s are whether the message and the ancillary data were truncated.
+fn recv_closing_fds<F: FnMut(AncillaryItem)>(
+        fd: RawFd,  from: Option<&mut UnixSocketAddr>,  bufs: &mut[IoSliceMut],
+        extra_capacity: usize,  mut on_message: F,
+) -> Result<(usize, bool, bool), io::Error> {
+    let mut ancillary_buf = AncillaryBuf::with_capacity(small_messages_space() + extra_capacity);
+    let (num_bytes, mut ancillary) = recv_ancillary(fd, from, 0, bufs, &mut ancillary_buf)?;
+    for message in &mut ancillary {
+        match message {
+            AncillaryItem::Fds(fds) => {
+                for &unwanted in fds {
+                    unsafe { close(unwanted) };
+                }
+            }
+            other => on_message(other),
+        }
+    }
+    Ok((num_bytes, ancillary.message_truncated(), ancillary.ancillary_truncated()))
+}
+
+/// Receives bytes and the credentials sent with them, closing any received file descriptors.
+#[cfg(any(target_os="linux", target_os="android"))]
+pub(crate) fn recv_credentials(
+        fd: RawFd,  from: Option<&mut UnixSocketAddr>,  bufs: &mut[IoSliceMut]
+) -> Result<(usize, bool, Option<ReceivedCredentials>), io::Error> {
+    let mut creds = None;
+    let (num_bytes, truncated, _) = recv_closing_fds(fd, from, bufs, 0, |message| {
+        if let AncillaryItem::Credentials(received) = message {
+            creds = Some(received);
+        }
+    })?;
+    Ok((num_bytes, truncated, creds))
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+pub(crate) fn recv_credentials(
+        _fd: RawFd,  _from: Option<&mut UnixSocketAddr>,  _bufs: &mut[IoSliceMut]
+) -> Result<(usize, bool, Option<ReceivedCredentials>), io::Error> {
+    Err(io::Error::new(ErrorKind::Unsupported, "not available"))
+}
+
+/// Receives bytes and the security context sent with them, closing any received file descriptors.
+///
+/// Fails with `InvalidInput` if the context didn't fit in `context_buf`.
+#[cfg(any(target_os="linux", target_os="android"))]
+pub(crate) fn recv_security_context(
+        fd: RawFd,  from: Option<&mut UnixSocketAddr>,
+        bufs: &mut[IoSliceMut],  context_buf: &mut[u8],
+) -> Result<(usize, bool, usize), io::Error> {
+    let context_space = unsafe { CMSG_SPACE(context_buf.len().min(0xff_ff) as c_uint) };
+    let mut context_len = 0;
+    let mut context_too_long = false;
+    let (num_bytes, truncated, ancillary_truncated)
+        = recv_closing_fds(fd, from, bufs, context_space as usize, |message| {
+            if let AncillaryItem::SecurityContext(context) = message {
+                context_len = context.len().min(context_buf.len());
+                context_buf[..context_len].copy_from_slice(&context[..context_len]);
+                context_too_long = context.len() > context_buf.len();
+            }
+        })?;
+    if ancillary_truncated || context_too_long {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "security context buffer is too small"));
+    }
+    Ok((num_bytes, truncated, context_len))
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+pub(crate) fn recv_security_context(
+        _fd: RawFd,  _from: Option<&mut UnixSocketAddr>,
+        _bufs: &mut[IoSliceMut],  _context_buf: &mut[u8],
+) -> Result<(usize, bool, usize), io::Error> {
+    Err(io::Error::new(ErrorKind::Unsupported, "not available"))
+}
+
+/// Receives bytes and the time they were received by the kernel, closing any received file descriptors.
+pub(crate) fn recv_timestamp(
+        fd: RawFd,  from: Option<&mut UnixSocketAddr>,  bufs: &mut[IoSliceMut]
+) -> Result<(usize, bool, Option<SystemTime>), io::Error> {
+    let mut timestamp = None;
+    let (num_bytes, truncated, _) = recv_closing_fds(fd, from, bufs, 0, |message| {
+        if let AncillaryItem::Timestamp(received) = message {
+            timestamp = Some(received);
+        }
+    })?;
+    Ok((num_bytes, truncated, timestamp))
 }
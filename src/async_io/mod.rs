@@ -0,0 +1,8 @@
+//! Provides support for `SEQPACKET` sockets with [async-io](https://docs.rs/async-io),
+//! and thereby with executors such as smol and async-std.
+//!
+//! Like the types in [`uds::tokio1`](../tokio1/index.html), these types take `&self`
+//! in all methods.
+
+mod seqpacket;
+pub use seqpacket::*;
@@ -0,0 +1,459 @@
+use crate::{nonblocking, UnixSocketAddr, ConnCredentials, PidFd};
+use crate::ancillary::{SendAncillary, ReceivedFds, RecvFdsInfo};
+use crate::batch::{SendMessage, RecvMessage};
+use std::io::{self, ErrorKind, IoSlice, IoSliceMut};
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd, AsFd, BorrowedFd};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use async_io_2::Async;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::ready;
+
+/// An I/O object representing a Unix Sequenced-packet socket.
+///
+/// It implements `AsyncRead` and `AsyncWrite` from `futures::io`,
+/// where each write sends one packet, and each read receives one packet.
+/// Reads fail with `InvalidData` if the next packet doesn't fit in the buffer,
+/// and leave the packet in the queue so that it can be read with a bigger one.
+/// An empty packet is indistinguishable from end-of-file.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::async_io::UnixSeqpacketConn;
+/// use futures::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// async_io_2::block_on(async {
+///     let (mut a, mut b) = UnixSeqpacketConn::pair().unwrap();
+///     a.send(b"hello").await.unwrap();
+///     a.write_all(b"world").await.unwrap();
+///     let mut buf = [0; 10];
+///     let len = b.recv(&mut buf).await.unwrap();
+///     assert_eq!(&buf[..len], b"hello");
+///     let len = b.read(&mut buf).await.unwrap();
+///     assert_eq!(&buf[..len], b"world");
+/// });
+/// ```
+#[derive(Debug)]
+pub struct UnixSeqpacketConn {
+    io: Async<nonblocking::UnixSeqpacketConn>,
+}
+
+impl UnixSeqpacketConn {
+    /// Connects to the socket named by path.
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let conn = nonblocking::UnixSeqpacketConn::connect(path)?;
+        let conn = Self::from_nonblocking(conn)?;
+        conn.writable().await?;
+        Ok(conn)
+    }
+    /// Connects to an unix seqpacket server listening at `addr`.
+    pub async fn connect_addr(addr: &UnixSocketAddr) -> io::Result<Self> {
+        let conn = nonblocking::UnixSeqpacketConn::connect_unix_addr(addr)?;
+        let conn = Self::from_nonblocking(conn)?;
+        conn.writable().await?;
+        Ok(conn)
+    }
+    /// Binds to an address before connecting to a listening seqpacet socket.
+    pub async fn connect_from_addr(from: &UnixSocketAddr,  to: &UnixSocketAddr)
+    -> io::Result<Self> {
+        let conn = nonblocking::UnixSeqpacketConn::connect_from_to_unix_addr(from, to)?;
+        let conn = Self::from_nonblocking(conn)?;
+        conn.writable().await?;
+        Ok(conn)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> Result<(UnixSeqpacketConn, UnixSeqpacketConn), io::Error> {
+        let (a, b) = nonblocking::UnixSeqpacketConn::pair()?;
+        Ok((Self::from_nonblocking(a)?, Self::from_nonblocking(b)?))
+    }
+
+    /// Registers an existing nonblocking socket with the async-io reactor.
+    pub fn from_nonblocking(conn: nonblocking::UnixSeqpacketConn) -> Result<Self, io::Error> {
+        Ok(UnixSeqpacketConn { io: Async::new(conn)? })
+    }
+    /// Deregisters the connection and returns the underlying non-blocking type.
+    pub fn into_nonblocking(self) -> Result<nonblocking::UnixSeqpacketConn, io::Error> {
+        self.io.into_inner()
+    }
+    /// Creates an async-io-compatible socket from a raw file descriptor.
+    ///
+    /// This function is provided instead of implementing [`FromRawFd`](std::os::unix::io::FromRawFd)
+    /// because registering with the reactor might fail.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor must represent a connected seqpacket socket.
+    pub unsafe fn from_raw_fd(fd: RawFd) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketConn::from_raw_fd(fd))
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self,  how: Shutdown) -> Result<(), io::Error> {
+        self.io.get_ref().shutdown(how)
+    }
+
+    /// Returns the address of this side of the connection.
+    pub fn local_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        self.io.get_ref().local_unix_addr()
+    }
+    /// Returns the address of the other side of the connection.
+    pub fn peer_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        self.io.get_ref().peer_unix_addr()
+    }
+
+    /// Returns information about the process of the peer when the connection was established.
+    ///
+    /// See documentation of the returned type for details.
+    pub fn initial_peer_credentials(&self) -> Result<ConnCredentials, io::Error> {
+        self.io.get_ref().initial_peer_credentials()
+    }
+    /// Returns a pidfd referring to the process that created the other end
+    /// of this connection.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn initial_peer_pidfd(&self) -> Result<PidFd, io::Error> {
+        self.io.get_ref().initial_peer_pidfd()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> Result<Option<io::Error>, io::Error> {
+        self.io.get_ref().take_error()
+    }
+
+    /// Sets the size of the send buffer.
+    pub fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        self.io.get_ref().set_send_buffer_size(size)
+    }
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().send_buffer_size()
+    }
+    /// Sets the size of the receive buffer.
+    pub fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        self.io.get_ref().set_recv_buffer_size(size)
+    }
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().recv_buffer_size()
+    }
+    /// Returns the number of bytes in packets that have been received
+    /// but not read yet.
+    pub fn pending_incoming(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().pending_incoming()
+    }
+    /// Returns the number of bytes sent but not yet read by the peer.
+    ///
+    /// Only available on Linux, Android, FreeBSD and macOS.
+    pub fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().pending_outgoing()
+    }
+}
+
+impl UnixSeqpacketConn {
+    /// Waits for the socket to become readable.
+    pub async fn readable(&self) -> io::Result<()> {
+        self.io.readable().await
+    }
+    /// Waits for the socket to become writable.
+    pub async fn writable(&self) -> io::Result<()> {
+        self.io.writable().await
+    }
+
+    /// Sends a packet to the socket's peer.
+    pub async fn send(&self,  packet: &[u8]) -> io::Result<usize> {
+        self.io.write_with(|conn| conn.send(packet) ).await
+    }
+    /// Receives a packet from the socket's peer.
+    pub async fn recv(&self,  buffer: &mut[u8]) -> io::Result<usize> {
+        self.io.read_with(|conn| conn.recv(buffer) ).await
+    }
+
+    /// Sends a packet assembled from multiple byte slices.
+    pub async fn send_vectored(&self,  slices: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.io.write_with(|conn| conn.send_vectored(slices) ).await
+    }
+    /// Receives a packet and places the bytes across multiple buffers.
+    ///
+    /// Returns the number of bytes received and whether the packet was truncated.
+    pub async fn recv_vectored(&self,  buffers: &mut[IoSliceMut<'_>])
+    -> io::Result<(usize, bool)> {
+        self.io.read_with(|conn| conn.recv_vectored(buffers) ).await
+    }
+
+    /// Receives a packet without removing it from the incoming queue.
+    pub async fn peek(&self,  buffer: &mut[u8]) -> io::Result<usize> {
+        self.io.read_with(|conn| conn.peek(buffer) ).await
+    }
+    /// Returns the length of the next packet without receiving it.
+    pub async fn peek_len(&self) -> io::Result<usize> {
+        self.io.read_with(|conn| conn.peek_len() ).await
+    }
+    /// Receives a packet into a `Vec` that is exactly as big as the packet.
+    pub async fn recv_to_vec(&self) -> io::Result<Vec<u8>> {
+        self.io.read_with(|conn| conn.recv_to_vec() ).await
+    }
+
+    /// Sends a packet with associated file descriptors.
+    pub async fn send_fds(&self,  bytes: &[u8],  fds: &[RawFd]) -> io::Result<usize> {
+        self.io.write_with(|conn| conn.send_fds(bytes, fds) ).await
+    }
+    /// Receives a packet and associated file descriptors.
+    pub async fn recv_fds(&self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
+    -> io::Result<RecvFdsInfo> {
+        self.io.read_with(|conn| conn.recv_fds(byte_buffer, fd_buffer) ).await
+    }
+    /// Sends a packet assembled from multiple byte slices,
+    /// together with the ancillary messages in `ancillary`.
+    pub async fn send_ancillary(&self,  slices: &[IoSlice<'_>],  ancillary: &SendAncillary)
+    -> io::Result<usize> {
+        self.io.write_with(|conn| conn.send_ancillary(slices, ancillary) ).await
+    }
+    /// Sends a packet with file descriptors that are borrowed for the
+    /// duration of the call.
+    pub async fn send_borrowed_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F])
+    -> io::Result<usize> {
+        self.io.write_with(|conn| conn.send_borrowed_fds(bytes, fds) ).await
+    }
+    /// Receives a packet and takes ownership of the associated file descriptors.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    pub async fn recv_owned_fds(&self,  byte_buffer: &mut[u8],  fds: &mut ReceivedFds)
+    -> io::Result<RecvFdsInfo> {
+        self.io.read_with(|conn| conn.recv_owned_fds(byte_buffer, fds) ).await
+    }
+    /// Sends multiple packets with one system call where supported,
+    /// and returns how many were sent.
+    pub async fn send_batch(&self,  messages: &[SendMessage<'_>]) -> io::Result<usize> {
+        self.io.write_with(|conn| conn.send_batch(messages) ).await
+    }
+    /// Receives the packets that are available with one system call where
+    /// supported, and returns how many were received.
+    pub async fn recv_batch(&self,  messages: &mut[RecvMessage<'_>]) -> io::Result<usize> {
+        self.io.read_with(|conn| conn.recv_batch(messages) ).await
+    }
+
+    fn poll_send_priv<R>(&self,
+            cx: &mut Context<'_>,
+            mut send_op: impl FnMut(&nonblocking::UnixSeqpacketConn) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        loop {
+            match send_op(self.io.get_ref()) {
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                result => return Poll::Ready(result),
+            }
+            ready!(self.io.poll_writable(cx))?;
+        }
+    }
+
+    fn poll_recv_priv<R>(&self,
+            cx: &mut Context<'_>,
+            mut recv_op: impl FnMut(&nonblocking::UnixSeqpacketConn) -> io::Result<R>,
+    ) -> Poll<io::Result<R>> {
+        loop {
+            match recv_op(self.io.get_ref()) {
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                result => return Poll::Ready(result),
+            }
+            ready!(self.io.poll_readable(cx))?;
+        }
+    }
+}
+
+fn fail_if_truncated((received, truncated): (usize, bool)) -> io::Result<usize> {
+    if truncated {
+        Err(io::Error::new(ErrorKind::InvalidData, "packet was too big for the buffer"))
+    } else {
+        Ok(received)
+    }
+}
+
+/// Receives a packet only if it fits in the buffers, so that it's not lost.
+///
+/// Another thread could receive the peeked packet first,
+/// so a truncated packet is still possible.
+fn recv_if_fits(conn: &nonblocking::UnixSeqpacketConn,  bufs: &mut[IoSliceMut])
+-> io::Result<usize> {
+    let capacity = bufs.iter().map(|buf| buf.len() ).sum::<usize>();
+    if conn.peek_len()? > capacity {
+        return Err(io::Error::new(ErrorKind::InvalidData, "packet is too big for the buffer"));
+    }
+    conn.recv_vectored(bufs).and_then(fail_if_truncated)
+}
+
+impl AsyncRead for &UnixSeqpacketConn {
+    fn poll_read(self: Pin<&mut Self>,  cx: &mut Context<'_>,  buf: &mut[u8])
+    -> Poll<io::Result<usize>> {
+        self.poll_recv_priv(cx, |conn| recv_if_fits(conn, &mut[IoSliceMut::new(buf)]) )
+    }
+    fn poll_read_vectored(self: Pin<&mut Self>,  cx: &mut Context<'_>,  bufs: &mut[IoSliceMut])
+    -> Poll<io::Result<usize>> {
+        self.poll_recv_priv(cx, |conn| recv_if_fits(conn, bufs) )
+    }
+}
+
+impl AsyncWrite for &UnixSeqpacketConn {
+    fn poll_write(self: Pin<&mut Self>,  cx: &mut Context<'_>,  buf: &[u8])
+    -> Poll<io::Result<usize>> {
+        self.poll_send_priv(cx, |conn| conn.send(buf) )
+    }
+    fn poll_write_vectored(self: Pin<&mut Self>,  cx: &mut Context<'_>,  bufs: &[IoSlice])
+    -> Poll<io::Result<usize>> {
+        self.poll_send_priv(cx, |conn| conn.send_vectored(bufs) )
+    }
+    fn poll_flush(self: Pin<&mut Self>,  _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_close(self: Pin<&mut Self>,  _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+impl AsyncRead for UnixSeqpacketConn {
+    fn poll_read(self: Pin<&mut Self>,  cx: &mut Context<'_>,  buf: &mut[u8])
+    -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_read(cx, buf)
+    }
+    fn poll_read_vectored(self: Pin<&mut Self>,  cx: &mut Context<'_>,  bufs: &mut[IoSliceMut])
+    -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_read_vectored(cx, bufs)
+    }
+}
+
+impl AsyncWrite for UnixSeqpacketConn {
+    fn poll_write(self: Pin<&mut Self>,  cx: &mut Context<'_>,  buf: &[u8])
+    -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_write(cx, buf)
+    }
+    fn poll_write_vectored(self: Pin<&mut Self>,  cx: &mut Context<'_>,  bufs: &[IoSlice])
+    -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_write_vectored(cx, bufs)
+    }
+    fn poll_flush(self: Pin<&mut Self>,  cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>,  cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_close(cx)
+    }
+}
+
+impl AsRef<nonblocking::UnixSeqpacketConn> for UnixSeqpacketConn {
+    fn as_ref(&self) -> &nonblocking::UnixSeqpacketConn {
+        self.io.get_ref()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketConn {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl AsFd for UnixSeqpacketConn {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.get_ref().as_fd()
+    }
+}
+
+
+
+/// An I/O object representing a Unix Sequenced-packet listener.
+#[derive(Debug)]
+pub struct UnixSeqpacketListener {
+    io: Async<nonblocking::UnixSeqpacketListener>,
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a socket that listens for seqpacket connections on the specified socket file.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketListener::bind(path)?)
+    }
+    /// Creates a socket that listens for seqpacket connections on the specified address.
+    pub fn bind_addr(addr: &UnixSocketAddr) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketListener::bind_unix_addr(addr)?)
+    }
+
+    /// Registers an existing nonblocking listener with the async-io reactor.
+    pub fn from_nonblocking(listener: nonblocking::UnixSeqpacketListener)
+    -> Result<Self, io::Error> {
+        Ok(UnixSeqpacketListener { io: Async::new(listener)? })
+    }
+    /// Deregisters the listener and returns the underlying non-blocking type.
+    pub fn into_nonblocking(self) -> Result<nonblocking::UnixSeqpacketListener, io::Error> {
+        self.io.into_inner()
+    }
+    /// Creates an async-io-compatible listener from a raw file descriptor.
+    ///
+    /// This function is provided instead of implementing [`FromRawFd`](std::os::unix::io::FromRawFd)
+    /// because registering with the reactor might fail.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor must represent a seqpacket listener.
+    pub unsafe fn from_raw_fd(fd: RawFd) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketListener::from_raw_fd(fd))
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::async_io::{UnixSeqpacketConn, UnixSeqpacketListener};
+    ///
+    /// async_io_2::block_on(async {
+    ///     # let _ = std::fs::remove_file("async_io_accept.sock");
+    ///     let listener = UnixSeqpacketListener::bind("async_io_accept.sock").unwrap();
+    ///     let client = UnixSeqpacketConn::connect("async_io_accept.sock").await.unwrap();
+    ///     let (server, _addr) = listener.accept().await.unwrap();
+    ///     # let _ = std::fs::remove_file("async_io_accept.sock");
+    ///     client.send(b"hi").await.unwrap();
+    ///     assert_eq!(server.recv(&mut[0; 10]).await.unwrap(), 2);
+    /// });
+    /// ```
+    pub async fn accept(&self) -> io::Result<(UnixSeqpacketConn, UnixSocketAddr)> {
+        let (conn, addr) = self.io.read_with(|listener| listener.accept_unix_addr() ).await?;
+        Ok((UnixSeqpacketConn::from_nonblocking(conn)?, addr))
+    }
+
+    /// Returns the address the socket is listening on.
+    pub fn local_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        self.io.get_ref().local_unix_addr()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    ///
+    /// This might never produce any errors for listeners. It is therefore
+    /// unlikely to be useful, but is provided for parity with
+    /// `std::unix::net::UnixListener`.
+    pub fn take_error(&self) -> Result<Option<io::Error>, io::Error> {
+        self.io.get_ref().take_error()
+    }
+}
+
+impl AsRef<nonblocking::UnixSeqpacketListener> for UnixSeqpacketListener {
+    fn as_ref(&self) -> &nonblocking::UnixSeqpacketListener {
+        self.io.get_ref()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl AsFd for UnixSeqpacketListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.get_ref().as_fd()
+    }
+}
@@ -0,0 +1,298 @@
+//! Types for sending or receiving multiple datagrams or packets with one call.
+//!
+//! The seqpacket types and `UnixDatagramExt` have `send_batch()` and
+//! `recv_batch()` methods, which use `sendmmsg()` and `recvmmsg()` on
+//! Linux and Android, and fall back to calling `sendmsg()` or `recvmsg()`
+//! in a loop on other operating systems or if the kernel doesn't support them.
+//!
+//! # Examples
+//!
+#![cfg_attr(not(target_vendor="apple"), doc="```")]
+#![cfg_attr(target_vendor="apple", doc="```no_run")]
+//! use uds::UnixSeqpacketConn;
+//! use uds::batch::{SendMessage, RecvMessage};
+//!
+//! let (a, b) = UnixSeqpacketConn::pair().unwrap();
+//! let sent = a.send_batch(&[
+//!     SendMessage::new(b"one"),
+//!     SendMessage::new(b"two"),
+//!     SendMessage::new(b"three"),
+//! ]).unwrap();
+//! assert_eq!(sent, 3);
+//!
+//! let mut bufs = [[0u8; 8]; 4];
+//! let mut messages = bufs.iter_mut()
+//!     .map(|buf| RecvMessage::new(buf) )
+//!     .collect::<Vec<_>>();
+//! let received = b.recv_batch(&mut messages).unwrap();
+//! assert_eq!(received, 3);
+//! assert_eq!(messages[0].data(), b"one");
+//! assert_eq!(messages[2].data(), b"three");
+//! ```
+
+use std::io::{self, IoSlice, IoSliceMut, ErrorKind};
+use std::os::unix::io::RawFd;
+use std::fmt::{self, Debug};
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::{mem, ptr};
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::convert::TryInto;
+
+use libc::{c_int, MSG_DONTWAIT};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{c_uint, c_void, socklen_t, mmsghdr, iovec, sockaddr_un, sendmmsg, recvmmsg};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{MSG_CMSG_CLOEXEC, MSG_WAITFORONE, ENOSYS};
+
+use crate::addr::UnixSocketAddr;
+use crate::ancillary::*;
+use crate::helpers::*;
+
+/// A datagram or packet to send with `send_batch()`.
+#[derive(Clone,Copy, Debug)]
+pub struct SendMessage<'a> {
+    bytes: &'a[u8],
+    fds: &'a[RawFd],
+    to: Option<&'a UnixSocketAddr>,
+}
+impl<'a> SendMessage<'a> {
+    /// Creates a message with the bytes and no file descriptors or address.
+    pub fn new(bytes: &'a[u8]) -> Self {
+        SendMessage { bytes,  fds: &[],  to: None }
+    }
+    /// Sends file descriptors with the message.
+    ///
+    /// The file descriptors are not duplicated or closed.
+    pub fn with_fds(mut self,  fds: &'a[RawFd]) -> Self {
+        self.fds = fds;
+        self
+    }
+    /// Sends the message to an address, for unconnected datagram sockets.
+    pub fn to(mut self,  addr: &'a UnixSocketAddr) -> Self {
+        self.to = Some(addr);
+        self
+    }
+}
+
+/// Buffers for a datagram or packet received with `recv_batch()`,
+/// and what was received into them.
+///
+/// File descriptors received are not closed when this type is dropped
+/// (just like with `recv_fds()`), and are overwritten if the message
+/// is reused for another `recv_batch()`.
+pub struct RecvMessage<'a> {
+    buf: &'a mut[u8],
+    fd_buf: &'a mut[RawFd],
+    info: RecvFdsInfo,
+    addr: UnixSocketAddr,
+}
+impl<'a> Debug for RecvMessage<'a> {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("RecvMessage")
+            .field("data", &self.data())
+            .field("fds", &self.fds())
+            .field("info", &self.info)
+            .field("addr", &self.addr)
+            .finish()
+    }
+}
+impl<'a> RecvMessage<'a> {
+    /// Creates a message that receives bytes into `buf` and no file descriptors.
+    pub fn new(buf: &'a mut[u8]) -> Self {
+        RecvMessage {
+            buf,
+            fd_buf: &mut[],
+            info: RecvFdsInfo::default(),
+            addr: UnixSocketAddr::new_unspecified(),
+        }
+    }
+    /// Receives up to `fd_buf.len()` file descriptors with the message.
+    pub fn with_fds(mut self,  fd_buf: &'a mut[RawFd]) -> Self {
+        self.fd_buf = fd_buf;
+        self
+    }
+    /// The number of bytes and file descriptors received, and what was lost.
+    pub fn info(&self) -> RecvFdsInfo {
+        self.info
+    }
+    /// The received bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.buf[..self.info.bytes.min(self.buf.len())]
+    }
+    /// The received file descriptors, which the caller is responsible for closing.
+    pub fn fds(&self) -> &[RawFd] {
+        &self.fd_buf[..self.info.fds]
+    }
+    /// The address of the sender.
+    ///
+    /// This is only meaningful for datagram sockets.
+    pub fn addr(&self) -> &UnixSocketAddr {
+        &self.addr
+    }
+
+    fn reset(&mut self) {
+        self.info = RecvFdsInfo::default();
+        self.addr = UnixSocketAddr::new_unspecified();
+    }
+}
+
+
+
+/// Safe wrapper around `sendmmsg()`, with fallback to `sendmsg()`.
+pub(crate) fn send_batch(socket: RawFd,  flags: c_int,  messages: &[SendMessage])
+-> Result<usize, io::Error> {
+    if messages.is_empty() {
+        return Ok(0);
+    }
+    #[cfg(any(target_os="linux", target_os="android"))] {
+        match send_mmsg(socket, flags, messages) {
+            Err(ref e) if e.raw_os_error() == Some(ENOSYS) => {}
+            result => return result,
+        }
+    }
+    send_loop(socket, flags, messages)
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[allow(clippy::useless_conversion)] // msg_controllen is usize on some targets
+fn send_mmsg(socket: RawFd,  flags: c_int,  messages: &[SendMessage])
+-> Result<usize, io::Error> {
+    let mut ancillary = Vec::with_capacity(messages.len());
+    for message in messages {
+        let mut encoded = SendAncillary::new();
+        encoded.add_fds(message.fds);
+        ancillary.push(encoded);
+    }
+    let iovecs = messages.iter()
+        .map(|message| IoSlice::new(message.bytes) )
+        .collect::<Vec<IoSlice>>();
+    let mut headers = Vec::with_capacity(messages.len());
+    for ((message, ancillary), iov) in messages.iter().zip(&ancillary).zip(&iovecs) {
+        let control = ancillary.encoded()?;
+        unsafe {
+            let mut header: mmsghdr = mem::zeroed();
+            header.msg_hdr.msg_iov = iov as *const IoSlice as *mut iovec;
+            header.msg_hdr.msg_iovlen = 1;
+            if let Some(addr) = message.to {
+                let (addr, len) = addr.as_raw();
+                header.msg_hdr.msg_name = addr as *const sockaddr_un as *mut c_void;
+                header.msg_hdr.msg_namelen = len;
+            }
+            if !control.is_empty() {
+                // sendmmsg() doesn't modify the buffer
+                header.msg_hdr.msg_control = control.as_ptr() as *mut c_void;
+                header.msg_hdr.msg_controllen = control.len().try_into().unwrap();
+            }
+            headers.push(header);
+        }
+    }
+    let count = headers.len().min(c_uint::MAX as usize) as c_uint;
+    let flags = flags | MSG_NOSIGNAL;
+    let sent = cvt_r!(unsafe { sendmmsg(socket, headers.as_mut_ptr(), count, flags as _) })?;
+    Ok(sent as usize)
+}
+
+fn send_loop(socket: RawFd,  flags: c_int,  messages: &[SendMessage])
+-> Result<usize, io::Error> {
+    for (sent, message) in messages.iter().enumerate() {
+        let bytes = &[IoSlice::new(message.bytes)];
+        if let Err(e) = send_ancillary(socket, message.to, flags, bytes, message.fds, None) {
+            // like sendmmsg(), only report an error if nothing was sent
+            return if sent == 0 {Err(e)} else {Ok(sent)};
+        }
+    }
+    Ok(messages.len())
+}
+
+/// Safe wrapper around `recvmmsg()`, with fallback to `recvmsg()`.
+///
+/// Blocks until at least one message is received, unless the socket is
+/// nonblocking, and then receives the messages that are immediately available.
+pub(crate) fn recv_batch(socket: RawFd,  messages: &mut[RecvMessage])
+-> Result<usize, io::Error> {
+    if messages.is_empty() {
+        return Ok(0);
+    }
+    for message in messages.iter_mut() {
+        message.reset();
+    }
+    #[cfg(any(target_os="linux", target_os="android"))] {
+        match recv_mmsg(socket, messages) {
+            Err(ref e) if e.raw_os_error() == Some(ENOSYS) => {}
+            result => return result,
+        }
+    }
+    recv_loop(socket, messages)
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[allow(clippy::useless_conversion)] // msg_controllen is usize on some targets
+fn recv_mmsg(socket: RawFd,  messages: &mut[RecvMessage]) -> Result<usize, io::Error> {
+    let mut ancillary_bufs = messages.iter()
+        .map(|message| AncillaryBuf::with_fd_capacity(message.fd_buf.len()) )
+        .collect::<Vec<AncillaryBuf>>();
+    let mut iovecs = messages.iter_mut()
+        .map(|message| iovec {
+            iov_base: message.buf.as_mut_ptr() as *mut c_void,
+            iov_len: message.buf.len(),
+        })
+        .collect::<Vec<iovec>>();
+    let mut headers = Vec::with_capacity(messages.len());
+    for ((message, ancillary_buf), iov) in messages.iter_mut()
+            .zip(&mut ancillary_bufs)
+            .zip(&mut iovecs) {
+        unsafe {
+            let mut header: mmsghdr = mem::zeroed();
+            header.msg_hdr.msg_iov = iov;
+            header.msg_hdr.msg_iovlen = 1;
+            let (addr, _) = message.addr.as_raw_mut();
+            header.msg_hdr.msg_name = addr as *mut sockaddr_un as *mut c_void;
+            header.msg_hdr.msg_namelen = mem::size_of::<sockaddr_un>() as socklen_t;
+            if !ancillary_buf.is_empty() {
+                header.msg_hdr.msg_control = ancillary_buf.as_mut_ptr() as *mut c_void;
+                header.msg_hdr.msg_controllen = ancillary_buf.len().try_into().unwrap();
+            }
+            headers.push(header);
+        }
+    }
+    let count = headers.len().min(c_uint::MAX as usize) as c_uint;
+    let flags = MSG_NOSIGNAL | MSG_CMSG_CLOEXEC | MSG_WAITFORONE;
+    let received = cvt_r!(unsafe {
+        recvmmsg(socket, headers.as_mut_ptr(), count, flags as _, ptr::null_mut())
+    })? as usize;
+
+    for (message, header) in messages.iter_mut().zip(&headers).take(received) {
+        let ancillary = unsafe { Ancillary::from_received(header.msg_hdr) };
+        message.info = store_fds(header.msg_len as usize, ancillary, message.fd_buf);
+        let (_, addr_len) = unsafe { message.addr.as_raw_mut() };
+        *addr_len = header.msg_hdr.msg_namelen;
+        if message.addr.normalize_from_ffi().is_err() {
+            // not worth losing the other messages and file descriptors for
+            message.addr = UnixSocketAddr::new_unspecified();
+        }
+    }
+    Ok(received)
+}
+
+fn recv_loop(socket: RawFd,  messages: &mut[RecvMessage]) -> Result<usize, io::Error> {
+    let count = messages.len();
+    for (received, message) in messages.iter_mut().enumerate() {
+        // only block for the first message
+        let flags = if received == 0 {0} else {MSG_DONTWAIT};
+        let result = recv_fds_with_flags(
+            socket,
+            Some(&mut message.addr),
+            flags,
+            &mut[IoSliceMut::new(message.buf)],
+            message.fd_buf,
+        );
+        match result {
+            Ok(info) => message.info = info,
+            Err(ref e) if received != 0 && e.kind() == ErrorKind::WouldBlock => {
+                return Ok(received);
+            }
+            Err(e) => return if received == 0 {Err(e)} else {Ok(received)},
+        }
+    }
+    Ok(count)
+}
@@ -19,11 +19,13 @@ extern crate libc;
 extern crate uds;
 use uds::{UnixListenerExt, UnixStreamExt, UnixDatagramExt, UnixSocketAddr};
 use uds::nonblocking::UnixSeqpacketConn;
+use uds::ancillary::RecvFdsInfo;
 
 fn max_path_len() -> usize {
     unsafe { mem::size_of_val(&mem::zeroed::<libc::sockaddr_un>().sun_path) }
 }
 
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn std_bind_max_len_path() {
     print!("std_bind_max_len_path ");
     let max_len = max_path_len();
@@ -50,6 +52,7 @@ fn max_path_addr(fill: u8) -> (libc::sockaddr_un, libc::socklen_t) {
     }
 }
 
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn std_get_local_max_len_path() {
     print!("std_get_local_max_len_path ");
     let max_len = UnixSocketAddr::max_path_len();
@@ -77,6 +80,7 @@ fn std_get_local_max_len_path() {
     remove_file(&max_path).expect("delete socket file");
 }
 
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn std_reply_max_len_path() {
     print!("std_reply_max_len ");
     let max_path = std::iter::repeat('S').take(max_path_len()).collect::<String>();
@@ -134,6 +138,7 @@ fn longer_paths() {
             &slice[..slice.iter().take_while(|&&b| b != b'\0' ).count()]
         }
     }
+    #[allow(clippy::needless_range_loop)]
     fn new_longaddr(fill: u8,  extra_len: usize) -> (LongAddr, libc::socklen_t) {
         let mut addr = unsafe { std::mem::zeroed::<LongAddr>() };
         addr.sockaddr.sun_family = libc::AF_UNIX as libc::sa_family_t;
@@ -161,6 +166,7 @@ fn longer_paths() {
         }
     }
 
+    #[allow(clippy::explicit_auto_deref, clippy::useless_format)]
     fn try_longer(fill: u8,  extra_len: usize) -> Result<bool, String> {
         let socket_a = UnixDatagram::unbound().unwrap();
         let (path_addr, addrlen) = new_longaddr(fill, extra_len);
@@ -249,6 +255,7 @@ fn std_includes_nuls_normal() {
     remove_file(regular_path).expect("delete socket file");
 }
 
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn std_includes_nuls_long() {
     print!("std_includes_nuls_long ");
     let max_regular_len = UnixSocketAddr::max_path_len()-1;
@@ -307,15 +314,15 @@ fn fd_must_remain_open_until_received() {
     }
     let mut fd_buf = [-1; 1];
     match b.recv_fds(&mut[0; 10], &mut fd_buf[..]) {
-        Ok((_, 1)) => {
+        Ok(RecvFdsInfo { fds: 1, .. }) => {
             let received = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
             match received.local_addr() {
                 Ok(_) => println!("no"),
                 Err(e) => println!("yes (operation on fd failed with {})", e),
             }
         },
-        Ok((_, 0)) => println!("yes (fd was not received)"),
-        Ok((_, too_many)) => println!("N/A (received {} fds out of a single sent", too_many),
+        Ok(RecvFdsInfo { fds: 0, .. }) => println!("yes (fd was not received)"),
+        Ok(RecvFdsInfo { fds: too_many, .. }) => println!("N/A (received {} fds out of a single sent", too_many),
         Err(ref e) if e.kind() == WouldBlock => println!("yes (datagram got dropped"),
         Err(e) => println!("N/A (receive failed with unexpected reaseon {})", e),
     }
@@ -331,7 +338,7 @@ fn fd_might_not_be_cloned() {
     }
     let mut fd_buf = [-1; 1];
     match b.recv_fds(&mut[0; 10], &mut fd_buf[..]) {
-        Ok((_, 1)) if fd_buf[0] == a.as_raw_fd() => {
+        Ok(RecvFdsInfo { fds: 1, .. }) if fd_buf[0] == a.as_raw_fd() => {
             drop(a);
             let received = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
             match received.local_addr() {
@@ -339,15 +346,15 @@ fn fd_might_not_be_cloned() {
                 Err(_) => println!("yes (sent and got fd {})", fd_buf[0]),
             }
         }
-        Ok((_, 1)) => {
+        Ok(RecvFdsInfo { fds: 1, .. }) => {
             let received = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
             match received.local_addr() {
                 Ok(_) => println!("no (sent fd {} got fd {})", a.as_raw_fd(), fd_buf[0]),
                 Err(e) => println!("maybe (operation on different fd failed with {})", e),
             }
         }
-        Ok((_, 0)) => println!("yes (fd was not received)"),
-        Ok((_, too_many)) => println!("N/A (received {} fds out of a single sent", too_many),
+        Ok(RecvFdsInfo { fds: 0, .. }) => println!("yes (fd was not received)"),
+        Ok(RecvFdsInfo { fds: too_many, .. }) => println!("N/A (received {} fds out of a single sent", too_many),
         Err(ref e) if e.kind() == WouldBlock => println!("yes (datagram got dropped"),
         Err(e) => println!("N/A (receive failed with unexpected reaseon {})", e),
     }
@@ -361,8 +368,8 @@ fn fd_cloned_same_process() {
     } else {
         let mut fd_buf = [-1; 2];
         match b.recv_fds(&mut[0; 32], &mut fd_buf) {
-            Ok((_, 1)) if fd_buf[0] == a.as_raw_fd() => println!("no"),
-            Ok((_, 1)) => {
+            Ok(RecvFdsInfo { fds: 1, .. }) if fd_buf[0] == a.as_raw_fd() => println!("no"),
+            Ok(RecvFdsInfo { fds: 1, .. }) => {
                 println!("yes");
                 let _ = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
             },
@@ -382,10 +389,10 @@ fn fd_cloned_on_send() {
         let new_fd = UnixDatagram::unbound().expect("create unbound datagram socket");
         let mut fd_buf = [-1; 2];
         match b.recv_fds(&mut[0; 32], &mut fd_buf) {
-            Ok((_, 1)) if fd_buf[0] == a_fd  &&  new_fd.as_raw_fd() == a_fd => {
+            Ok(RecvFdsInfo { fds: 1, .. }) if fd_buf[0] == a_fd  &&  new_fd.as_raw_fd() == a_fd => {
                 println!("N/A (original, now reused fd returned)");
             }
-            Ok((_, 1)) => {
+            Ok(RecvFdsInfo { fds: 1, .. }) => {
                 let received = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
                 match received.local_unix_addr() {
                     Ok(_) => println!("yes"),
@@ -397,6 +404,7 @@ fn fd_cloned_on_send() {
     }
 }
 
+#[allow(clippy::unused_io_amount)] // a single write() call is what's being tested
 fn stream_ancillary_payloads_not_merged() {
     print!("stream_ancillary_payloads_not_merged ");
     let (mut a, b) = UnixStream::pair().expect("create stream socket pair");
@@ -410,8 +418,8 @@ fn stream_ancillary_payloads_not_merged() {
     a.write(b"0").expect("write more bytes but no fds");
     let mut fd_buf = [-1; 6];
     match b.recv_fds(&mut[0u8; 20], &mut fd_buf) {
-        Ok((1, 1)) if fd_buf[0] != -1  &&  fd_buf[1] == -1 => print!("yes "),
-        Ok((bytes, fds)) => print!("no ({} bytes, {} fds) ", bytes, fds),
+        Ok(RecvFdsInfo { bytes: 1, fds: 1, .. }) if fd_buf[0] != -1  &&  fd_buf[1] == -1 => print!("yes "),
+        Ok(RecvFdsInfo { bytes, fds, .. }) => print!("no ({} bytes, {} fds) ", bytes, fds),
         Err(e) => print!("no ({})", e),
     }
     if fd_buf[0] != a.as_raw_fd() {
@@ -420,8 +428,8 @@ fn stream_ancillary_payloads_not_merged() {
 
     let mut fd_buf = [-1; 6];
     match b.recv_fds(&mut[0u8; 20], &mut fd_buf) {
-        Ok((1, 0)) if fd_buf[0] == -1 => print!("yes "),
-        Ok((bytes, fds)) => print!("no ({} bytes, {} fds) ", bytes, fds),
+        Ok(RecvFdsInfo { bytes: 1, fds: 0, .. }) if fd_buf[0] == -1 => print!("yes "),
+        Ok(RecvFdsInfo { bytes, fds, .. }) => print!("no ({} bytes, {} fds) ", bytes, fds),
         Err(e) => print!("no ({}) ", e),
     }
 
@@ -431,8 +439,8 @@ fn stream_ancillary_payloads_not_merged() {
         .expect("write three more fds");
     let mut fd_buf = [-1; 6];
     match b.recv_fds(&mut[0u8; 3], &mut fd_buf) {
-        Ok((1, 2)) if fd_buf[1] != -1  &&  fd_buf[2] == -1 => print!("yes "),
-        Ok((bytes, fds)) => print!("no ({} bytes, {} fds) ", bytes, fds),
+        Ok(RecvFdsInfo { bytes: 1, fds: 2, .. }) if fd_buf[1] != -1  &&  fd_buf[2] == -1 => print!("yes "),
+        Ok(RecvFdsInfo { bytes, fds, .. }) => print!("no ({} bytes, {} fds) ", bytes, fds),
         Err(e) => print!("no ({})", e),
     }
     if fd_buf[0] != a.as_raw_fd()  &&  fd_buf[0] != b.as_raw_fd() {
@@ -442,8 +450,8 @@ fn stream_ancillary_payloads_not_merged() {
 
     let mut fd_buf = [-1; 6];
     match b.recv_fds(&mut[0u8; 3], &mut fd_buf) {
-        Ok((1, 3)) if fd_buf[2] != -1  &&  fd_buf[3] == -1 => println!("yes"),
-        Ok((bytes, fds)) => println!("no ({} bytes, {} fds)", bytes, fds),
+        Ok(RecvFdsInfo { bytes: 1, fds: 3, .. }) if fd_buf[2] != -1  &&  fd_buf[3] == -1 => println!("yes"),
+        Ok(RecvFdsInfo { bytes, fds, .. }) => println!("no ({} bytes, {} fds)", bytes, fds),
         Err(e) => println!("no ({})", e),
     }
     if fd_buf[0] != a.as_raw_fd()  &&  fd_buf[0] != b.as_raw_fd() {
@@ -21,6 +21,8 @@ use libc::{getsockopt, c_void, socklen_t};
 use libc::{pid_t, uid_t, gid_t, getpid, getuid, geteuid, getgid, getegid};
 #[cfg(any(target_os="linux", target_os="android"))]
 use libc::{ucred, SOL_SOCKET, SO_PEERCRED, SO_PEERSEC};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{SO_PASSCRED, SO_PASSSEC};
 #[cfg(any(target_os="freebsd", target_os="dragonfly", target_vendor="apple"))]
 use libc::{xucred, XUCRED_VERSION, LOCAL_PEERCRED};
 #[cfg(target_vendor="apple")]
@@ -34,20 +36,27 @@ use libc::{getpeerucred, ucred_free, ucred_t};
 #[cfg(any(target_os="illumos", target_os="solaris"))]
 use libc::{ucred_geteuid, ucred_getegid, ucred_getpid, ucred_getgroups, uid_t, gid_t, pid_t};
 
+#[cfg(any(target_os="linux", target_os="android"))]
+use crate::helpers::set_bool_option;
+
 /// Credentials to be sent with `send_ancillary()`.
 ///
 /// Only on Linux (& Android) does one need to send credentials, and on other
 /// operating systems this struct is ignored.
 #[derive(Clone,Copy, PartialEq,Eq, Debug)]
-#[allow(unused)] // not used yet
 pub enum SendCredentials {
+    /// The pid, effective user ID and effective group ID of the current process.
     Effective,
+    /// The pid, real user ID and real group ID of the current process.
     Real,
+    /// Any values, which the kernel will only accept if the process
+    /// has the necessary privileges (`CAP_SYS_ADMIN`, `CAP_SETUID` and `CAP_SETGID`)
     Custom{ pid: u32, uid: u32, gid: u32 }
 }
 #[cfg(any(target_os="linux", target_os="android"))]
 impl SendCredentials {
-    pub fn into_raw(self) -> ucred {
+    #[allow(clippy::needless_return)]
+    pub(crate) fn into_raw(self) -> ucred {
         let mut ucred: ucred = unsafe { mem::zeroed() };
         let (pid, uid, gid) = match self {
             SendCredentials::Effective => unsafe { (getpid(), geteuid(), getegid()) },
@@ -67,7 +76,7 @@ impl SendCredentials {
 pub fn selinux_context(fd: RawFd,  buffer: &mut[u8]) -> Result<usize, io::Error> {
     unsafe {
         let ptr = buffer.as_mut_ptr() as *mut c_void;
-        let mut capacity = buffer.len().min(socklen_t::max_value() as usize) as socklen_t;
+        let mut capacity = buffer.len().min(socklen_t::MAX as usize) as socklen_t;
         match getsockopt(fd, SOL_SOCKET, SO_PEERSEC, ptr, &mut capacity) {
             -1 => Err(io::Error::last_os_error()),
             _ => Ok(capacity as usize),
@@ -80,6 +89,28 @@ pub fn selinux_context(_fd: RawFd,  _buffer: &mut[u8]) -> Result<usize, io::Erro
     Err(io::Error::new(Other, "not available"))
 }
 
+/// Safe wrapper around `setsockopt(SO_PASSCRED)`.
+#[cfg(any(target_os="linux", target_os="android"))]
+pub fn set_pass_credentials(fd: RawFd,  pass: bool) -> Result<(), io::Error> {
+    set_bool_option(fd, SOL_SOCKET, SO_PASSCRED, pass)
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+pub fn set_pass_credentials(_fd: RawFd,  _pass: bool) -> Result<(), io::Error> {
+    Err(io::Error::new(Unsupported, "not available"))
+}
+
+/// Safe wrapper around `setsockopt(SO_PASSSEC)`.
+#[cfg(any(target_os="linux", target_os="android"))]
+pub fn set_pass_security(fd: RawFd,  pass: bool) -> Result<(), io::Error> {
+    set_bool_option(fd, SOL_SOCKET, SO_PASSSEC, pass)
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+pub fn set_pass_security(_fd: RawFd,  _pass: bool) -> Result<(), io::Error> {
+    Err(io::Error::new(Unsupported, "not available"))
+}
+
 
 
 /// Credentials of the peer process when it called `connect()`, `accept()` or `pair()`.
@@ -110,6 +141,7 @@ pub enum ConnCredentials {
     LinuxLike{ pid: NonZeroU32, euid: u32, egid: u32 },
     MacOsLike{ euid: u32, number_of_groups: u8, groups: [u32; 16/*what libc uses for all OSes*/] },
 }
+#[allow(clippy::match_ref_pats)]
 impl ConnCredentials {
     /// Get the process ID of the initial peer of a connection.
     ///
@@ -169,6 +201,7 @@ impl ConnCredentials {
     }
 }
 impl fmt::Debug for ConnCredentials {
+    #[allow(clippy::match_ref_pats, clippy::needless_borrowed_reference)]
     fn fmt(&self,  fmtr: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         let mut repr = fmtr.debug_struct("ConnCredentials");
         match self {
@@ -406,9 +439,9 @@ pub struct ReceivedCredentials {
     groups: [u32; 5],
 }
 
-#[allow(unused)] // TODO
 impl ReceivedCredentials {
     #[cfg(any(target_os="linux", target_os="android"))]
+    #[allow(clippy::unnecessary_cast)] // uid_t and gid_t are not u32 everywhere
     pub(crate) fn from_raw(creds: libc::ucred) -> Self {
         ReceivedCredentials {
             pid: creds.pid as u32,
@@ -428,6 +461,7 @@ impl ReceivedCredentials {
             None
         }
     }
+    /// The effective user ID of the peer, or the user ID it chose to send on Linux.
     pub fn effective_or_sent_uid(&self) -> u32 {
         #[cfg(any(target_os="linux", target_os="android"))] {
             self.uid
@@ -446,6 +480,7 @@ impl ReceivedCredentials {
             unreachable!("struct cannot be created on unsupported OSes")
         }
     }
+    /// The real user ID of the peer, or the user ID it chose to send on Linux.
     pub fn real_or_sent_uid(&self) -> u32 {
         #[cfg(any(target_os="linux", target_os="android"))] {
             self.uid
@@ -464,6 +499,9 @@ impl ReceivedCredentials {
             unreachable!("struct cannot be created on unsupported OSes")
         }
     }
+    /// The effective group ID of the peer, or the group ID it chose to send on Linux.
+    ///
+    /// This information is not available on DragonFly BSD.
     pub fn effective_or_sent_gid(&self) -> Option<u32> {
         #[cfg(any(target_os="linux", target_os="android"))] {
             Some(self.gid)
@@ -482,6 +520,7 @@ impl ReceivedCredentials {
             None
         }
     }
+    /// The real group ID of the peer, or the group ID it chose to send on Linux.
     pub fn real_or_sent_gid(&self) -> u32 {
         #[cfg(any(target_os="linux", target_os="android"))] {
             self.gid
@@ -0,0 +1,200 @@
+//! Passing listening sockets to another process, such as a new version of
+//! a daemon that is restarting without refusing connections.
+//!
+//! The sending process gives each listener a name, and sends them all over a
+//! seqpacket connection with [`send_listeners()`](fn.send_listeners.html).
+//! The receiving process gets them with [`recv_listeners()`](fn.recv_listeners.html),
+//! and takes them out by name as typed listeners, which fails if the
+//! file descriptor isn't the expected kind of listening socket.
+//!
+//! # Examples
+//!
+#![cfg_attr(not(target_vendor="apple"), doc="```")]
+#![cfg_attr(target_vendor="apple", doc="```no_run")]
+//! use uds::{UnixSeqpacketConn, UnixSeqpacketListener, handoff};
+//! use std::os::unix::net::UnixListener;
+//! use std::net::TcpListener;
+//! use std::os::unix::io::AsFd;
+//!
+//! # let _ = std::fs::remove_file("handoff_control.sock");
+//! # let _ = std::fs::remove_file("handoff_api.sock");
+//! let control = UnixSeqpacketListener::bind("handoff_control.sock").unwrap();
+//! let api = UnixListener::bind("handoff_api.sock").unwrap();
+//! let http = TcpListener::bind("127.0.0.1:0").unwrap();
+//! # let _ = std::fs::remove_file("handoff_control.sock");
+//! # let _ = std::fs::remove_file("handoff_api.sock");
+//!
+//! let (old_process, new_process) = UnixSeqpacketConn::pair().unwrap();
+//! handoff::send_listeners(&old_process, &[
+//!     ("control", control.as_fd()),
+//!     ("api", api.as_fd()),
+//!     ("http", http.as_fd()),
+//! ]).unwrap();
+//!
+//! let mut listeners = handoff::recv_listeners(&new_process).unwrap();
+//! assert_eq!(listeners.names().collect::<Vec<_>>(), ["control", "api", "http"]);
+//! let control = listeners.take_seqpacket_listener("control").unwrap();
+//! let api = listeners.take_unix_listener("api").unwrap();
+//! assert!(listeners.take_unix_listener("http").is_err());
+//! let http = listeners.take_tcp_listener("http").unwrap();
+//! assert!(listeners.is_empty());
+//! ```
+
+use std::io::{self, ErrorKind::*};
+use std::os::unix::io::{AsFd, OwnedFd};
+use std::os::unix::net::UnixListener;
+use std::net::TcpListener;
+use std::convert::TryInto;
+
+use crate::ancillary::ReceivedFds;
+use crate::received_fd::{ReceivedFd, FdKind};
+use crate::seqpacket::{UnixSeqpacketConn, UnixSeqpacketListener};
+
+/// Maximum size of the encoded name table.
+const MAX_TABLE_SIZE: usize = 64*1024;
+/// Maximum number of listeners that will be received.
+const MAX_LISTENERS: usize = 4096;
+
+/// Sends listening sockets with their names.
+///
+/// The names must be unique, and the file descriptors are not checked
+/// to be listeners by the sender.
+/// All the names together can take up to 64 KiB.
+pub fn send_listeners<F: AsFd>(conn: &UnixSeqpacketConn,  listeners: &[(&str, F)])
+-> Result<(), io::Error> {
+    let mut table = Vec::new();
+    for (i, &(name, _)) in listeners.iter().enumerate() {
+        if listeners[..i].iter().any(|&(earlier, _)| earlier == name ) {
+            return Err(io::Error::new(InvalidInput, "listener names are not unique"));
+        }
+        let len: u16 = name.len().try_into()
+            .map_err(|_| io::Error::new(InvalidInput, "listener name is too long") )?;
+        table.extend_from_slice(&len.to_le_bytes());
+        table.extend_from_slice(name.as_bytes());
+    }
+    if table.len() > MAX_TABLE_SIZE  ||  listeners.len() > MAX_LISTENERS {
+        return Err(io::Error::new(InvalidInput, "too many listeners"));
+    }
+    let fds = listeners.iter().map(|(_, fd)| fd.as_fd() ).collect::<Vec<_>>();
+    conn.send_many_fds(&table, &fds)
+}
+
+/// Receives listening sockets sent with `send_listeners()`.
+pub fn recv_listeners(conn: &UnixSeqpacketConn) -> Result<Listeners, io::Error> {
+    let mut table = vec![0; MAX_TABLE_SIZE];
+    let mut fds = ReceivedFds::with_capacity(MAX_LISTENERS);
+    fds.set_strict(true);
+    let len = conn.recv_many_fds(&mut table, &mut fds)?.bytes;
+
+    let mut names = Vec::with_capacity(fds.len());
+    let mut table = &table[..len];
+    while !table.is_empty() {
+        let name_len = match table {
+            &[a, b, ..] => u16::from_le_bytes([a, b]) as usize,
+            _ => return Err(io::Error::new(InvalidData, "truncated listener name")),
+        };
+        let name = table.get(2..2+name_len)
+            .ok_or_else(|| io::Error::new(InvalidData, "truncated listener name") )?;
+        let name = String::from_utf8(name.to_vec())
+            .map_err(|_| io::Error::new(InvalidData, "listener name is not UTF-8") )?;
+        if names.contains(&name) {
+            return Err(io::Error::new(InvalidData, "listener names are not unique"));
+        }
+        names.push(name);
+        table = &table[2+name_len..];
+    }
+    if names.len() != fds.len() {
+        return Err(io::Error::new(InvalidData, "number of names and listeners differ"));
+    }
+    let listeners = names.into_iter()
+        .zip(fds.into_vec().into_iter().map(ReceivedFd::from))
+        .collect();
+    Ok(Listeners { listeners })
+}
+
+/// Named listeners received with `recv_listeners()`.
+///
+/// Listeners that haven't been taken out are closed when this is dropped.
+#[derive(Debug)]
+pub struct Listeners {
+    listeners: Vec<(String, ReceivedFd)>,
+}
+
+impl Listeners {
+    /// The names of the listeners that haven't been taken out,
+    /// in the order they were sent.
+    pub fn names(&self) -> impl Iterator<Item=&str> + '_ {
+        self.listeners.iter().map(|(name, _)| name.as_str() )
+    }
+    /// Returns the number of listeners that haven't been taken out.
+    pub fn len(&self) -> usize {
+        self.listeners.len()
+    }
+    /// Returns whether all the listeners have been taken out.
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+    /// Takes out a listener without checking what it is.
+    pub fn take(&mut self,  name: &str) -> Option<ReceivedFd> {
+        let index = self.listeners.iter().position(|(n, _)| n == name )?;
+        Some(self.listeners.remove(index).1)
+    }
+
+    /// Takes out the listener if it passes `check`,
+    /// so that it isn't lost if it's of the wrong type.
+    fn take_checked(&mut self,  name: &str,
+            check: impl Fn(&ReceivedFd) -> Result<bool, io::Error>,
+            desc: &'static str,
+    ) -> Result<ReceivedFd, io::Error> {
+        let index = self.listeners.iter().position(|(n, _)| n == name )
+            .ok_or_else(|| io::Error::new(NotFound, "no listener with that name") )?;
+        if !check(&self.listeners[index].1)? {
+            return Err(io::Error::new(InvalidData, desc));
+        }
+        Ok(self.listeners.remove(index).1)
+    }
+
+    /// Takes out a listening unix seqpacket socket.
+    ///
+    /// Fails with `NotFound` if there is no listener with the name, and with
+    /// `InvalidData` if it's not a listening seqpacket socket,
+    /// in which case it's not taken out.
+    pub fn take_seqpacket_listener(&mut self,  name: &str)
+    -> Result<UnixSeqpacketListener, io::Error> {
+        self.take_checked(
+            name,
+            |fd| Ok(fd.kind()? == FdKind::UnixSeqpacketListener),
+            "listener is not a unix seqpacket listener",
+        )?.into_seqpacket_listener()
+    }
+    /// Takes out a listening unix stream socket.
+    ///
+    /// Fails with `NotFound` if there is no listener with the name, and with
+    /// `InvalidData` if it's not a listening unix stream socket,
+    /// in which case it's not taken out.
+    pub fn take_unix_listener(&mut self,  name: &str) -> Result<UnixListener, io::Error> {
+        self.take_checked(
+            name,
+            |fd| Ok(fd.kind()? == FdKind::UnixListener),
+            "listener is not a unix stream listener",
+        )?.into_unix_listener()
+    }
+    /// Takes out a listening TCP socket.
+    ///
+    /// Fails with `NotFound` if there is no listener with the name, and with
+    /// `InvalidData` if it's not a listening TCP socket,
+    /// in which case it's not taken out.
+    pub fn take_tcp_listener(&mut self,  name: &str) -> Result<TcpListener, io::Error> {
+        self.take_checked(
+            name,
+            ReceivedFd::is_tcp_listener,
+            "listener is not a TCP listener",
+        )?.into_tcp_listener()
+    }
+    /// Returns the remaining listeners with their names.
+    pub fn into_vec(self) -> Vec<(String, OwnedFd)> {
+        self.listeners.into_iter()
+            .map(|(name, fd)| (name, fd.into_owned_fd()) )
+            .collect()
+    }
+}
@@ -14,7 +14,13 @@ use libc::{bind, connect, getsockname, getpeername};
 use libc::{socket, accept, close, listen, socketpair};
 use libc::{ioctl, FIONBIO, FIOCLEX, FIONCLEX};
 use libc::{fcntl, F_DUPFD_CLOEXEC, EINVAL, dup};
-use libc::{getsockopt, SOL_SOCKET, SO_ERROR, c_void};
+use libc::{getsockopt, SOL_SOCKET, SO_ERROR, SO_SNDBUF, SO_RCVBUF, FIONREAD, c_void};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::TIOCOUTQ as SIOCOUTQ;
+#[cfg(target_os="freebsd")]
+use libc::FIONWRITE;
+#[cfg(target_vendor="apple")]
+use libc::SO_NWRITE;
 #[cfg_attr(target_env="musl", allow(deprecated))]
 use libc::{setsockopt, SO_RCVTIMEO, SO_SNDTIMEO, timeval, time_t};
 #[cfg(any(target_os="illumos", target_os="solaris"))]
@@ -30,7 +36,7 @@ use crate::addr::*;
 
 
 
-const LISTEN_BACKLOG: c_int = 10; // what std uses, I think
+pub const LISTEN_BACKLOG: c_int = 10; // what std uses, I think
 
 
 
@@ -131,6 +137,28 @@ pub fn take_error(socket: RawFd) -> Result<Option<io::Error>, io::Error> {
     }
 }
 
+/// Safe wrapper around `setsockopt()` for options that are enabled or disabled.
+pub fn set_bool_option(socket: RawFd,  level: c_int,  option: c_int,  enable: bool)
+-> Result<(), io::Error> {
+    unsafe {
+        let value = enable as c_int;
+        let value_ptr = &value as *const c_int as *const c_void;
+        let value_size = mem::size_of::<c_int>() as socklen_t;
+        cvt!(setsockopt(socket, level, option, value_ptr, value_size))?;
+    }
+    Ok(())
+}
+
+/// Enables / disables `SO_TIMESTAMPNS` on Linux and Android,
+/// or `SO_TIMESTAMP` on other OSes.
+pub fn set_receive_timestamps(socket: RawFd,  enable: bool) -> Result<(), io::Error> {
+    #[cfg(any(target_os="linux", target_os="android"))]
+    let option = libc::SO_TIMESTAMPNS;
+    #[cfg(not(any(target_os="linux", target_os="android")))]
+    let option = libc::SO_TIMESTAMP;
+    set_bool_option(socket, SOL_SOCKET, option, enable)
+}
+
 #[repr(C)]
 pub struct TimeoutDirection(c_int);
 impl TimeoutDirection {
@@ -149,7 +177,7 @@ pub fn set_timeout(socket: RawFd,  direction: TimeoutDirection,  timeout: Option
             // (there is no polymorphic way to get the max value of a signed type.)
             // TODO change to ::MAX after MSRV is bumped to 1.43.
             #[cfg_attr(target_env="musl", allow(deprecated))]
-            Err(_) => time_t::max_value() as _,
+            Err(_) => time_t::MAX as _,
         };
         time.tv_usec = duration.subsec_micros() as _;
 
@@ -199,6 +227,78 @@ pub fn get_timeout(socket: RawFd,  direction: TimeoutDirection)
 }
 
 
+#[repr(C)]
+pub struct BufferDirection(c_int);
+impl BufferDirection {
+    pub const SEND: Self = BufferDirection(SO_SNDBUF);
+    pub const RECV: Self = BufferDirection(SO_RCVBUF);
+}
+/// Safe wrapper around `setsockopt(SO_SNDBUF)` or `setsockopt(SO_RCVBUF)`.
+pub fn set_buffer_size(socket: RawFd,  direction: BufferDirection,  size: usize)
+-> Result<(), io::Error> {
+    let size: c_int = size.try_into().map_err(|_| io::Error::new(
+        ErrorKind::InvalidInput,
+        "buffer size is too big"
+    ))?;
+    unsafe {
+        let size_ptr = &size as *const c_int as *const c_void;
+        let size_size = mem::size_of::<c_int>() as socklen_t;
+        cvt!(setsockopt(socket, SOL_SOCKET, direction.0, size_ptr, size_size))?;
+    }
+    Ok(())
+}
+/// Safe wrapper around `getsockopt(SO_SNDBUF)` or `getsockopt(SO_RCVBUF)`.
+pub fn get_buffer_size(socket: RawFd,  direction: BufferDirection)
+-> Result<usize, io::Error> {
+    let size = unsafe {
+        let mut size: c_int = 0;
+        let size_ptr = &mut size as *mut c_int as *mut c_void;
+        let mut size_size = mem::size_of::<c_int>() as socklen_t;
+        cvt!(getsockopt(socket, SOL_SOCKET, direction.0, size_ptr, &mut size_size))?;
+        size
+    };
+    size.try_into().map_err(|_| io::Error::new(
+        ErrorKind::InvalidData,
+        "buffer size is negative"
+    ))
+}
+
+/// Safe wrapper around `ioctl(FIONREAD)`, which is also known as `SIOCINQ`.
+pub fn pending_incoming(socket: RawFd) -> Result<usize, io::Error> {
+    let mut pending: c_int = 0;
+    cvt!(unsafe { ioctl(socket, FIONREAD, &mut pending) })?;
+    Ok(pending as usize)
+}
+/// Gets the number of bytes sent but not yet received by the peer,
+/// with `ioctl(SIOCOUTQ)` on Linux and Android, `ioctl(FIONWRITE)` on FreeBSD
+/// and `getsockopt(SO_NWRITE)` on macOS.
+pub fn pending_outgoing(socket: RawFd) -> Result<usize, io::Error> {
+    #[cfg(any(target_os="linux", target_os="android", target_os="freebsd"))] {
+        #[cfg(any(target_os="linux", target_os="android"))]
+        let request = SIOCOUTQ;
+        #[cfg(target_os="freebsd")]
+        let request = FIONWRITE;
+        let mut pending: c_int = 0;
+        cvt!(unsafe { ioctl(socket, request, &mut pending) })?;
+        Ok(pending as usize)
+    }
+    #[cfg(target_vendor="apple")]
+    unsafe {
+        let mut pending: c_int = 0;
+        let pending_ptr = &mut pending as *mut c_int as *mut c_void;
+        let mut pending_size = mem::size_of::<c_int>() as socklen_t;
+        cvt!(getsockopt(socket, SOL_SOCKET, SO_NWRITE, pending_ptr, &mut pending_size))?;
+        Ok(pending as usize)
+    }
+    #[cfg(not(any(
+        target_os="linux", target_os="android", target_os="freebsd", target_vendor="apple"
+    )))] {
+        let _ = socket;
+        Err(io::Error::new(ErrorKind::Other, "not available"))
+    }
+}
+
+
 
 /// Used in setup of sockets to ensure the file descriptor is always closed
 /// if later parts of the setup fails.
@@ -0,0 +1,118 @@
+use std::io::{self, ErrorKind::*, IoSlice, IoSliceMut};
+use std::os::unix::io::RawFd;
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::os::unix::io::{AsRawFd, FromRawFd};
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::os::unix::fs::FileExt;
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::io::Write;
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::convert::TryInto;
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::fs::File;
+
+use libc::MSG_EOR;
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{c_int, fcntl, memfd_create, MFD_CLOEXEC, MFD_ALLOW_SEALING};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{F_ADD_SEALS, F_GET_SEALS, F_SEAL_SEAL, F_SEAL_SHRINK, F_SEAL_GROW, F_SEAL_WRITE};
+
+use crate::ancillary::*;
+#[cfg(any(target_os="linux", target_os="android"))]
+use crate::received_fd::{ReceivedFd, FdKind};
+
+/// Payloads up to this size are sent inline.
+///
+/// This is far below the default send buffer size on Linux,
+/// so inline packets are not rejected as too big.
+pub(crate) const LARGE_INLINE_MAX: usize = 32*1024;
+
+/// Tag byte for packets carrying the payload.
+const INLINE: u8 = 0;
+/// Tag byte for packets carrying the length of a sealed memfd sent with it.
+#[cfg(any(target_os="linux", target_os="android"))]
+const MEMFD: u8 = 1;
+#[cfg(any(target_os="linux", target_os="android"))]
+const REQUIRED_SEALS: c_int = F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_WRITE;
+
+fn invalid(desc: &'static str) -> io::Error {
+    io::Error::new(InvalidData, desc)
+}
+
+/// Sends a packet with a tag byte and the payload,
+/// or the length and a sealed memfd containing it.
+pub(crate) fn send_large(socket: RawFd,  bytes: &[u8]) -> Result<(), io::Error> {
+    if bytes.len() <= LARGE_INLINE_MAX {
+        let slices = [IoSlice::new(&[INLINE]), IoSlice::new(bytes)];
+        send_ancillary(socket, None, MSG_EOR, &slices, &[], None)?;
+        return Ok(());
+    }
+    #[cfg(any(target_os="linux", target_os="android"))] {
+        let name = b"uds-large-payload\0";
+        let flags = MFD_CLOEXEC | MFD_ALLOW_SEALING;
+        let memfd = cvt!(unsafe { memfd_create(name.as_ptr() as *const _, flags) })?;
+        let mut memfd = unsafe { File::from_raw_fd(memfd) };
+        memfd.write_all(bytes)?;
+        let seals = REQUIRED_SEALS | F_SEAL_SEAL;
+        cvt!(unsafe { fcntl(memfd.as_raw_fd(), F_ADD_SEALS, seals) })?;
+        let length = (bytes.len() as u64).to_le_bytes();
+        let slices = [IoSlice::new(&[MEMFD]), IoSlice::new(&length)];
+        send_ancillary(socket, None, MSG_EOR, &slices, &[memfd.as_raw_fd()], None)?;
+        Ok(())
+    }
+    #[cfg(not(any(target_os="linux", target_os="android")))] {
+        Err(io::Error::new(Other, "not available"))
+    }
+}
+
+/// Receives a packet sent with `send_large()`, and reads the memfd if there is one.
+pub(crate) fn recv_large(socket: RawFd,  max_len: usize) -> Result<Vec<u8>, io::Error> {
+    let mut buf = vec![0; 1+LARGE_INLINE_MAX];
+    let mut fds = ReceivedFds::with_capacity(1);
+    fds.set_strict(true);
+    let info = recv_owned_fds(socket, None, &mut[IoSliceMut::new(&mut buf)], &mut fds)?;
+    if info.message_truncated  ||  info.bytes == 0 {
+        return Err(invalid("packet was not sent with send_large()"));
+    }
+    if buf[0] == INLINE  &&  fds.is_empty() {
+        if info.bytes-1 > max_len {
+            return Err(invalid("payload is longer than the maximum length"));
+        }
+        buf.truncate(info.bytes);
+        buf.remove(0);
+        return Ok(buf);
+    }
+    #[cfg(any(target_os="linux", target_os="android"))] {
+        if buf[0] == MEMFD  &&  info.bytes == 1+8  &&  fds.len() == 1 {
+            let length = u64::from_le_bytes(buf[1..9].try_into().unwrap());
+            if length > max_len as u64 {
+                return Err(invalid("payload is longer than the maximum length"));
+            }
+            return read_memfd(ReceivedFd::from(fds.take().remove(0)), length as usize);
+        }
+    }
+    Err(invalid("packet was not sent with send_large()"))
+}
+
+/// Reads the payload after checking that the sender can no longer change it.
+#[cfg(any(target_os="linux", target_os="android"))]
+fn read_memfd(memfd: ReceivedFd,  length: usize) -> Result<Vec<u8>, io::Error> {
+    if memfd.kind()? != FdKind::Memfd {
+        return Err(invalid("received file descriptor is not a memfd"));
+    }
+    let seals = cvt!(unsafe { fcntl(memfd.as_raw_fd(), F_GET_SEALS) })?;
+    if seals & REQUIRED_SEALS != REQUIRED_SEALS {
+        return Err(invalid("received memfd is not sealed"));
+    }
+    let memfd = memfd.into_file()?;
+    if memfd.metadata()?.len() != length as u64 {
+        return Err(invalid("received memfd has a different length than announced"));
+    }
+    let mut payload = Vec::new();
+    payload.try_reserve_exact(length)
+        .map_err(|_| io::Error::new(OutOfMemory, "payload is too big to fit in memory") )?;
+    payload.resize(length, 0);
+    // the file offset is shared with the sender, so don't use it
+    memfd.read_exact_at(&mut payload, 0)?;
+    Ok(payload)
+}
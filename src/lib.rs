@@ -55,17 +55,34 @@ macro_rules! cvt_r {($syscall:expr) => {
 
 mod addr;
 mod credentials;
+mod pidfd;
+mod received_fd;
+mod large;
+mod many_fds;
+#[allow(clippy::empty_line_after_doc_comments)]
 mod helpers;
-mod ancillary;
+mod listener_builder;
+mod unlink_on_drop;
+pub mod ancillary;
+pub mod batch;
+pub mod handoff;
 mod traits;
 mod seqpacket;
 #[cfg(feature="tokio")]
 pub mod tokio;
+#[cfg(feature="tokio1")]
+pub mod tokio1;
+#[cfg(feature="async-io")]
+pub mod async_io;
 
 pub use addr::{UnixSocketAddr, UnixSocketAddrRef, AddrName};
 pub use traits::{UnixListenerExt, UnixStreamExt, UnixDatagramExt};
 pub use seqpacket::{UnixSeqpacketListener, UnixSeqpacketConn};
-pub use credentials::ConnCredentials;
+pub use credentials::{ConnCredentials, ReceivedCredentials, SendCredentials};
+pub use pidfd::PidFd;
+pub use received_fd::{ReceivedFd, FdKind};
+pub use listener_builder::ListenerBuilder;
+pub use unlink_on_drop::UnlinkOnDrop;
 
 pub mod nonblocking {
     pub use crate::seqpacket::NonblockingUnixSeqpacketListener as UnixSeqpacketListener;
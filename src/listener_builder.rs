@@ -0,0 +1,222 @@
+use std::ffi::CString;
+use std::fmt::{self, Debug};
+use std::fs;
+use std::io::{self, ErrorKind::*};
+use std::marker::PhantomData;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+use std::path::Path;
+
+use libc::{c_int, mode_t, uid_t, gid_t, chmod, lchown, listen};
+
+use crate::addr::UnixSocketAddr;
+use crate::helpers::*;
+
+/// Options for creating a listening socket, such as a
+/// [`UnixSeqpacketListener`](struct.UnixSeqpacketListener.html)
+/// or a std `UnixListener`.
+///
+/// Created by `UnixSeqpacketListener::builder()`,
+/// `nonblocking::UnixSeqpacketListener::builder()` or
+/// [`UnixListenerExt::builder()`](trait.UnixListenerExt.html#method.builder).
+///
+/// The permissions and owner of the socket file are changed after binding
+/// but before the socket starts listening, so nobody can connect to it
+/// while it has the permissions given by the umask.
+/// Changing the umask isn't necessary, which would affect all threads.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::UnixSeqpacketListener;
+/// use std::os::unix::fs::PermissionsExt;
+///
+/// # let _ = std::fs::remove_file("builder_example.sock");
+/// let listener = UnixSeqpacketListener::builder()
+///     .backlog(100)
+///     .mode(0o600)
+///     .unlink_if_stale(true)
+///     .bind("builder_example.sock")
+///     .unwrap();
+/// let permissions = std::fs::metadata("builder_example.sock").unwrap().permissions();
+/// assert_eq!(permissions.mode() & 0o777, 0o600);
+/// # drop(listener);
+/// # let _ = std::fs::remove_file("builder_example.sock");
+/// ```
+pub struct ListenerBuilder<L> {
+    socket_type: c_int,
+    nonblocking: bool,
+    backlog: c_int,
+    mode: Option<mode_t>,
+    owner: Option<uid_t>,
+    group: Option<gid_t>,
+    unlink_if_stale: bool,
+    listener: PhantomData<fn() -> L>,
+}
+
+impl<L> Clone for ListenerBuilder<L> {
+    fn clone(&self) -> Self {
+        ListenerBuilder { listener: PhantomData, ..*self }
+    }
+}
+
+impl<L> Debug for ListenerBuilder<L> {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("ListenerBuilder")
+            .field("nonblocking", &self.nonblocking)
+            .field("backlog", &self.backlog)
+            .field("mode", &self.mode.map(|mode| format!("{:o}", mode) ))
+            .field("owner", &self.owner)
+            .field("group", &self.group)
+            .field("unlink_if_stale", &self.unlink_if_stale)
+            .finish()
+    }
+}
+
+impl<L: FromRawFd> ListenerBuilder<L> {
+    pub(crate) fn new(socket_type: c_int,  nonblocking: bool) -> Self {
+        ListenerBuilder {
+            socket_type,
+            nonblocking,
+            backlog: LISTEN_BACKLOG,
+            mode: None,
+            owner: None,
+            group: None,
+            unlink_if_stale: false,
+            listener: PhantomData,
+        }
+    }
+
+    /// Sets how many connections can wait to be accepted before
+    /// new connection attempts block or are refused.
+    ///
+    /// The OS might cap it, for example to `net.core.somaxconn` on Linux.
+    /// The default is 10.
+    pub fn backlog(&mut self,  backlog: u32) -> &mut Self {
+        self.backlog = backlog.min(c_int::MAX as u32) as c_int;
+        self
+    }
+
+    /// Sets the permissions of the socket file, such as `0o660`.
+    ///
+    /// Connecting requires write permission to the socket file
+    /// on Linux and most other operating systems.
+    /// Binding to an abstract address fails if this is set.
+    pub fn mode(&mut self,  mode: u32) -> &mut Self {
+        self.mode = Some(mode as mode_t);
+        self
+    }
+
+    /// Sets the user that owns the socket file, which usually requires root.
+    ///
+    /// Binding to an abstract address fails if this is set.
+    pub fn owner(&mut self,  uid: u32) -> &mut Self {
+        self.owner = Some(uid as uid_t);
+        self
+    }
+
+    /// Sets the group that owns the socket file.
+    ///
+    /// Binding to an abstract address fails if this is set.
+    pub fn group(&mut self,  gid: u32) -> &mut Self {
+        self.group = Some(gid as gid_t);
+        self
+    }
+
+    /// Removes a leftover socket file if binding fails because it exists
+    /// but nothing is listening on it.
+    ///
+    /// This is checked by connecting to it, so a listener that is alive
+    /// might see a connection that is closed immediately.
+    /// The file is only removed if the connection is refused, and never if
+    /// it's not a socket.
+    pub fn unlink_if_stale(&mut self,  unlink: bool) -> &mut Self {
+        self.unlink_if_stale = unlink;
+        self
+    }
+
+    /// Creates a listener on the specified socket file.
+    pub fn bind<P: AsRef<Path>>(&self,  path: P) -> Result<L, io::Error> {
+        let addr = UnixSocketAddr::from_path(path.as_ref())?;
+        self.bind_unix_addr(&addr)
+    }
+
+    /// Creates a listener on the specified address.
+    pub fn bind_unix_addr(&self,  addr: &UnixSocketAddr) -> Result<L, io::Error> {
+        let path = addr.as_pathname();
+        let change_file = self.mode.is_some() || self.owner.is_some() || self.group.is_some();
+        if change_file  &&  path.is_none() {
+            return Err(io::Error::new(
+                InvalidInput,
+                "permissions and owner can only be set for socket files"
+            ));
+        }
+
+        let socket = Socket::new(self.socket_type, self.nonblocking)?;
+        match (set_unix_addr(socket.as_raw_fd(), SetAddr::LOCAL, addr), path) {
+            (Err(ref e), Some(path)) if e.kind() == AddrInUse && self.unlink_if_stale => {
+                remove_if_stale(path, self.socket_type)?;
+                set_unix_addr(socket.as_raw_fd(), SetAddr::LOCAL, addr)?;
+            }
+            (result, _) => result?,
+        }
+
+        let listening = match path {
+            Some(path) if change_file => self.change_file(path),
+            _ => Ok(()),
+        }.and_then(|()| {
+            cvt!(unsafe { listen(socket.as_raw_fd(), self.backlog) }).map(|_| () )
+        });
+        if let Err(e) = listening {
+            if let Some(path) = path {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e);
+        }
+        Ok(unsafe { L::from_raw_fd(socket.into_raw_fd()) })
+    }
+
+    fn change_file(&self,  path: &Path) -> Result<(), io::Error> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| io::Error::new(InvalidInput, "path contains NUL byte") )?;
+        if self.owner.is_some()  ||  self.group.is_some() {
+            // -1 means don't change
+            let owner = self.owner.unwrap_or(!0);
+            let group = self.group.unwrap_or(!0);
+            cvt!(unsafe { lchown(path.as_ptr(), owner, group) })?;
+        }
+        if let Some(mode) = self.mode {
+            // chmod() follows symlinks, but the file was created by bind() just before,
+            // and replacing it requires write access to the directory.
+            cvt!(unsafe { chmod(path.as_ptr(), mode) })?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes the socket file if connecting to it is refused.
+fn remove_if_stale(path: &Path,  socket_type: c_int) -> Result<(), io::Error> {
+    match fs::symlink_metadata(path) {
+        Ok(ref metadata) if metadata.file_type().is_socket() => {}
+        Ok(_) => return Err(io::Error::new(AddrInUse, "path exists and is not a socket")),
+        Err(ref e) if e.kind() == NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    }
+
+    // nonblocking so that a listener with a full backlog doesn't make this block
+    let probe = Socket::new(socket_type, true)?;
+    let addr = UnixSocketAddr::from_path(path)?;
+    match set_unix_addr(probe.as_raw_fd(), SetAddr::PEER, &addr) {
+        Err(ref e) if e.kind() == ConnectionRefused => {
+            match fs::remove_file(path) {
+                Err(ref e) if e.kind() == NotFound => Ok(()),
+                result => result,
+            }
+        }
+        Err(ref e) if e.kind() == NotFound => Ok(()),
+        // connected, the backlog is full or it's a different type of socket
+        _ => Err(io::Error::new(AddrInUse, "another socket is listening on the path")),
+    }
+}
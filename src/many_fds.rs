@@ -0,0 +1,183 @@
+use std::io::{self, ErrorKind::*, IoSliceMut};
+use std::os::unix::io::{RawFd, AsRawFd, AsFd};
+use std::convert::TryInto;
+
+use libc::c_int;
+
+use crate::ancillary::*;
+
+/// Linux rejects messages with more file descriptors than this (`SCM_MAX_FD`),
+/// and other operating systems have similar or higher limits.
+pub(crate) const FDS_PER_MESSAGE: usize = 253;
+
+/// Size of the header of the first message, which contains the total number
+/// of file descriptors and the length of the payload.
+const FIRST_HEADER: usize = 8;
+/// Size of the header of later messages, which contains the position of
+/// their first file descriptor.
+const CHUNK_HEADER: usize = 4;
+
+fn invalid(desc: &'static str) -> io::Error {
+    io::Error::new(InvalidData, desc)
+}
+
+fn read_u32(bytes: &[u8]) -> usize {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize
+}
+
+/// Sends the bytes with the first chunk of file descriptors,
+/// and the remaining chunks in separate messages.
+pub(crate) fn send_many_fds<F: AsFd>(
+        socket: RawFd,  flags: c_int,
+        bytes: &[u8],  fds: &[F]
+) -> Result<(), io::Error> {
+    let fds = fds.iter().map(|fd| fd.as_fd().as_raw_fd() ).collect::<Vec<RawFd>>();
+    let too_many = |_| io::Error::new(InvalidInput, "too many file descriptors or bytes");
+    let total: u32 = fds.len().try_into().map_err(too_many)?;
+    let len: u32 = bytes.len().try_into().map_err(too_many)?;
+    let mut first = Vec::with_capacity(FIRST_HEADER+bytes.len());
+    first.extend_from_slice(&total.to_le_bytes());
+    first.extend_from_slice(&len.to_le_bytes());
+    first.extend_from_slice(bytes);
+
+    let mut chunks = fds.chunks(FDS_PER_MESSAGE);
+    write_all_with_fds(socket, flags, &first, chunks.next().unwrap_or(&[]))?;
+    for (i, chunk) in chunks.enumerate() {
+        let position = ((i+1)*FDS_PER_MESSAGE) as u32;
+        write_all_with_fds(socket, flags, &position.to_le_bytes(), chunk)?;
+    }
+    Ok(())
+}
+
+/// Receives what `send_many_fds()` sent, storing the payload in `buf`
+/// and appending the file descriptors to `fds`.
+///
+/// `message_truncated` and `ancillary_truncated` of the returned info are
+/// always `false`, as those are errors here.
+pub(crate) fn recv_many_fds(
+        socket: RawFd,  stream: bool,
+        buf: &mut[u8],  fds: &mut ReceivedFds
+) -> Result<RecvFdsInfo, io::Error> {
+    let stored_before = fds.len();
+    let mut chunk = ReceivedFds::with_capacity(FDS_PER_MESSAGE);
+    chunk.set_strict(true);
+    chunk.set_inheritable(fds.is_inheritable());
+
+    // If the bytes don't fit, the remaining messages are still received
+    // (and their file descriptors closed), so that the next call starts
+    // at the next message.
+    let mut too_small = false;
+    let mut header = [0u8; FIRST_HEADER];
+    let (total, len) = if stream {
+        recv_exact(socket, true, &mut header, &mut chunk)?;
+        let len = read_u32(&header[4..]);
+        if len > buf.len() {
+            skip_bytes(socket, len)?;
+            too_small = true;
+        } else {
+            recv_exact(socket, true, &mut buf[..len], &mut chunk)?;
+        }
+        (read_u32(&header), len)
+    } else {
+        let bufs = &mut[IoSliceMut::new(&mut header), IoSliceMut::new(buf)];
+        let info = recv_owned_fds(socket, None, bufs, &mut chunk)?;
+        let len = info.bytes.saturating_sub(FIRST_HEADER);
+        if info.bytes < FIRST_HEADER {
+            return Err(invalid("message was not sent with send_many_fds()"));
+        } else if info.message_truncated {
+            too_small = true;
+        } else if read_u32(&header[4..]) != len {
+            return Err(invalid("message was not sent with send_many_fds()"));
+        }
+        (read_u32(&header), len)
+    };
+
+    let mut received = 0;
+    let mut discarded = 0;
+    loop {
+        let expected = (total-received).min(FDS_PER_MESSAGE);
+        if chunk.len() != expected {
+            fds.truncate(stored_before);
+            return Err(invalid("received a different number of file descriptors than announced"));
+        }
+        received += expected;
+        for fd in chunk.take() {
+            if too_small  ||  !fds.push(fd) {
+                discarded += 1;
+            }
+        }
+        if received == total {
+            break;
+        }
+        let mut position = [0u8; CHUNK_HEADER];
+        if let Err(e) = recv_exact(socket, stream, &mut position, &mut chunk) {
+            fds.truncate(stored_before);
+            return Err(e);
+        }
+        if read_u32(&position) != received {
+            fds.truncate(stored_before);
+            return Err(invalid("file descriptors were received out of order"));
+        }
+    }
+
+    if too_small {
+        fds.truncate(stored_before);
+        return Err(io::Error::new(InvalidInput, "buffer is too small for the bytes"));
+    }
+    if discarded != 0  &&  fds.is_strict() {
+        fds.truncate(stored_before);
+        return Err(invalid("received more file descriptors than there was room for"));
+    }
+    Ok(RecvFdsInfo {
+        bytes: len,
+        message_truncated: false,
+        fds: fds.len() - stored_before,
+        fds_discarded: discarded,
+        ancillary_truncated: false,
+    })
+}
+
+/// Receives and discards `len` bytes from a stream,
+/// closing any file descriptors sent with them.
+fn skip_bytes(socket: RawFd,  mut len: usize) -> Result<(), io::Error> {
+    let mut scratch = [0u8; 1024];
+    let mut no_fds = ReceivedFds::with_capacity(0);
+    while len > 0 {
+        let size = len.min(scratch.len());
+        let bufs = &mut[IoSliceMut::new(&mut scratch[..size])];
+        let info = match recv_owned_fds(socket, None, bufs, &mut no_fds) {
+            Ok(info) => info,
+            Err(ref e) if e.kind() == Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if info.bytes == 0 {
+            return Err(io::Error::new(UnexpectedEof, "connection closed in the middle of a message"));
+        }
+        len -= info.bytes;
+    }
+    Ok(())
+}
+
+/// Fills `buf`, which on seqpacket sockets must be done by one packet.
+fn recv_exact(socket: RawFd,  stream: bool,  buf: &mut[u8],  fds: &mut ReceivedFds)
+-> Result<(), io::Error> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let bufs = &mut[IoSliceMut::new(&mut buf[filled..])];
+        let info = match recv_owned_fds(socket, None, bufs, fds) {
+            Ok(info) => info,
+            Err(ref e) if e.kind() == Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if info.bytes == 0 {
+            return Err(io::Error::new(UnexpectedEof, "connection closed in the middle of a message"));
+        }
+        // file descriptors are attached to the first byte of a message
+        let misplaced_fds = filled != 0  &&  info.fds != 0;
+        filled += info.bytes;
+        if info.message_truncated  ||  misplaced_fds  ||  (!stream  &&  filled != buf.len()) {
+            return Err(invalid("message was not sent with send_many_fds()"));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd, AsFd, BorrowedFd, OwnedFd};
+use std::io::{self, ErrorKind, ErrorKind::*};
+use std::num::NonZeroU32;
+use std::fs::File;
+use std::fmt::{self, Debug};
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::io::Read;
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::mem;
+
+use libc::close;
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{c_int, c_void, socklen_t, getsockopt, SOL_SOCKET, ENOPROTOOPT};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{poll, pollfd, POLLIN, open, O_RDONLY, O_DIRECTORY, O_CLOEXEC};
+
+#[cfg(any(target_os="linux", target_os="android"))]
+use crate::helpers::set_bool_option;
+
+// Not in libc for all supported versions, values are from asm/socket.h
+#[cfg(all(
+    any(target_os="linux", target_os="android"),
+    not(any(target_arch="sparc", target_arch="sparc64"))
+))]
+const SO_PASSPIDFD: c_int = 76;
+#[cfg(all(
+    any(target_os="linux", target_os="android"),
+    not(any(target_arch="sparc", target_arch="sparc64"))
+))]
+const SO_PEERPIDFD: c_int = 77;
+#[cfg(all(
+    any(target_os="linux", target_os="android"),
+    any(target_arch="sparc", target_arch="sparc64")
+))]
+const SO_PASSPIDFD: c_int = 0x55;
+#[cfg(all(
+    any(target_os="linux", target_os="android"),
+    any(target_arch="sparc", target_arch="sparc64")
+))]
+const SO_PEERPIDFD: c_int = 0x56;
+/// From linux/socket.h
+#[cfg(any(target_os="linux", target_os="android"))]
+pub(crate) const SCM_PIDFD: c_int = 0x04;
+
+/// Error returned by kernels without pidfd support for unix sockets.
+#[cfg(any(target_os="linux", target_os="android"))]
+fn kernel_unsupported(error: io::Error) -> io::Error {
+    if error.raw_os_error() == Some(ENOPROTOOPT) {
+        io::Error::new(Unsupported, "not supported by the kernel (requires Linux 6.5 or later)")
+    } else {
+        error
+    }
+}
+
+
+
+/// An owned file descriptor referring to a process.
+///
+/// Unlike a pid, a pidfd cannot be reused for another process after the
+/// process it refers to has terminated, which makes it safe to act on.
+///
+/// It can be polled (for example with mio or `poll()` through
+/// [`as_raw_fd()`](#method.as_raw_fd)), and becomes readable when the
+/// process exits.
+///
+/// Pidfds are only available on Linux (& Android) 5.3 and later,
+/// and sockets can only provide them on Linux 6.5 and later.
+pub struct PidFd {
+    fd: RawFd,
+}
+
+impl PidFd {
+    /// Gets the current pid of the process from `/proc/self/fdinfo`.
+    ///
+    /// Returns `None` if the process has exited or
+    /// is in a pid namespace that isn't visible from the current one.
+    pub fn pid(&self) -> Result<Option<NonZeroU32>, io::Error> {
+        #[cfg(any(target_os="linux", target_os="android"))] {
+            let mut fdinfo = String::new();
+            File::open(format!("/proc/self/fdinfo/{}", self.fd))?
+                .read_to_string(&mut fdinfo)?;
+            let pid = fdinfo.lines()
+                .find(|line| line.starts_with("Pid:") )
+                .and_then(|line| line["Pid:".len()..].trim().parse::<i64>().ok() )
+                .ok_or_else(|| io::Error::new(InvalidData, "fd is not a pidfd") )?;
+            // pid is -1 if the process has exited, and 0 if it's in another namespace
+            Ok(NonZeroU32::new(pid.max(0) as u32))
+        }
+        #[cfg(not(any(target_os="linux", target_os="android")))] {
+            Err(io::Error::new(Unsupported, "not available"))
+        }
+    }
+
+    /// Checks whether the process has exited, without blocking.
+    pub fn has_exited(&self) -> Result<bool, io::Error> {
+        #[cfg(any(target_os="linux", target_os="android"))] {
+            let mut fds = [pollfd { fd: self.fd, events: POLLIN, revents: 0 }];
+            let ready = cvt_r!(unsafe { poll(fds.as_mut_ptr(), 1, 0) })?;
+            Ok(ready > 0  &&  fds[0].revents & POLLIN != 0)
+        }
+        #[cfg(not(any(target_os="linux", target_os="android")))] {
+            Err(io::Error::new(Unsupported, "not available"))
+        }
+    }
+
+    /// Opens the `/proc/<pid>` directory of the process.
+    ///
+    /// The process is checked to still be alive after the directory has been
+    /// opened, so the directory cannot belong to another process that has
+    /// been given the same pid.
+    /// Files inside it can then be opened with `openat()`.
+    ///
+    /// Fails with `NotFound` if the process has exited.
+    pub fn open_proc_dir(&self) -> Result<File, io::Error> {
+        #[cfg(any(target_os="linux", target_os="android"))] {
+            let pid = match self.pid()? {
+                Some(pid) => pid,
+                None => return Err(io::Error::new(NotFound, "process has exited or is not visible")),
+            };
+            let path = format!("/proc/{}\0", pid);
+            let flags = O_RDONLY | O_DIRECTORY | O_CLOEXEC;
+            let dir = cvt_r!(unsafe { open(path.as_ptr() as *const _, flags) })?;
+            let dir = unsafe { File::from_raw_fd(dir) };
+            if self.has_exited()? {
+                return Err(io::Error::new(NotFound, "process has exited"));
+            }
+            Ok(dir)
+        }
+        #[cfg(not(any(target_os="linux", target_os="android")))] {
+            Err(io::Error::new(Unsupported, "not available"))
+        }
+    }
+}
+
+impl Debug for PidFd {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("PidFd")
+            .field("fd", &self.fd)
+            .finish()
+    }
+}
+
+impl FromRawFd for PidFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        PidFd { fd }
+    }
+}
+
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl IntoRawFd for PidFd {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.fd;
+        std::mem::forget(self);
+        fd
+    }
+}
+
+impl AsFd for PidFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        unsafe { BorrowedFd::borrow_raw(self.fd) }
+    }
+}
+
+impl From<OwnedFd> for PidFd {
+    fn from(fd: OwnedFd) -> Self {
+        PidFd { fd: fd.into_raw_fd() }
+    }
+}
+
+impl From<PidFd> for OwnedFd {
+    fn from(pidfd: PidFd) -> Self {
+        unsafe { OwnedFd::from_raw_fd(pidfd.into_raw_fd()) }
+    }
+}
+
+impl Drop for PidFd {
+    fn drop(&mut self) {
+        unsafe { close(self.fd) };
+    }
+}
+
+
+
+/// Safe wrapper around `getsockopt(SO_PEERPIDFD)`.
+#[cfg(any(target_os="linux", target_os="android"))]
+pub fn peer_pidfd(socket: RawFd) -> Result<PidFd, io::Error> {
+    unsafe {
+        let mut fd: c_int = -1;
+        let ptr = &mut fd as *mut c_int as *mut c_void;
+        let mut size = mem::size_of::<c_int>() as socklen_t;
+        if getsockopt(socket, SOL_SOCKET, SO_PEERPIDFD, ptr, &mut size) == -1 {
+            return Err(kernel_unsupported(io::Error::last_os_error()));
+        }
+        Ok(PidFd::from_raw_fd(fd))
+    }
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+pub fn peer_pidfd(_socket: RawFd) -> Result<PidFd, io::Error> {
+    Err(io::Error::new(Unsupported, "not available"))
+}
+
+/// Safe wrapper around `setsockopt(SO_PASSPIDFD)`.
+#[cfg(any(target_os="linux", target_os="android"))]
+pub fn set_pass_pidfd(socket: RawFd,  pass: bool) -> Result<(), io::Error> {
+    set_bool_option(socket, SOL_SOCKET, SO_PASSPIDFD, pass).map_err(kernel_unsupported)
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+pub fn set_pass_pidfd(_socket: RawFd,  _pass: bool) -> Result<(), io::Error> {
+    Err(io::Error::new(Unsupported, "not available"))
+}
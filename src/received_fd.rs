@@ -0,0 +1,303 @@
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd, AsFd, BorrowedFd, OwnedFd};
+use std::os::unix::net::{UnixStream, UnixListener, UnixDatagram};
+use std::net::TcpListener;
+use std::io::{self, ErrorKind::*};
+use std::fs::File;
+use std::mem;
+#[cfg(any(target_os="linux", target_os="android"))]
+use std::io::Read;
+
+use libc::{c_int, c_void, socklen_t, sockaddr, sockaddr_storage, getsockopt, getsockname, fstat};
+use libc::{SOL_SOCKET, SO_TYPE, SO_ACCEPTCONN, SOCK_STREAM, SOCK_DGRAM, SOCK_SEQPACKET};
+use libc::{AF_UNIX, AF_INET, AF_INET6};
+use libc::{S_IFMT, S_IFREG, S_IFDIR, S_IFIFO, S_IFSOCK, S_IFCHR, S_IFBLK};
+#[cfg(any(target_os="linux", target_os="android"))]
+use libc::{fcntl, F_GET_SEALS};
+
+use crate::seqpacket::{UnixSeqpacketConn, UnixSeqpacketListener};
+use crate::pidfd::PidFd;
+
+/// What a file descriptor refers to, as determined by [`ReceivedFd::kind()`](struct.ReceivedFd.html#method.kind).
+#[derive(Clone,Copy, PartialEq,Eq, Debug)]
+pub enum FdKind {
+    /// A regular file (which is not a memfd).
+    RegularFile,
+    /// An anonymous file created with `memfd_create()` (Linux & Android only).
+    Memfd,
+    /// A directory.
+    Directory,
+    /// A pipe or FIFO.
+    Pipe,
+    /// A connected or unconnected unix stream socket.
+    UnixStream,
+    /// A listening unix stream socket.
+    UnixListener,
+    /// A unix datagram socket.
+    UnixDatagram,
+    /// A connected or unconnected unix seqpacket socket.
+    UnixSeqpacket,
+    /// A listening unix seqpacket socket.
+    UnixSeqpacketListener,
+    /// A socket that is not a unix domain socket, such as a TCP socket.
+    OtherSocket,
+    /// An eventfd (Linux & Android only).
+    EventFd,
+    /// A pidfd (Linux & Android only).
+    PidFd,
+    /// A character device, such as a terminal.
+    CharacterDevice,
+    /// A block device.
+    BlockDevice,
+    /// Anything else.
+    Other,
+}
+
+/// A received file descriptor that can be inspected before it is trusted.
+///
+/// A peer can send any kind of file descriptor, so using one as a socket
+/// without checking could end up writing to a device or a directory.
+/// The `into_*()` methods only succeed if the file descriptor is of the
+/// expected [kind](enum.FdKind.html), and fail with `InvalidData` otherwise.
+///
+/// The file descriptor is closed when this type is dropped.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::{UnixSeqpacketConn, ReceivedFd, FdKind};
+/// use uds::ancillary::ReceivedFds;
+/// use std::os::unix::io::AsFd;
+///
+/// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+/// let (c, d) = UnixSeqpacketConn::pair().unwrap();
+/// a.send_borrowed_fds(b"connection", &[c.as_fd()]).unwrap();
+///
+/// let mut fds = ReceivedFds::with_capacity(1);
+/// b.recv_owned_fds(&mut[0; 16], &mut fds).unwrap();
+/// let received = ReceivedFd::from(fds.take().pop().unwrap());
+/// assert_eq!(received.kind().unwrap(), FdKind::UnixSeqpacket);
+/// let c = received.into_seqpacket_conn().unwrap();
+/// c.send(b"hello").unwrap();
+/// assert_eq!(d.recv(&mut[0; 16]).unwrap(), 5);
+/// ```
+#[derive(Debug)]
+pub struct ReceivedFd {
+    fd: OwnedFd,
+}
+
+impl ReceivedFd {
+    /// Determines what the file descriptor refers to.
+    ///
+    /// Memfds, eventfds and pidfds are only recognized on Linux and Android,
+    /// where `/proc` must be mounted to tell them apart from
+    /// regular files or unknown file descriptors.
+    pub fn kind(&self) -> Result<FdKind, io::Error> {
+        let fd = self.fd.as_raw_fd();
+        let mode = unsafe {
+            let mut stat = mem::zeroed();
+            cvt!(fstat(fd, &mut stat))?;
+            stat.st_mode
+        };
+        match mode & S_IFMT {
+            S_IFDIR => Ok(FdKind::Directory),
+            S_IFIFO => Ok(FdKind::Pipe),
+            S_IFCHR => Ok(FdKind::CharacterDevice),
+            S_IFBLK => Ok(FdKind::BlockDevice),
+            S_IFSOCK => socket_kind(fd),
+            file_type => {
+                #[cfg(any(target_os="linux", target_os="android"))] {
+                    if let Some(kind) = anon_inode_kind(fd)? {
+                        return Ok(kind);
+                    }
+                    if file_type == S_IFREG  &&  is_memfd(fd) {
+                        return Ok(FdKind::Memfd);
+                    }
+                }
+                if file_type == S_IFREG {
+                    Ok(FdKind::RegularFile)
+                } else {
+                    Ok(FdKind::Other)
+                }
+            }
+        }
+    }
+
+    fn expect(&self,  expected: &[FdKind],  desc: &'static str) -> Result<(), io::Error> {
+        if expected.contains(&self.kind()?) {
+            Ok(())
+        } else {
+            Err(io::Error::new(InvalidData, desc))
+        }
+    }
+
+    /// Converts to a `File` if the file descriptor is a regular file or a memfd.
+    pub fn into_file(self) -> Result<File, io::Error> {
+        self.expect(&[FdKind::RegularFile, FdKind::Memfd], "file descriptor is not a regular file")?;
+        Ok(File::from(self.fd))
+    }
+    /// Converts to a `UnixStream` if the file descriptor is a non-listening
+    /// unix stream socket.
+    pub fn into_unix_stream(self) -> Result<UnixStream, io::Error> {
+        self.expect(&[FdKind::UnixStream], "file descriptor is not a unix stream socket")?;
+        Ok(UnixStream::from(self.fd))
+    }
+    /// Converts to a `UnixListener` if the file descriptor is a listening
+    /// unix stream socket.
+    pub fn into_unix_listener(self) -> Result<UnixListener, io::Error> {
+        self.expect(&[FdKind::UnixListener], "file descriptor is not a unix stream listener")?;
+        Ok(UnixListener::from(self.fd))
+    }
+    /// Converts to a `UnixDatagram` if the file descriptor is a unix datagram socket.
+    pub fn into_unix_datagram(self) -> Result<UnixDatagram, io::Error> {
+        self.expect(&[FdKind::UnixDatagram], "file descriptor is not a unix datagram socket")?;
+        Ok(UnixDatagram::from(self.fd))
+    }
+    /// Converts to a `UnixSeqpacketConn` if the file descriptor is a
+    /// non-listening unix seqpacket socket.
+    pub fn into_seqpacket_conn(self) -> Result<UnixSeqpacketConn, io::Error> {
+        self.expect(&[FdKind::UnixSeqpacket], "file descriptor is not a unix seqpacket socket")?;
+        Ok(UnixSeqpacketConn::from(self.fd))
+    }
+    /// Converts to a `UnixSeqpacketListener` if the file descriptor is a
+    /// listening unix seqpacket socket.
+    pub fn into_seqpacket_listener(self) -> Result<UnixSeqpacketListener, io::Error> {
+        self.expect(
+            &[FdKind::UnixSeqpacketListener],
+            "file descriptor is not a unix seqpacket listener"
+        )?;
+        Ok(UnixSeqpacketListener::from(self.fd))
+    }
+    /// Converts to a `TcpListener` if the file descriptor is a listening
+    /// IPv4 or IPv6 stream socket.
+    pub fn into_tcp_listener(self) -> Result<TcpListener, io::Error> {
+        if !self.is_tcp_listener()? {
+            return Err(io::Error::new(InvalidData, "file descriptor is not a TCP listener"));
+        }
+        Ok(TcpListener::from(self.fd))
+    }
+    pub(crate) fn is_tcp_listener(&self) -> Result<bool, io::Error> {
+        let fd = self.fd.as_raw_fd();
+        Ok(self.kind()? == FdKind::OtherSocket
+            &&  [AF_INET, AF_INET6].contains(&socket_family(fd)?)
+            &&  int_option(fd, SO_TYPE)? == SOCK_STREAM
+            &&  int_option(fd, SO_ACCEPTCONN)? != 0)
+    }
+    /// Converts to a `PidFd` if the file descriptor is a pidfd.
+    pub fn into_pidfd(self) -> Result<PidFd, io::Error> {
+        self.expect(&[FdKind::PidFd], "file descriptor is not a pidfd")?;
+        Ok(PidFd::from(self.fd))
+    }
+    /// Returns the file descriptor without checking what it is.
+    pub fn into_owned_fd(self) -> OwnedFd {
+        self.fd
+    }
+}
+
+impl From<OwnedFd> for ReceivedFd {
+    fn from(fd: OwnedFd) -> Self {
+        ReceivedFd { fd }
+    }
+}
+
+impl From<ReceivedFd> for OwnedFd {
+    fn from(received: ReceivedFd) -> Self {
+        received.fd
+    }
+}
+
+impl FromRawFd for ReceivedFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        ReceivedFd { fd: OwnedFd::from_raw_fd(fd) }
+    }
+}
+
+impl AsRawFd for ReceivedFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for ReceivedFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.fd.into_raw_fd()
+    }
+}
+
+impl AsFd for ReceivedFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+
+
+fn int_option(socket: RawFd,  option: c_int) -> Result<c_int, io::Error> {
+    unsafe {
+        let mut value: c_int = 0;
+        let mut size = mem::size_of::<c_int>() as socklen_t;
+        let ptr = &mut value as *mut c_int as *mut c_void;
+        cvt!(getsockopt(socket, SOL_SOCKET, option, ptr, &mut size))?;
+        Ok(value)
+    }
+}
+
+fn socket_family(socket: RawFd) -> Result<c_int, io::Error> {
+    unsafe {
+        let mut addr: sockaddr_storage = mem::zeroed();
+        let mut len = mem::size_of::<sockaddr_storage>() as socklen_t;
+        let ptr = &mut addr as *mut sockaddr_storage as *mut sockaddr;
+        cvt!(getsockname(socket, ptr, &mut len))?;
+        Ok(addr.ss_family as c_int)
+    }
+}
+
+fn socket_kind(socket: RawFd) -> Result<FdKind, io::Error> {
+    if socket_family(socket)? != AF_UNIX {
+        return Ok(FdKind::OtherSocket);
+    }
+    let listening = int_option(socket, SO_ACCEPTCONN)? != 0;
+    match (int_option(socket, SO_TYPE)?, listening) {
+        (SOCK_STREAM, false) => Ok(FdKind::UnixStream),
+        (SOCK_STREAM, true) => Ok(FdKind::UnixListener),
+        (SOCK_DGRAM, _) => Ok(FdKind::UnixDatagram),
+        (SOCK_SEQPACKET, false) => Ok(FdKind::UnixSeqpacket),
+        (SOCK_SEQPACKET, true) => Ok(FdKind::UnixSeqpacketListener),
+        _ => Ok(FdKind::OtherSocket),
+    }
+}
+
+/// Recognizes eventfds and pidfds by the fields the kernel adds to fdinfo,
+/// as the file name could come from any file.
+///
+/// Returns `None` if /proc is not mounted.
+#[cfg(any(target_os="linux", target_os="android"))]
+fn anon_inode_kind(fd: RawFd) -> Result<Option<FdKind>, io::Error> {
+    let mut fdinfo = String::new();
+    match File::open(format!("/proc/self/fdinfo/{}", fd)) {
+        Ok(mut file) => file.read_to_string(&mut fdinfo)?,
+        Err(_) => return Ok(None),
+    };
+    for line in fdinfo.lines() {
+        if line.starts_with("eventfd-count:") {
+            return Ok(Some(FdKind::EventFd));
+        } else if line.starts_with("Pid:") {
+            return Ok(Some(FdKind::PidFd));
+        }
+    }
+    Ok(None)
+}
+
+/// Memfds support sealing and are named `/memfd:<name>`.
+#[cfg(any(target_os="linux", target_os="android"))]
+fn is_memfd(fd: RawFd) -> bool {
+    // tmpfs files also support sealing, but cannot be named /memfd:
+    // unless somebody can create files in the root directory.
+    if unsafe { fcntl(fd, F_GET_SEALS) } == -1 {
+        return false;
+    }
+    match std::fs::read_link(format!("/proc/self/fd/{}", fd)) {
+        Ok(path) => path.to_string_lossy().starts_with("/memfd:"),
+        Err(_) => false,
+    }
+}
@@ -1,9 +1,9 @@
 use std::io::{self, ErrorKind, IoSlice, IoSliceMut};
 use std::mem;
 use std::net::Shutdown;
-use std::os::unix::io::{RawFd, FromRawFd, AsRawFd, IntoRawFd};
+use std::os::unix::io::{RawFd, FromRawFd, AsRawFd, IntoRawFd, AsFd, BorrowedFd, OwnedFd};
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use libc::{SOCK_SEQPACKET, MSG_EOR, MSG_PEEK, c_void, close, send, recv};
 
@@ -20,6 +20,11 @@ use crate::addr::*;
 use crate::helpers::*;
 use crate::ancillary::*;
 use crate::credentials::*;
+use crate::pidfd::*;
+use crate::batch::*;
+use crate::large::*;
+use crate::many_fds::*;
+use crate::listener_builder::ListenerBuilder;
 
 /// Implements traits apropriate for any file-descriptor-wrapping type.
 macro_rules! impl_rawfd_traits {($type:tt) => {
@@ -40,6 +45,21 @@ macro_rules! impl_rawfd_traits {($type:tt) => {
             fd
         }
     }
+    impl AsFd for $type {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            unsafe { BorrowedFd::borrow_raw(self.fd) }
+        }
+    }
+    impl From<OwnedFd> for $type {
+        fn from(fd: OwnedFd) -> Self {
+            $type { fd: fd.into_raw_fd() }
+        }
+    }
+    impl From<$type> for OwnedFd {
+        fn from(socket: $type) -> Self {
+            unsafe { OwnedFd::from_raw_fd(socket.into_raw_fd()) }
+        }
+    }
     impl Drop for $type {
         fn drop(&mut self) {
             let _ = unsafe { close(self.fd) };
@@ -280,6 +300,17 @@ impl UnixSeqpacketConn {
     pub fn initial_peer_selinux_context(&self,  buf: &mut[u8]) -> Result<usize, io::Error> {
         selinux_context(self.as_raw_fd(), buf)
     }
+    /// Returns a pidfd referring to the process that created the other end
+    /// of this connection.
+    ///
+    /// Unlike the pid from `initial_peer_credentials()`, the pidfd cannot
+    /// start referring to another process if the peer exits.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn initial_peer_pidfd(&self) -> Result<PidFd, io::Error> {
+        peer_pidfd(self.fd)
+    }
 
 
     /// Sends a packet to the peer.
@@ -316,11 +347,295 @@ impl UnixSeqpacketConn {
     -> Result<usize, io::Error> {
         send_ancillary(self.fd, None, MSG_EOR, &[IoSlice::new(bytes)], fds, None)
     }
+    /// Sends a packet assembled from multiple byte slices,
+    /// together with the ancillary messages in `ancillary`.
+    pub fn send_ancillary(&self,  slices: &[IoSlice],  ancillary: &SendAncillary)
+    -> Result<usize, io::Error> {
+        send_ancillary_msgs(self.fd, None, MSG_EOR, slices, ancillary)
+    }
+    /// Sends a packet together with credentials which the receiver can
+    /// get with `recv_with_credentials()`.
+    ///
+    /// Credentials are only sent explicitly on Linux and Android,
+    /// and are ignored on other operating systems.
+    /// The receiver only gets them if it has enabled `set_pass_credentials()`.
+    pub fn send_with_credentials(&self,  bytes: &[u8],  creds: SendCredentials)
+    -> Result<usize, io::Error> {
+        send_ancillary(self.fd, None, MSG_EOR, &[IoSlice::new(bytes)], &[], Some(creds))
+    }
+    /// Sends multiple packets with one system call where supported.
+    ///
+    /// Returns the number of packets sent, which can be less than
+    /// `messages.len()` if an error occurs after some were sent.
+    /// Addresses of the messages are ignored.
+    ///
+    /// See the [`batch`](batch/index.html) module for details.
+    pub fn send_batch(&self,  messages: &[SendMessage]) -> Result<usize, io::Error> {
+        send_batch(self.fd, MSG_EOR, messages)
+    }
+    /// Receives multiple packets with one system call where supported.
+    ///
+    /// Blocks until at least one packet is available, and then also
+    /// receives the packets that are immediately available.
+    /// Returns the number of packets received, whose results are stored in
+    /// the first messages.
+    pub fn recv_batch(&self,  messages: &mut[RecvMessage]) -> Result<usize, io::Error> {
+        recv_batch(self.fd, messages)
+    }
     /// Receives a packet and associated file descriptors.
+    ///
+    /// File descriptors that don't fit in `fd_buffer` are closed,
+    /// which the returned [`RecvFdsInfo`](ancillary/struct.RecvFdsInfo.html) reports.
     pub fn recv_fds(&self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
-    -> Result<(usize, bool, usize), io::Error> {
+    -> Result<RecvFdsInfo, io::Error> {
         recv_fds(self.fd, None, &mut[IoSliceMut::new(byte_buffer)], fd_buffer)
     }
+    /// Sends a packet with file descriptors that are borrowed for the
+    /// duration of the call, such as `BorrowedFd`s or `&File`s.
+    pub fn send_borrowed_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F])
+    -> Result<usize, io::Error> {
+        send_borrowed_fds(self.fd, None, MSG_EOR, &[IoSlice::new(bytes)], fds)
+    }
+    /// Receives a packet and takes ownership of the associated file descriptors.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    pub fn recv_owned_fds(&self,  byte_buffer: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        recv_owned_fds(self.fd, None, &mut[IoSliceMut::new(byte_buffer)], fds)
+    }
+    /// Sends a payload of any size, which must be received with `recv_large()`.
+    ///
+    /// Payloads up to 32 KiB are sent inline, and bigger ones are written to
+    /// a memfd which is sealed against modification and sent with the packet.
+    /// This avoids the limit on packet size imposed by the send buffer.
+    ///
+    /// Payloads bigger than 32 KiB are only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+    #[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+    /// let (a, b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// let payload = vec![7u8; 10_000_000];
+    /// a.send_large(&payload).unwrap();
+    /// a.send_large(b"small").unwrap();
+    /// assert_eq!(b.recv_large(usize::MAX).unwrap(), payload);
+    /// assert_eq!(b.recv_large(100).unwrap(), b"small");
+    /// ```
+    pub fn send_large(&self,  bytes: &[u8]) -> Result<(), io::Error> {
+        send_large(self.fd, bytes)
+    }
+    /// Receives a payload sent with `send_large()`.
+    ///
+    /// The length of the payload is chosen by the sender, so payloads longer
+    /// than `max_len` are rejected before anything is allocated for them.
+    ///
+    /// Fails with `InvalidData` if the packet was not sent with `send_large()`,
+    /// if the payload is longer than `max_len`,
+    /// or if the memfd could still be modified by the sender.
+    /// Fails with `OutOfMemory` if memory for the payload cannot be allocated.
+    /// The packet is consumed in all these cases.
+    pub fn recv_large(&self,  max_len: usize) -> Result<Vec<u8>, io::Error> {
+        recv_large(self.fd, max_len)
+    }
+    /// Sends a packet with any number of file descriptors,
+    /// which must be received with `recv_many_fds()`.
+    ///
+    /// Linux limits how many file descriptors can be sent with one packet
+    /// (to 253), so they are split across as many packets as needed,
+    /// the first of which also carries the bytes.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::{UnixSeqpacketConn, ancillary::ReceivedFds};
+    /// use std::os::unix::io::AsFd;
+    ///
+    /// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    /// let fds = vec![a.as_fd(); 600];
+    /// a.send_many_fds(b"workers", &fds).unwrap();
+    /// let mut buf = [0; 16];
+    /// let mut received = ReceivedFds::with_capacity(1000);
+    /// let info = b.recv_many_fds(&mut buf, &mut received).unwrap();
+    /// assert_eq!(&buf[..info.bytes], b"workers");
+    /// assert_eq!((info.fds, info.fds_discarded), (600, 0));
+    /// assert_eq!(received.len(), 600);
+    /// ```
+    pub fn send_many_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F]) -> Result<(), io::Error> {
+        send_many_fds(self.fd, MSG_EOR, bytes, fds)
+    }
+    /// Receives bytes and file descriptors sent with `send_many_fds()`.
+    ///
+    /// The file descriptors are appended to `fds` in the order they were sent,
+    /// and any beyond its capacity are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    /// Returns the number of bytes, and how many file descriptors were stored
+    /// and how many were discarded.
+    ///
+    /// Fails with `InvalidInput` if `buf` is too small for the bytes, after
+    /// receiving the remaining packets and closing all the file descriptors.
+    /// Fails with `InvalidData` if the packets were not sent with `send_many_fds()`.
+    /// If an error happens after the first packet has been received,
+    /// no file descriptors are added to `fds`, but the remaining packets
+    /// might not have been received.
+    pub fn recv_many_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        recv_many_fds(self.fd, false, buf, fds)
+    }
+    /// Receives a packet and any ancillary messages sent with it.
+    ///
+    /// `ancillary_buf` should be aligned for `cmsghdr`,
+    /// which [`AncillaryBuf`](ancillary/struct.AncillaryBuf.html) is.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::{UnixSeqpacketConn, ancillary::{AncillaryBuf, AncillaryItem}};
+    /// use std::io::IoSliceMut;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    /// a.send_fds(b"stdin", &[std::io::stdin().as_raw_fd()]).unwrap();
+    /// let mut buf = [0u8; 10];
+    /// let mut ancillary_buf = AncillaryBuf::with_fd_capacity(2);
+    /// let (bytes, mut ancillary) = b.recv_ancillary(
+    ///     &mut[IoSliceMut::new(&mut buf)],
+    ///     &mut ancillary_buf,
+    /// ).unwrap();
+    /// assert_eq!(&buf[..bytes], b"stdin");
+    /// assert!(!ancillary.message_truncated());
+    /// assert!(!ancillary.ancillary_truncated());
+    /// match ancillary.next() {
+    ///     Some(AncillaryItem::Fds(fds)) => {
+    ///         assert_eq!(fds.len(), 1);
+    ///         unsafe { libc::close(fds[0]) };
+    ///     }
+    ///     _ => panic!("expected file descriptors"),
+    /// }
+    /// assert!(ancillary.next().is_none());
+    /// ```
+    pub fn recv_ancillary<'a>(&self,
+            buffers: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>), io::Error> {
+        recv_ancillary(self.fd, None, 0, buffers, ancillary_buf)
+    }
+    /// Receives a packet and the credentials of the process that sent it.
+    ///
+    /// The returned `bool` indicates whether the packet was truncated due to
+    /// too short buffer.
+    /// The credentials are `None` unless `set_pass_credentials(true)` has been
+    /// called on the receiving socket, as Linux only attaches them then,
+    /// even if the sender sent some explicitly.
+    /// Any file descriptors sent with the packet are closed.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+    #[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+    /// let (a, b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// b.set_pass_credentials(true).unwrap();
+    /// a.send(b"who am I?").unwrap();
+    /// let mut buf = [0u8; 16];
+    /// let (len, truncated, creds) = b.recv_with_credentials(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], b"who am I?");
+    /// assert!(!truncated);
+    /// let creds = creds.expect("kernel attaches credentials");
+    /// assert_eq!(creds.pid(), Some(std::process::id()));
+    /// ```
+    pub fn recv_with_credentials(&self,  buffer: &mut[u8])
+    -> Result<(usize, bool, Option<ReceivedCredentials>), io::Error> {
+        recv_credentials(self.fd, None, &mut[IoSliceMut::new(buffer)])
+    }
+    /// Enables or disables receiving credentials of the sender with every packet.
+    ///
+    /// When enabled, Linux attaches the credentials of the sending process to
+    /// every packet, even if the sender doesn't send any explicitly.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    pub fn set_pass_credentials(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_credentials(self.fd, pass)
+    }
+    /// Receives a packet and the security context of the process that sent it.
+    ///
+    /// The returned `bool` indicates whether the packet was truncated due to
+    /// too short buffer, and the last `usize` is the length of the context
+    /// stored in `context_buf`, which is zero if none was received.
+    /// Any file descriptors sent with the packet are closed.
+    ///
+    /// If `context_buf` is too short for the context, the packet is consumed
+    /// but an error of kind `InvalidInput` is returned.
+    ///
+    /// The context is only received on Linux and Android, and only if
+    /// `set_pass_security()` has been enabled and a security module which
+    /// provides labels (such as SELinux or Smack) is active.
+    /// An error is returned on other operating systems.
+    pub fn recv_with_security_context(&self,  buffer: &mut[u8],  context_buf: &mut[u8])
+    -> Result<(usize, bool, usize), io::Error> {
+        recv_security_context(self.fd, None, &mut[IoSliceMut::new(buffer)], context_buf)
+    }
+    /// Enables or disables receiving the security context of the sender
+    /// with every packet.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    pub fn set_pass_security(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_security(self.fd, pass)
+    }
+    /// Enables or disables receiving a pidfd of the sender with every packet,
+    /// as `AncillaryItem::Pidfd` from `recv_ancillary()`.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn set_pass_pidfd(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_pidfd(self.fd, pass)
+    }
+    /// Receives a packet and the time it was received by the kernel.
+    ///
+    /// The returned `bool` indicates whether the packet was truncated due to
+    /// too short buffer.
+    /// The timestamp is `None` unless `set_receive_timestamps()` was enabled
+    /// before the packet was sent, and any file descriptors sent with the
+    /// packet are closed.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(any(target_vendor="apple", target_os="illumos", target_os="solaris")), doc="```")]
+    #[cfg_attr(any(target_vendor="apple", target_os="illumos", target_os="solaris"), doc="```no_run")]
+    /// use std::time::SystemTime;
+    ///
+    /// let (a, b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// b.set_receive_timestamps(true).unwrap();
+    /// let before = SystemTime::now();
+    /// a.send(b"when?").unwrap();
+    /// let mut buf = [0u8; 10];
+    /// let (len, truncated, timestamp) = b.recv_timestamped(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], b"when?");
+    /// assert!(!truncated);
+    /// assert!(timestamp.unwrap() >= before);
+    /// ```
+    pub fn recv_timestamped(&self,  buffer: &mut[u8])
+    -> Result<(usize, bool, Option<SystemTime>), io::Error> {
+        recv_timestamp(self.fd, None, &mut[IoSliceMut::new(buffer)])
+    }
+    /// Enables or disables timestamping of received packets.
+    ///
+    /// This sets `SO_TIMESTAMPNS` on Linux and Android,
+    /// and `SO_TIMESTAMP` (with microsecond precision) on other operating systems.
+    pub fn set_receive_timestamps(&self,  enable: bool) -> Result<(), io::Error> {
+        set_receive_timestamps(self.fd, enable)
+    }
     /// Receives a packet without removing it from the incoming queue.
     ///
     /// # Examples
@@ -352,6 +667,77 @@ impl UnixSeqpacketConn {
         recv_ancillary(self.fd, None, MSG_PEEK, buffers, &mut[])
             .map(|(bytes, ancillary)| (bytes, ancillary.message_truncated()) )
     }
+    /// Receives a packet and its ancillary messages without removing it
+    /// from the incoming queue.
+    ///
+    /// Any file descriptors are closed before this returns, and are replaced
+    /// by `-1` in [`AncillaryItem::Fds`](ancillary/enum.AncillaryItem.html#variant.Fds),
+    /// so they can be counted but not used.
+    /// They can then be received with `recv_fds()` or similar.
+    /// Pidfds enabled with `set_pass_pidfd()` are also closed, and are not
+    /// produced as [`AncillaryItem::Pidfd`](ancillary/enum.AncillaryItem.html#variant.Pidfd)
+    /// by peeks, only when the packet is received.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::{UnixSeqpacketConn, ancillary::{AncillaryBuf, AncillaryItem}};
+    /// use std::io::IoSliceMut;
+    /// use std::os::unix::io::AsFd;
+    ///
+    /// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    /// a.send_borrowed_fds(b"job 1", &[a.as_fd(), a.as_fd()]).unwrap();
+    /// let mut header = [0u8; 3];
+    /// let mut ancillary_buf = AncillaryBuf::with_fd_capacity(2);
+    /// let (_, ancillary) = b.peek_ancillary(
+    ///     &mut[IoSliceMut::new(&mut header)],
+    ///     &mut ancillary_buf,
+    /// ).unwrap();
+    /// let num_fds = ancillary.map(|item| match item {
+    ///     AncillaryItem::Fds(fds) => fds.len(),
+    ///     _ => 0,
+    /// }).sum::<usize>();
+    /// assert_eq!(&header, b"job");
+    /// assert_eq!(num_fds, 2);
+    /// // the packet is still there
+    /// assert_eq!(b.recv_fds(&mut[0; 10], &mut[-1; 2]).unwrap().fds, 2);
+    /// ```
+    pub fn peek_ancillary<'a>(&self,
+            buffers: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>), io::Error> {
+        peek_ancillary(self.fd, None, buffers, ancillary_buf)
+    }
+    /// Returns the length of the next packet without receiving it.
+    ///
+    /// Linux can report the length directly,
+    /// but on other operating systems the packet is peeked with
+    /// increasingly bigger buffers until it fits.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// let (a, b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// a.send(&[1; 5000]).unwrap();
+    /// assert_eq!(b.peek_len().unwrap(), 5000);
+    /// let packet = b.recv_to_vec().unwrap();
+    /// assert_eq!(packet, [1; 5000]);
+    /// ```
+    pub fn peek_len(&self) -> Result<usize, io::Error> {
+        peek_len(self.fd)
+    }
+    /// Receives a packet into a `Vec` that is exactly as big as the packet,
+    /// so that it's never truncated.
+    ///
+    /// Any file descriptors sent with the packet are closed.
+    /// If another thread receives the peeked packet first and the next one
+    /// is bigger, an error of kind `Other` is returned
+    /// and the bigger packet is truncated.
+    pub fn recv_to_vec(&self) -> Result<Vec<u8>, io::Error> {
+        recv_to_vec(self.fd)
+    }
 
     /// Returns the value of the `SO_ERROR` option.
     ///
@@ -373,6 +759,82 @@ impl UnixSeqpacketConn {
         take_error(self.fd)
     }
 
+    /// Sets the size of the send buffer, which limits how many bytes
+    /// can be queued before `send()` blocks.
+    ///
+    /// The OS might adjust the size; Linux doubles it to make room for
+    /// bookkeeping overhead and has a minimum.
+    /// Use `send_buffer_size()` to get the value that was used.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// let (a, _b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// a.set_send_buffer_size(64*1024).unwrap();
+    /// assert!(a.send_buffer_size().unwrap() >= 64*1024);
+    /// ```
+    pub fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.fd, BufferDirection::SEND, size)
+    }
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.fd, BufferDirection::SEND)
+    }
+    /// Sets the size of the receive buffer.
+    ///
+    /// Linux ignores the receive buffer of unix sockets: how much can be
+    /// queued is instead limited by the send buffer of the peer,
+    /// and for datagram sockets also by `net.unix.max_dgram_qlen`.
+    /// The value is still stored and returned by `recv_buffer_size()`.
+    pub fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.fd, BufferDirection::RECV, size)
+    }
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.fd, BufferDirection::RECV)
+    }
+    /// Returns the number of bytes in packets that have been received
+    /// but not read yet. (`FIONREAD` / `SIOCINQ`)
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// let (a, b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// assert_eq!(b.pending_incoming().unwrap(), 0);
+    /// a.send(b"one").unwrap();
+    /// a.send(b"two").unwrap();
+    /// assert_eq!(b.pending_incoming().unwrap(), 6);
+    /// ```
+    pub fn pending_incoming(&self) -> Result<usize, io::Error> {
+        pending_incoming(self.fd)
+    }
+    /// Returns the number of bytes sent but not yet read by the peer.
+    /// (`SIOCOUTQ` on Linux and Android)
+    ///
+    /// The count can include some bookkeeping overhead per packet,
+    /// so it's only useful for comparing against the send buffer size
+    /// or for checking that everything has been read.
+    ///
+    /// # Operating System Support
+    ///
+    /// Only available on Linux, Android, FreeBSD and macOS.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+    #[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+    /// let (a, b) = uds::UnixSeqpacketConn::pair().unwrap();
+    /// a.send(b"queued").unwrap();
+    /// assert!(a.pending_outgoing().unwrap() >= 6);
+    /// b.recv(&mut[0; 10]).unwrap();
+    /// assert_eq!(a.pending_outgoing().unwrap(), 0);
+    /// ```
+    pub fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        pending_outgoing(self.fd)
+    }
+
 
     /// Creates a new file descriptor also pointing to this side of this connection.
     ///
@@ -605,6 +1067,30 @@ impl UnixSeqpacketListener {
         socket.start_listening()?;
         Ok(UnixSeqpacketListener { fd: socket.into_raw_fd() })
     }
+    /// Creates a builder for setting the backlog, the permissions of
+    /// the socket file or removing a leftover socket file before binding.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::{UnixSeqpacketListener, UnixSeqpacketConn};
+    ///
+    /// # let _ = std::fs::remove_file("seqpacket_stale.sock");
+    /// let listener = UnixSeqpacketListener::bind("seqpacket_stale.sock").unwrap();
+    /// drop(listener); // leaves the file behind
+    /// assert!(UnixSeqpacketListener::bind("seqpacket_stale.sock").is_err());
+    ///
+    /// let listener = UnixSeqpacketListener::builder()
+    ///     .unlink_if_stale(true)
+    ///     .bind("seqpacket_stale.sock")
+    ///     .unwrap();
+    /// let _conn = UnixSeqpacketConn::connect("seqpacket_stale.sock").unwrap();
+    /// # let _ = std::fs::remove_file("seqpacket_stale.sock");
+    /// ```
+    pub fn builder() -> ListenerBuilder<Self> {
+        ListenerBuilder::new(SOCK_SEQPACKET, false)
+    }
 
     /// Returns the address the socket is listening on.
     pub fn local_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
@@ -882,6 +1368,17 @@ impl NonblockingUnixSeqpacketConn {
     pub fn initial_peer_selinux_context(&self,  buf: &mut[u8]) -> Result<usize, io::Error> {
         selinux_context(self.as_raw_fd(), buf)
     }
+    /// Returns a pidfd referring to the process that created the other end
+    /// of this connection.
+    ///
+    /// Unlike the pid from `initial_peer_credentials()`, the pidfd cannot
+    /// start referring to another process if the peer exits.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn initial_peer_pidfd(&self) -> Result<PidFd, io::Error> {
+        peer_pidfd(self.fd)
+    }
 
     /// Sends a packet to the peer.
     pub fn send(&self,  packet: &[u8]) -> Result<usize, io::Error> {
@@ -917,11 +1414,197 @@ impl NonblockingUnixSeqpacketConn {
     -> Result<usize, io::Error> {
         send_ancillary(self.fd, None, MSG_EOR, &[IoSlice::new(bytes)], fds, None)
     }
+    /// Sends a packet assembled from multiple byte slices,
+    /// together with the ancillary messages in `ancillary`.
+    pub fn send_ancillary(&self,  slices: &[IoSlice],  ancillary: &SendAncillary)
+    -> Result<usize, io::Error> {
+        send_ancillary_msgs(self.fd, None, MSG_EOR, slices, ancillary)
+    }
+    /// Sends a packet together with credentials which the receiver can
+    /// get with `recv_with_credentials()`.
+    ///
+    /// Credentials are only sent explicitly on Linux and Android,
+    /// and are ignored on other operating systems.
+    /// The receiver only gets them if it has enabled `set_pass_credentials()`.
+    pub fn send_with_credentials(&self,  bytes: &[u8],  creds: SendCredentials)
+    -> Result<usize, io::Error> {
+        send_ancillary(self.fd, None, MSG_EOR, &[IoSlice::new(bytes)], &[], Some(creds))
+    }
+    /// Sends multiple packets with one system call where supported.
+    ///
+    /// Returns the number of packets sent, which can be less than
+    /// `messages.len()` if an error occurs after some were sent.
+    /// Addresses of the messages are ignored.
+    ///
+    /// See the [`batch`](../batch/index.html) module for details.
+    pub fn send_batch(&self,  messages: &[SendMessage]) -> Result<usize, io::Error> {
+        send_batch(self.fd, MSG_EOR, messages)
+    }
+    /// Receives multiple packets with one system call where supported.
+    ///
+    /// Fails with `WouldBlock` if no packets are available, and otherwise
+    /// receives the packets that are immediately available.
+    /// Returns the number of packets received, whose results are stored in
+    /// the first messages.
+    pub fn recv_batch(&self,  messages: &mut[RecvMessage]) -> Result<usize, io::Error> {
+        recv_batch(self.fd, messages)
+    }
     /// Receives a packet and associated file descriptors.
+    ///
+    /// File descriptors that don't fit in `fd_buffer` are closed,
+    /// which the returned [`RecvFdsInfo`](../ancillary/struct.RecvFdsInfo.html) reports.
     pub fn recv_fds(&self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
-    -> Result<(usize, bool, usize), io::Error> {
+    -> Result<RecvFdsInfo, io::Error> {
         recv_fds(self.fd, None, &mut[IoSliceMut::new(byte_buffer)], fd_buffer)
     }
+    /// Sends a packet with file descriptors that are borrowed for the
+    /// duration of the call, such as `BorrowedFd`s or `&File`s.
+    pub fn send_borrowed_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F])
+    -> Result<usize, io::Error> {
+        send_borrowed_fds(self.fd, None, MSG_EOR, &[IoSlice::new(bytes)], fds)
+    }
+    /// Receives a packet and takes ownership of the associated file descriptors.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    pub fn recv_owned_fds(&self,  byte_buffer: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        recv_owned_fds(self.fd, None, &mut[IoSliceMut::new(byte_buffer)], fds)
+    }
+    /// Sends a payload of any size, which must be received with `recv_large()`.
+    ///
+    /// Payloads up to 32 KiB are sent inline, and bigger ones are written to
+    /// a memfd which is sealed against modification and sent with the packet.
+    ///
+    /// Payloads bigger than 32 KiB are only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    pub fn send_large(&self,  bytes: &[u8]) -> Result<(), io::Error> {
+        send_large(self.fd, bytes)
+    }
+    /// Receives a payload sent with `send_large()`.
+    ///
+    /// The length of the payload is chosen by the sender, so payloads longer
+    /// than `max_len` are rejected before anything is allocated for them.
+    ///
+    /// Fails with `InvalidData` if the packet was not sent with `send_large()`,
+    /// if the payload is longer than `max_len`,
+    /// or if the memfd could still be modified by the sender.
+    /// Fails with `OutOfMemory` if memory for the payload cannot be allocated.
+    /// The packet is consumed in all these cases.
+    pub fn recv_large(&self,  max_len: usize) -> Result<Vec<u8>, io::Error> {
+        recv_large(self.fd, max_len)
+    }
+    /// Receives a packet and any ancillary messages sent with it.
+    ///
+    /// `ancillary_buf` should be aligned for `cmsghdr`,
+    /// which [`AncillaryBuf`](../ancillary/struct.AncillaryBuf.html) is.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// # use std::io::{IoSliceMut, ErrorKind::*};
+    /// use uds::{nonblocking::UnixSeqpacketConn, ancillary::AncillaryBuf};
+    ///
+    /// let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    /// let mut buf = [0u8; 10];
+    /// let mut ancillary_buf = AncillaryBuf::with_fd_capacity(1);
+    /// let error = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+    ///     .unwrap_err();
+    /// assert_eq!(error.kind(), WouldBlock);
+    /// a.send(b"no fds").unwrap();
+    /// let (bytes, mut ancillary) = b.recv_ancillary(
+    ///     &mut[IoSliceMut::new(&mut buf)],
+    ///     &mut ancillary_buf,
+    /// ).unwrap();
+    /// assert_eq!(&buf[..bytes], b"no fds");
+    /// assert!(ancillary.next().is_none());
+    /// ```
+    pub fn recv_ancillary<'a>(&self,
+            buffers: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>), io::Error> {
+        recv_ancillary(self.fd, None, 0, buffers, ancillary_buf)
+    }
+    /// Receives a packet and the credentials of the process that sent it.
+    ///
+    /// The returned `bool` indicates whether the packet was truncated due to
+    /// too short buffer.
+    /// The credentials are `None` unless `set_pass_credentials(true)` has been
+    /// called on the receiving socket, as Linux only attaches them then,
+    /// even if the sender sent some explicitly.
+    /// Any file descriptors sent with the packet are closed.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    pub fn recv_with_credentials(&self,  buffer: &mut[u8])
+    -> Result<(usize, bool, Option<ReceivedCredentials>), io::Error> {
+        recv_credentials(self.fd, None, &mut[IoSliceMut::new(buffer)])
+    }
+    /// Enables or disables receiving credentials of the sender with every packet.
+    ///
+    /// When enabled, Linux attaches the credentials of the sending process to
+    /// every packet, even if the sender doesn't send any explicitly.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    pub fn set_pass_credentials(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_credentials(self.fd, pass)
+    }
+    /// Receives a packet and the security context of the process that sent it.
+    ///
+    /// The returned `bool` indicates whether the packet was truncated due to
+    /// too short buffer, and the last `usize` is the length of the context
+    /// stored in `context_buf`, which is zero if none was received.
+    /// Any file descriptors sent with the packet are closed.
+    ///
+    /// If `context_buf` is too short for the context, the packet is consumed
+    /// but an error of kind `InvalidInput` is returned.
+    ///
+    /// The context is only received on Linux and Android, and only if
+    /// `set_pass_security()` has been enabled and a security module which
+    /// provides labels (such as SELinux or Smack) is active.
+    /// An error is returned on other operating systems.
+    pub fn recv_with_security_context(&self,  buffer: &mut[u8],  context_buf: &mut[u8])
+    -> Result<(usize, bool, usize), io::Error> {
+        recv_security_context(self.fd, None, &mut[IoSliceMut::new(buffer)], context_buf)
+    }
+    /// Enables or disables receiving the security context of the sender
+    /// with every packet.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    pub fn set_pass_security(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_security(self.fd, pass)
+    }
+    /// Enables or disables receiving a pidfd of the sender with every packet,
+    /// as `AncillaryItem::Pidfd` from `recv_ancillary()`.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn set_pass_pidfd(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_pidfd(self.fd, pass)
+    }
+    /// Receives a packet and the time it was received by the kernel.
+    ///
+    /// The returned `bool` indicates whether the packet was truncated due to
+    /// too short buffer.
+    /// The timestamp is `None` unless `set_receive_timestamps()` was enabled
+    /// before the packet was sent, and any file descriptors sent with the
+    /// packet are closed.
+    pub fn recv_timestamped(&self,  buffer: &mut[u8])
+    -> Result<(usize, bool, Option<SystemTime>), io::Error> {
+        recv_timestamp(self.fd, None, &mut[IoSliceMut::new(buffer)])
+    }
+    /// Enables or disables timestamping of received packets.
+    ///
+    /// This sets `SO_TIMESTAMPNS` on Linux and Android,
+    /// and `SO_TIMESTAMP` (with microsecond precision) on other operating systems.
+    pub fn set_receive_timestamps(&self,  enable: bool) -> Result<(), io::Error> {
+        set_receive_timestamps(self.fd, enable)
+    }
     /// Receives a packet without removing it from the incoming queue.
     ///
     /// # Examples
@@ -954,6 +1637,45 @@ impl NonblockingUnixSeqpacketConn {
         recv_ancillary(self.fd, None, MSG_PEEK, buffers, &mut[])
             .map(|(bytes, ancillary)| (bytes, ancillary.message_truncated()) )
     }
+    /// Receives a packet and its ancillary messages without removing it
+    /// from the incoming queue.
+    ///
+    /// Any file descriptors are closed before this returns, and are replaced
+    /// by `-1` in [`AncillaryItem::Fds`](../ancillary/enum.AncillaryItem.html#variant.Fds),
+    /// so they can be counted but not used.
+    /// Pidfds are also closed, and are only produced when the packet is received.
+    pub fn peek_ancillary<'a>(&self,
+            buffers: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>), io::Error> {
+        peek_ancillary(self.fd, None, buffers, ancillary_buf)
+    }
+    /// Returns the length of the next packet without receiving it.
+    ///
+    /// Linux can report the length directly,
+    /// but on other operating systems the packet is peeked with
+    /// increasingly bigger buffers until it fits.
+    pub fn peek_len(&self) -> Result<usize, io::Error> {
+        peek_len(self.fd)
+    }
+    /// Receives a packet into a `Vec` that is exactly as big as the packet,
+    /// so that it's never truncated.
+    ///
+    /// Any file descriptors sent with the packet are closed.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// # use std::io::ErrorKind::*;
+    /// let (a, b) = uds::nonblocking::UnixSeqpacketConn::pair().unwrap();
+    /// assert_eq!(b.recv_to_vec().unwrap_err().kind(), WouldBlock);
+    /// a.send(b"whole").unwrap();
+    /// assert_eq!(b.recv_to_vec().unwrap(), b"whole");
+    /// ```
+    pub fn recv_to_vec(&self) -> Result<Vec<u8>, io::Error> {
+        recv_to_vec(self.fd)
+    }
 
     /// Returns the value of the `SO_ERROR` option.
     ///
@@ -974,6 +1696,54 @@ impl NonblockingUnixSeqpacketConn {
         take_error(self.fd)
     }
 
+    /// Sets the size of the send buffer, which limits how many bytes
+    /// can be queued before `send()` fails with `WouldBlock`.
+    ///
+    /// The OS might adjust the size, see
+    /// [`UnixSeqpacketConn::set_send_buffer_size()`](../struct.UnixSeqpacketConn.html#method.set_send_buffer_size).
+    pub fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.fd, BufferDirection::SEND, size)
+    }
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.fd, BufferDirection::SEND)
+    }
+    /// Sets the size of the receive buffer.
+    ///
+    /// This has no effect on Linux, see
+    /// [`UnixSeqpacketConn::set_recv_buffer_size()`](../struct.UnixSeqpacketConn.html#method.set_recv_buffer_size).
+    pub fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.fd, BufferDirection::RECV, size)
+    }
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.fd, BufferDirection::RECV)
+    }
+    /// Returns the number of bytes in packets that have been received
+    /// but not read yet. (`FIONREAD` / `SIOCINQ`)
+    pub fn pending_incoming(&self) -> Result<usize, io::Error> {
+        pending_incoming(self.fd)
+    }
+    /// Returns the number of bytes sent but not yet read by the peer,
+    /// including some bookkeeping overhead per packet.
+    ///
+    /// Only available on Linux, Android, FreeBSD and macOS.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+    #[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+    /// let (a, b) = uds::nonblocking::UnixSeqpacketConn::pair().unwrap();
+    /// a.set_send_buffer_size(4096).unwrap();
+    /// let limit = a.send_buffer_size().unwrap();
+    /// while a.send(&[0; 1000]).is_ok() {}
+    /// assert!(a.pending_outgoing().unwrap() > limit / 2);
+    /// assert!(b.pending_incoming().unwrap() >= 1000);
+    /// ```
+    pub fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        pending_outgoing(self.fd)
+    }
+
 
     /// Creates a new file descriptor also pointing to this side of this connection.
     ///
@@ -1099,6 +1869,11 @@ impl NonblockingUnixSeqpacketListener {
         socket.start_listening()?;
         Ok(NonblockingUnixSeqpacketListener { fd: socket.into_raw_fd() })
     }
+    /// Creates a builder for setting the backlog, the permissions of
+    /// the socket file or removing a leftover socket file before binding.
+    pub fn builder() -> ListenerBuilder<Self> {
+        ListenerBuilder::new(SOCK_SEQPACKET, true)
+    }
 
     /// Returns the address this listener was bound to.
     pub fn local_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
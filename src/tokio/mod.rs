@@ -1,4 +1,6 @@
 //! Provides support for `SEQPACKET` sockets in Tokio.
 
 mod seqpacket;
+mod split;
 pub use seqpacket::*;
+pub use split::*;
@@ -1,8 +1,10 @@
-use crate::{nonblocking, UnixSocketAddr, ConnCredentials};
+use crate::{nonblocking, UnixSocketAddr, ConnCredentials, PidFd};
+use crate::ancillary::{SendAncillary, ReceivedFds, RecvFdsInfo};
+use crate::batch::{SendMessage, RecvMessage};
 use futures::{future::poll_fn, ready};
 use std::io::{self, ErrorKind, IoSlice, IoSliceMut};
 use std::net::Shutdown;
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd, AsFd, BorrowedFd};
 use std::path::Path;
 use std::task::{Context, Poll};
 use tokio_02::io::PollEvented;
@@ -99,6 +101,14 @@ impl UnixSeqpacketConn {
     pub fn initial_peer_credentials(&self) -> Result<ConnCredentials, io::Error> {
         self.io.get_ref().initial_peer_credentials()
     }
+    /// Returns a pidfd referring to the process that created the other end
+    /// of this connection.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn initial_peer_pidfd(&self) -> Result<PidFd, io::Error> {
+        self.io.get_ref().initial_peer_pidfd()
+    }
     /// Returns the SELinux security context of the process that created the other
     /// end of this connection.
     ///
@@ -116,6 +126,34 @@ impl UnixSeqpacketConn {
     pub fn take_error(&self) -> Result<Option<io::Error>, io::Error> {
         self.io.get_ref().take_error()
     }
+
+    /// Sets the size of the send buffer.
+    pub fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        self.io.get_ref().set_send_buffer_size(size)
+    }
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().send_buffer_size()
+    }
+    /// Sets the size of the receive buffer.
+    pub fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        self.io.get_ref().set_recv_buffer_size(size)
+    }
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().recv_buffer_size()
+    }
+    /// Returns the number of bytes in packets that have been received
+    /// but not read yet.
+    pub fn pending_incoming(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().pending_incoming()
+    }
+    /// Returns the number of bytes sent but not yet read by the peer.
+    ///
+    /// Only available on Linux, Android, FreeBSD and macOS.
+    pub fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().pending_outgoing()
+    }
 }
 
 impl UnixSeqpacketConn {
@@ -129,11 +167,13 @@ impl UnixSeqpacketConn {
     }
 
     /// Sends a packet assembled from multiple byte slices.
+    #[allow(clippy::needless_lifetimes)]
     pub async fn send_vectored<'a, 'b>
     (&'a mut self,  slices: &'b [IoSlice<'b>]) -> io::Result<usize> {
         poll_fn(|cx| self.poll_send_priv(cx, |conn| conn.send_vectored(slices) ) ).await
     }
     /// Receives a packet and places the bytes across multiple buffers.
+    #[allow(clippy::needless_lifetimes)]
     pub async fn recv_vectored<'a, 'b>
     (&'a mut self,  buffers: &'b mut [IoSliceMut<'b>]) -> io::Result<usize> {
         poll_fn(|cx| {
@@ -149,6 +189,7 @@ impl UnixSeqpacketConn {
         poll_fn(|cx| self.poll_recv_priv(cx, |conn| conn.peek(buffer) ) ).await
     }
     /// Reads a packet into multiple buffers without removing it from the incoming queue.
+    #[allow(clippy::needless_lifetimes)]
     pub async fn peek_vectored<'a, 'b>
     (&'a mut self,  buffers: &'b mut [IoSliceMut<'b>]) -> io::Result<usize> {
         poll_fn(|cx| {
@@ -158,16 +199,56 @@ impl UnixSeqpacketConn {
             )
         }).await
     }
+    /// Returns the length of the next packet without receiving it.
+    pub async fn peek_len(&mut self) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_recv_priv(cx, |conn| conn.peek_len() ) ).await
+    }
+    /// Receives a packet into a `Vec` that is exactly as big as the packet.
+    pub async fn recv_to_vec(&mut self) -> io::Result<Vec<u8>> {
+        poll_fn(|cx| self.poll_recv_priv(cx, |conn| conn.recv_to_vec() ) ).await
+    }
 
     /// Sends a packet with associated file descriptors.
     pub async fn send_fds(&mut self,  bytes: &[u8],  fds: &[RawFd]) -> io::Result<usize> {
         poll_fn(|cx| self.poll_send_priv(cx, |conn| conn.send_fds(bytes, fds) ) ).await
     }
+    /// Sends a packet assembled from multiple byte slices,
+    /// together with the ancillary messages in `ancillary`.
+    pub async fn send_ancillary(&mut self,  slices: &[IoSlice<'_>],  ancillary: &SendAncillary)
+    -> io::Result<usize> {
+        poll_fn(|cx| self.poll_send_priv(cx, |conn| conn.send_ancillary(slices, ancillary) ) ).await
+    }
     /// Receives a packet and associated file descriptors.
     pub async fn recv_fds(&mut self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
-    -> io::Result<(usize, bool, usize)> {
+    -> io::Result<RecvFdsInfo> {
         poll_fn(|cx| self.poll_recv_priv(cx, |conn| conn.recv_fds(byte_buffer, fd_buffer) ) ).await
     }
+    /// Sends multiple packets with one system call where supported,
+    /// and returns how many were sent.
+    pub async fn send_batch(&mut self,  messages: &[SendMessage<'_>]) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_send_priv(cx, |conn| conn.send_batch(messages) ) ).await
+    }
+    /// Receives the packets that are available with one system call where
+    /// supported, and returns how many were received.
+    pub async fn recv_batch(&mut self,  messages: &mut[RecvMessage<'_>]) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_recv_priv(cx, |conn| conn.recv_batch(messages) ) ).await
+    }
+    /// Sends a packet with file descriptors that are borrowed for the
+    /// duration of the call.
+    pub async fn send_borrowed_fds<F: AsFd>(&mut self,  bytes: &[u8],  fds: &[F])
+    -> io::Result<usize> {
+        poll_fn(|cx| self.poll_send_priv(cx, |conn| conn.send_borrowed_fds(bytes, fds) ) ).await
+    }
+    /// Receives a packet and takes ownership of the associated file descriptors.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    pub async fn recv_owned_fds(&mut self,  byte_buffer: &mut[u8],  fds: &mut ReceivedFds)
+    -> io::Result<RecvFdsInfo> {
+        poll_fn(|cx| self.poll_recv_priv(cx, |conn| conn.recv_owned_fds(byte_buffer, fds) ) ).await
+    }
 
     pub(crate) fn poll_send_priv
     <O, S: Fn(&nonblocking::UnixSeqpacketConn)->Result<O,io::Error>>
@@ -208,6 +289,12 @@ impl AsRawFd for UnixSeqpacketConn {
     }
 }
 
+impl AsFd for UnixSeqpacketConn {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.get_ref().as_fd()
+    }
+}
+
 impl IntoRawFd for UnixSeqpacketConn {
     fn into_raw_fd(self) -> RawFd {
         let fd = self.io.get_ref().as_raw_fd(); // in case into_inner() fails
@@ -323,6 +410,12 @@ impl AsRawFd for UnixSeqpacketListener {
     }
 }
 
+impl AsFd for UnixSeqpacketListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.get_ref().as_fd()
+    }
+}
+
 impl IntoRawFd for UnixSeqpacketListener {
     fn into_raw_fd(self) -> RawFd {
         let fd = self.io.get_ref().as_raw_fd(); // in case into_inner() fails
@@ -0,0 +1,279 @@
+use crate::UnixSocketAddr;
+use crate::ancillary::{SendAncillary, ReceivedFds, RecvFdsInfo};
+use crate::batch::{SendMessage, RecvMessage};
+use super::UnixSeqpacketConn;
+use futures::future::poll_fn;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::io::{self, IoSlice, IoSliceMut};
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, AsFd, RawFd};
+use std::sync::Arc;
+
+macro_rules! impl_read_half {($type:ty, $name:ident) => {
+    impl $type {
+        /// Receives a packet from the socket's peer.
+        pub async fn recv(&mut self,  buffer: &mut[u8]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.recv(buffer) ) ).await
+        }
+        /// Receives a packet and places the bytes across multiple buffers.
+        pub async fn recv_vectored(&mut self,  buffers: &mut[IoSliceMut<'_>])
+        -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| {
+                conn.poll_recv_priv(
+                    cx,
+                    |conn| conn.recv_vectored(buffers).map(|(received, _)| received )
+                )
+            }).await
+        }
+        /// Receives a packet without removing it from the incoming queue.
+        pub async fn peek(&mut self,  buffer: &mut[u8]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.peek(buffer) ) ).await
+        }
+        /// Reads a packet into multiple buffers without removing it from the incoming queue.
+        pub async fn peek_vectored(&mut self,  buffers: &mut[IoSliceMut<'_>])
+        -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| {
+                conn.poll_recv_priv(
+                    cx,
+                    |conn| conn.peek_vectored(buffers).map(|(received, _)| received )
+                )
+            }).await
+        }
+        /// Returns the length of the next packet without receiving it.
+        pub async fn peek_len(&mut self) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.peek_len() ) ).await
+        }
+        /// Receives a packet into a `Vec` that is exactly as big as the packet.
+        pub async fn recv_to_vec(&mut self) -> io::Result<Vec<u8>> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.recv_to_vec() ) ).await
+        }
+        /// Receives a packet and associated file descriptors.
+        pub async fn recv_fds(&mut self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
+        -> io::Result<RecvFdsInfo> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.recv_fds(byte_buffer, fd_buffer) ) )
+                .await
+        }
+        /// Receives a packet and takes ownership of the associated file descriptors.
+        pub async fn recv_owned_fds(&mut self,  byte_buffer: &mut[u8],  fds: &mut ReceivedFds)
+        -> io::Result<RecvFdsInfo> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.recv_owned_fds(byte_buffer, fds) ) )
+                .await
+        }
+        /// Receives the packets that are available with one system call where
+        /// supported, and returns how many were received.
+        pub async fn recv_batch(&mut self,  messages: &mut[RecvMessage<'_>]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_recv_priv(cx, |conn| conn.recv_batch(messages) ) ).await
+        }
+
+        /// Returns the address of this side of the connection.
+        pub fn local_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+            self.0.local_addr()
+        }
+        /// Returns the address of the other side of the connection.
+        pub fn peer_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+            self.0.peer_addr()
+        }
+    }
+
+    impl AsRef<UnixSeqpacketConn> for $type {
+        fn as_ref(&self) -> &UnixSeqpacketConn {
+            &self.0
+        }
+    }
+
+    impl Debug for $type {
+        fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+            fmtr.debug_tuple(stringify!($name)).field(&self.0.as_raw_fd()).finish()
+        }
+    }
+}}
+
+macro_rules! impl_write_half {($type:ty, $name:ident) => {
+    impl $type {
+        /// Sends a packet to the socket's peer.
+        pub async fn send(&mut self,  packet: &[u8]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_send_priv(cx, |conn| conn.send(packet) ) ).await
+        }
+        /// Sends a packet assembled from multiple byte slices.
+        pub async fn send_vectored(&mut self,  slices: &[IoSlice<'_>]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_send_priv(cx, |conn| conn.send_vectored(slices) ) ).await
+        }
+        /// Sends a packet with associated file descriptors.
+        pub async fn send_fds(&mut self,  bytes: &[u8],  fds: &[RawFd]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_send_priv(cx, |conn| conn.send_fds(bytes, fds) ) ).await
+        }
+        /// Sends a packet with file descriptors that are borrowed for the
+        /// duration of the call.
+        pub async fn send_borrowed_fds<F: AsFd>(&mut self,  bytes: &[u8],  fds: &[F])
+        -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_send_priv(cx, |conn| conn.send_borrowed_fds(bytes, fds) ) )
+                .await
+        }
+        /// Sends a packet assembled from multiple byte slices,
+        /// together with the ancillary messages in `ancillary`.
+        pub async fn send_ancillary(&mut self,  slices: &[IoSlice<'_>],  ancillary: &SendAncillary)
+        -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_send_priv(cx, |conn| conn.send_ancillary(slices, ancillary) ) )
+                .await
+        }
+        /// Sends multiple packets with one system call where supported,
+        /// and returns how many were sent.
+        pub async fn send_batch(&mut self,  messages: &[SendMessage<'_>]) -> io::Result<usize> {
+            let conn: &UnixSeqpacketConn = &self.0;
+            poll_fn(|cx| conn.poll_send_priv(cx, |conn| conn.send_batch(messages) ) ).await
+        }
+
+        /// Shuts down the writing side of the connection,
+        /// so that the peer receives end-of-file.
+        pub fn shutdown(&self) -> Result<(), io::Error> {
+            self.0.shutdown(Shutdown::Write)
+        }
+
+        /// Returns the address of this side of the connection.
+        pub fn local_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+            self.0.local_addr()
+        }
+        /// Returns the address of the other side of the connection.
+        pub fn peer_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+            self.0.peer_addr()
+        }
+    }
+
+    impl AsRef<UnixSeqpacketConn> for $type {
+        fn as_ref(&self) -> &UnixSeqpacketConn {
+            &self.0
+        }
+    }
+
+    impl Debug for $type {
+        fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+            fmtr.debug_tuple(stringify!($name)).field(&self.0.as_raw_fd()).finish()
+        }
+    }
+}}
+
+/// The receiving half of a connection, borrowed from it by
+/// [`UnixSeqpacketConn::split()`](struct.UnixSeqpacketConn.html#method.split).
+pub struct ReadHalf<'a>(&'a UnixSeqpacketConn);
+impl_read_half!{ReadHalf<'_>, ReadHalf}
+
+/// The sending half of a connection, borrowed from it by
+/// [`UnixSeqpacketConn::split()`](struct.UnixSeqpacketConn.html#method.split).
+pub struct WriteHalf<'a>(&'a UnixSeqpacketConn);
+impl_write_half!{WriteHalf<'_>, WriteHalf}
+
+/// The receiving half of a connection, created by
+/// [`UnixSeqpacketConn::into_split()`](struct.UnixSeqpacketConn.html#method.into_split).
+///
+/// The connection is closed when both halves are dropped.
+pub struct OwnedReadHalf(Arc<UnixSeqpacketConn>);
+impl_read_half!{OwnedReadHalf, OwnedReadHalf}
+
+/// The sending half of a connection, created by
+/// [`UnixSeqpacketConn::into_split()`](struct.UnixSeqpacketConn.html#method.into_split).
+///
+/// The connection is closed when both halves are dropped.
+pub struct OwnedWriteHalf(Arc<UnixSeqpacketConn>);
+impl_write_half!{OwnedWriteHalf, OwnedWriteHalf}
+
+impl OwnedReadHalf {
+    /// Puts the halves back together,
+    /// or returns them if they are not from the same connection.
+    pub fn reunite(self,  other: OwnedWriteHalf) -> Result<UnixSeqpacketConn, ReuniteError> {
+        reunite(self, other)
+    }
+}
+
+impl OwnedWriteHalf {
+    /// Puts the halves back together,
+    /// or returns them if they are not from the same connection.
+    pub fn reunite(self,  other: OwnedReadHalf) -> Result<UnixSeqpacketConn, ReuniteError> {
+        reunite(other, self)
+    }
+}
+
+fn reunite(read: OwnedReadHalf,  write: OwnedWriteHalf)
+-> Result<UnixSeqpacketConn, ReuniteError> {
+    if !Arc::ptr_eq(&read.0, &write.0) {
+        return Err(ReuniteError(read, write));
+    }
+    drop(write);
+    match Arc::try_unwrap(read.0) {
+        Ok(conn) => Ok(conn),
+        Err(_) => unreachable!("both halves were given"),
+    }
+}
+
+/// Error returned when trying to reunite halves of different connections.
+///
+/// Contains the halves that were passed.
+pub struct ReuniteError(pub OwnedReadHalf, pub OwnedWriteHalf);
+
+impl Debug for ReuniteError {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_tuple("ReuniteError").field(&self.0).field(&self.1).finish()
+    }
+}
+
+impl Display for ReuniteError {
+    fn fmt(&self,  fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.write_str("tried to reunite halves that are not from the same connection")
+    }
+}
+
+impl Error for ReuniteError {}
+
+impl UnixSeqpacketConn {
+    /// Splits the connection into a receiving and a sending half that can be
+    /// used at the same time, but which borrow the connection.
+    pub fn split(&mut self) -> (ReadHalf<'_>, WriteHalf<'_>) {
+        (ReadHalf(self), WriteHalf(self))
+    }
+
+    /// Splits the connection into a receiving and a sending half that can be
+    /// moved to different tasks.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// # use tokio_02 as tokio;
+    /// use uds::tokio::UnixSeqpacketConn;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (a, mut b) = UnixSeqpacketConn::pair().unwrap();
+    ///     let (mut read, mut write) = a.into_split();
+    ///     let reader = tokio::spawn(async move {
+    ///         let mut buf = [0; 10];
+    ///         let len = read.recv(&mut buf).await.unwrap();
+    ///         assert_eq!(&buf[..len], b"ping");
+    ///         read
+    ///     });
+    ///     write.send(b"pong").await.unwrap();
+    ///     b.send(b"ping").await.unwrap();
+    ///     let read = reader.await.unwrap();
+    ///     assert_eq!(b.recv(&mut[0; 10]).await.unwrap(), 4);
+    ///     let _a = read.reunite(write).unwrap();
+    /// }
+    /// ```
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        let conn = Arc::new(self);
+        (OwnedReadHalf(conn.clone()), OwnedWriteHalf(conn))
+    }
+}
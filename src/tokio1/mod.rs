@@ -0,0 +1,13 @@
+//! Provides support for `SEQPACKET` sockets in Tokio 1.x,
+//! and extension traits for Tokio's own Unix socket types.
+//!
+//! Unlike the types in [`uds::tokio`](../tokio/index.html), which use Tokio 0.2,
+//! these types take `&self` in all methods, like Tokio's own `UnixDatagram`.
+//!
+//! The async methods of the extension traits return `Send` futures,
+//! also when called through a generic type.
+
+mod seqpacket;
+mod traits;
+pub use seqpacket::*;
+pub use traits::{UnixStreamExt, UnixListenerExt, UnixDatagramExt};
@@ -0,0 +1,470 @@
+use crate::{nonblocking, UnixSocketAddr, ConnCredentials, PidFd};
+use crate::ancillary::{SendAncillary, ReceivedFds, RecvFdsInfo};
+use crate::batch::{SendMessage, RecvMessage};
+use std::io::{self, IoSlice, IoSliceMut};
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd, AsFd, BorrowedFd};
+use std::path::Path;
+use std::task::{Context, Poll};
+use tokio_1::io::unix::AsyncFd;
+use tokio_1::io::{Interest, Ready};
+
+/// An I/O object representing a Unix Sequenced-packet socket.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::tokio1::UnixSeqpacketConn;
+///
+/// let runtime = tokio_1::runtime::Builder::new_current_thread()
+///     .enable_io()
+///     .build()
+///     .unwrap();
+/// runtime.block_on(async {
+///     let (a, b) = UnixSeqpacketConn::pair().unwrap();
+///     a.send(b"hello").await.unwrap();
+///     let mut buf = [0; 10];
+///     let len = b.recv(&mut buf).await.unwrap();
+///     assert_eq!(&buf[..len], b"hello");
+///     assert_eq!(b.try_recv(&mut buf).unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct UnixSeqpacketConn {
+    io: AsyncFd<nonblocking::UnixSeqpacketConn>,
+}
+
+impl UnixSeqpacketConn {
+    /// Connects to the socket named by path.
+    pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let conn = nonblocking::UnixSeqpacketConn::connect(path)?;
+        let conn = Self::from_nonblocking(conn)?;
+        conn.writable().await?;
+        Ok(conn)
+    }
+    /// Connects to an unix seqpacket server listening at `addr`.
+    pub async fn connect_addr(addr: &UnixSocketAddr) -> io::Result<Self> {
+        let conn = nonblocking::UnixSeqpacketConn::connect_unix_addr(addr)?;
+        let conn = Self::from_nonblocking(conn)?;
+        conn.writable().await?;
+        Ok(conn)
+    }
+    /// Binds to an address before connecting to a listening seqpacet socket.
+    pub async fn connect_from_addr(from: &UnixSocketAddr,  to: &UnixSocketAddr)
+    -> io::Result<Self> {
+        let conn = nonblocking::UnixSeqpacketConn::connect_from_to_unix_addr(from, to)?;
+        let conn = Self::from_nonblocking(conn)?;
+        conn.writable().await?;
+        Ok(conn)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn pair() -> Result<(UnixSeqpacketConn, UnixSeqpacketConn), io::Error> {
+        let (a, b) = nonblocking::UnixSeqpacketConn::pair()?;
+        Ok((Self::from_nonblocking(a)?, Self::from_nonblocking(b)?))
+    }
+
+    /// Registers an existing nonblocking socket with the current Tokio runtime.
+    pub fn from_nonblocking(conn: nonblocking::UnixSeqpacketConn) -> Result<Self, io::Error> {
+        Ok(UnixSeqpacketConn { io: AsyncFd::new(conn)? })
+    }
+    /// Deregisters the connection and returns the underlying non-blocking type.
+    pub fn into_nonblocking(self) -> nonblocking::UnixSeqpacketConn {
+        self.io.into_inner()
+    }
+    /// Creates a tokio-compatible socket from a raw file descriptor.
+    ///
+    /// This function is provided instead of implementing [`FromRawFd`](std::os::unix::io::FromRawFd)
+    /// because registering with the reactor might fail.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor must represent a connected non-blocking seqpacket socket.
+    pub unsafe fn from_raw_fd(fd: RawFd) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketConn::from_raw_fd(fd))
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self,  how: Shutdown) -> Result<(), io::Error> {
+        self.io.get_ref().shutdown(how)
+    }
+
+    /// Returns the address of this side of the connection.
+    pub fn local_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        self.io.get_ref().local_unix_addr()
+    }
+    /// Returns the address of the other side of the connection.
+    pub fn peer_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        self.io.get_ref().peer_unix_addr()
+    }
+
+    /// Returns information about the process of the peer when the connection was established.
+    ///
+    /// See documentation of the returned type for details.
+    pub fn initial_peer_credentials(&self) -> Result<ConnCredentials, io::Error> {
+        self.io.get_ref().initial_peer_credentials()
+    }
+    /// Returns a pidfd referring to the process that created the other end
+    /// of this connection.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    pub fn initial_peer_pidfd(&self) -> Result<PidFd, io::Error> {
+        self.io.get_ref().initial_peer_pidfd()
+    }
+    /// Returns the SELinux security context of the process that created the other
+    /// end of this connection.
+    ///
+    /// Will return an error on other operating systems than Linux or Android,
+    /// and also if running inside kubernetes.
+    /// On success the number of bytes used is returned. (like `Read`)
+    pub fn initial_peer_selinux_context(&self,  buffer: &mut[u8]) -> Result<usize, io::Error> {
+        self.io.get_ref().initial_peer_selinux_context(buffer)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> Result<Option<io::Error>, io::Error> {
+        self.io.get_ref().take_error()
+    }
+
+    /// Sets the size of the send buffer.
+    pub fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        self.io.get_ref().set_send_buffer_size(size)
+    }
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().send_buffer_size()
+    }
+    /// Sets the size of the receive buffer.
+    pub fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        self.io.get_ref().set_recv_buffer_size(size)
+    }
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().recv_buffer_size()
+    }
+    /// Returns the number of bytes in packets that have been received
+    /// but not read yet.
+    pub fn pending_incoming(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().pending_incoming()
+    }
+    /// Returns the number of bytes sent but not yet read by the peer.
+    ///
+    /// Only available on Linux, Android, FreeBSD and macOS.
+    pub fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        self.io.get_ref().pending_outgoing()
+    }
+}
+
+impl UnixSeqpacketConn {
+    /// Waits for any of the requested ready states.
+    ///
+    /// The readiness is not cleared, so the returned value can be stale;
+    /// the `try_*()` methods clear it when they fail with `WouldBlock`.
+    pub async fn ready(&self,  interest: Interest) -> io::Result<Ready> {
+        let guard = self.io.ready(interest).await?;
+        Ok(guard.ready())
+    }
+    /// Waits for the socket to become readable.
+    pub async fn readable(&self) -> io::Result<()> {
+        self.io.readable().await?.retain_ready();
+        Ok(())
+    }
+    /// Waits for the socket to become writable.
+    pub async fn writable(&self) -> io::Result<()> {
+        self.io.writable().await?.retain_ready();
+        Ok(())
+    }
+    /// Polls for read readiness, and registers the waker of `cx` if not ready.
+    pub fn poll_recv_ready(&self,  cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.io.poll_read_ready(cx).map_ok(|mut guard| guard.retain_ready() )
+    }
+    /// Polls for write readiness, and registers the waker of `cx` if not ready.
+    pub fn poll_send_ready(&self,  cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.io.poll_write_ready(cx).map_ok(|mut guard| guard.retain_ready() )
+    }
+
+    /// Performs a custom nonblocking operation on the socket, and clears
+    /// the readiness for `interest` if it fails with `WouldBlock`.
+    ///
+    /// `interest` must be either readable or writable, not both.
+    pub fn try_io<R>(&self,
+            interest: Interest,
+            op: impl FnOnce(&nonblocking::UnixSeqpacketConn) -> io::Result<R>,
+    ) -> io::Result<R> {
+        self.io.try_io(interest, op)
+    }
+
+    /// Sends a packet to the socket's peer.
+    pub async fn send(&self,  packet: &[u8]) -> io::Result<usize> {
+        self.io.async_io(Interest::WRITABLE, |conn| conn.send(packet) ).await
+    }
+    /// Tries to send a packet without waiting.
+    pub fn try_send(&self,  packet: &[u8]) -> io::Result<usize> {
+        self.io.try_io(Interest::WRITABLE, |conn| conn.send(packet) )
+    }
+    /// Receives a packet from the socket's peer.
+    pub async fn recv(&self,  buffer: &mut[u8]) -> io::Result<usize> {
+        self.io.async_io(Interest::READABLE, |conn| conn.recv(buffer) ).await
+    }
+    /// Tries to receive a packet without waiting.
+    pub fn try_recv(&self,  buffer: &mut[u8]) -> io::Result<usize> {
+        self.io.try_io(Interest::READABLE, |conn| conn.recv(buffer) )
+    }
+
+    /// Sends a packet assembled from multiple byte slices.
+    pub async fn send_vectored(&self,  slices: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.io.async_io(Interest::WRITABLE, |conn| conn.send_vectored(slices) ).await
+    }
+    /// Tries to send a packet assembled from multiple byte slices without waiting.
+    pub fn try_send_vectored(&self,  slices: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.io.try_io(Interest::WRITABLE, |conn| conn.send_vectored(slices) )
+    }
+    /// Receives a packet and places the bytes across multiple buffers.
+    ///
+    /// Returns the number of bytes received and whether the packet was truncated.
+    pub async fn recv_vectored(&self,  buffers: &mut[IoSliceMut<'_>])
+    -> io::Result<(usize, bool)> {
+        self.io.async_io(Interest::READABLE, |conn| conn.recv_vectored(buffers) ).await
+    }
+    /// Tries to receive a packet into multiple buffers without waiting.
+    pub fn try_recv_vectored(&self,  buffers: &mut[IoSliceMut<'_>])
+    -> io::Result<(usize, bool)> {
+        self.io.try_io(Interest::READABLE, |conn| conn.recv_vectored(buffers) )
+    }
+
+    /// Receives a packet without removing it from the incoming queue.
+    pub async fn peek(&self,  buffer: &mut[u8]) -> io::Result<usize> {
+        self.io.async_io(Interest::READABLE, |conn| conn.peek(buffer) ).await
+    }
+    /// Tries to peek at a packet without waiting.
+    pub fn try_peek(&self,  buffer: &mut[u8]) -> io::Result<usize> {
+        self.io.try_io(Interest::READABLE, |conn| conn.peek(buffer) )
+    }
+    /// Reads a packet into multiple buffers without removing it from the incoming queue.
+    ///
+    /// Returns the number of bytes peeked and whether the packet was truncated.
+    pub async fn peek_vectored(&self,  buffers: &mut[IoSliceMut<'_>])
+    -> io::Result<(usize, bool)> {
+        self.io.async_io(Interest::READABLE, |conn| conn.peek_vectored(buffers) ).await
+    }
+    /// Returns the length of the next packet without receiving it.
+    pub async fn peek_len(&self) -> io::Result<usize> {
+        self.io.async_io(Interest::READABLE, |conn| conn.peek_len() ).await
+    }
+    /// Receives a packet into a `Vec` that is exactly as big as the packet.
+    pub async fn recv_to_vec(&self) -> io::Result<Vec<u8>> {
+        self.io.async_io(Interest::READABLE, |conn| conn.recv_to_vec() ).await
+    }
+
+    /// Sends a packet with associated file descriptors.
+    pub async fn send_fds(&self,  bytes: &[u8],  fds: &[RawFd]) -> io::Result<usize> {
+        self.io.async_io(Interest::WRITABLE, |conn| conn.send_fds(bytes, fds) ).await
+    }
+    /// Tries to send a packet with associated file descriptors without waiting.
+    pub fn try_send_fds(&self,  bytes: &[u8],  fds: &[RawFd]) -> io::Result<usize> {
+        self.io.try_io(Interest::WRITABLE, |conn| conn.send_fds(bytes, fds) )
+    }
+    /// Receives a packet and associated file descriptors.
+    pub async fn recv_fds(&self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
+    -> io::Result<RecvFdsInfo> {
+        self.io.async_io(Interest::READABLE, |conn| conn.recv_fds(byte_buffer, fd_buffer) ).await
+    }
+    /// Tries to receive a packet and associated file descriptors without waiting.
+    pub fn try_recv_fds(&self,  byte_buffer: &mut[u8],  fd_buffer: &mut[RawFd])
+    -> io::Result<RecvFdsInfo> {
+        self.io.try_io(Interest::READABLE, |conn| conn.recv_fds(byte_buffer, fd_buffer) )
+    }
+    /// Sends a packet assembled from multiple byte slices,
+    /// together with the ancillary messages in `ancillary`.
+    pub async fn send_ancillary(&self,  slices: &[IoSlice<'_>],  ancillary: &SendAncillary)
+    -> io::Result<usize> {
+        self.io.async_io(Interest::WRITABLE, |conn| conn.send_ancillary(slices, ancillary) ).await
+    }
+    /// Sends a packet with file descriptors that are borrowed for the
+    /// duration of the call.
+    pub async fn send_borrowed_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F])
+    -> io::Result<usize> {
+        self.io.async_io(Interest::WRITABLE, |conn| conn.send_borrowed_fds(bytes, fds) ).await
+    }
+    /// Receives a packet and takes ownership of the associated file descriptors.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    pub async fn recv_owned_fds(&self,  byte_buffer: &mut[u8],  fds: &mut ReceivedFds)
+    -> io::Result<RecvFdsInfo> {
+        self.io.async_io(Interest::READABLE, |conn| conn.recv_owned_fds(byte_buffer, fds) ).await
+    }
+    /// Sends multiple packets with one system call where supported,
+    /// and returns how many were sent.
+    pub async fn send_batch(&self,  messages: &[SendMessage<'_>]) -> io::Result<usize> {
+        self.io.async_io(Interest::WRITABLE, |conn| conn.send_batch(messages) ).await
+    }
+    /// Receives the packets that are available with one system call where
+    /// supported, and returns how many were received.
+    pub async fn recv_batch(&self,  messages: &mut[RecvMessage<'_>]) -> io::Result<usize> {
+        self.io.async_io(Interest::READABLE, |conn| conn.recv_batch(messages) ).await
+    }
+}
+
+impl AsRef<nonblocking::UnixSeqpacketConn> for UnixSeqpacketConn {
+    fn as_ref(&self) -> &nonblocking::UnixSeqpacketConn {
+        self.io.get_ref()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketConn {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl AsFd for UnixSeqpacketConn {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.get_ref().as_fd()
+    }
+}
+
+impl IntoRawFd for UnixSeqpacketConn {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_inner().into_raw_fd()
+    }
+}
+
+
+
+/// An I/O object representing a Unix Sequenced-packet listener.
+#[derive(Debug)]
+pub struct UnixSeqpacketListener {
+    io: AsyncFd<nonblocking::UnixSeqpacketListener>,
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a socket that listens for seqpacket connections on the specified socket file.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketListener::bind(path)?)
+    }
+    /// Creates a socket that listens for seqpacket connections on the specified address.
+    pub fn bind_addr(addr: &UnixSocketAddr) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketListener::bind_unix_addr(addr)?)
+    }
+
+    /// Registers an existing nonblocking listener with the current Tokio runtime.
+    pub fn from_nonblocking(listener: nonblocking::UnixSeqpacketListener)
+    -> Result<Self, io::Error> {
+        Ok(UnixSeqpacketListener { io: AsyncFd::new(listener)? })
+    }
+    /// Deregisters the listener and returns the underlying non-blocking type.
+    pub fn into_nonblocking(self) -> nonblocking::UnixSeqpacketListener {
+        self.io.into_inner()
+    }
+    /// Creates a tokio-compatible listener from a raw file descriptor.
+    ///
+    /// This function is provided instead of implementing [`FromRawFd`](std::os::unix::io::FromRawFd)
+    /// because registering with the reactor might fail.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor must represent a non-blocking seqpacket listener.
+    pub unsafe fn from_raw_fd(fd: RawFd) -> Result<Self, io::Error> {
+        Self::from_nonblocking(nonblocking::UnixSeqpacketListener::from_raw_fd(fd))
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(target_vendor="apple"), doc="```")]
+    #[cfg_attr(target_vendor="apple", doc="```no_run")]
+    /// use uds::tokio1::{UnixSeqpacketConn, UnixSeqpacketListener};
+    ///
+    /// let runtime = tokio_1::runtime::Builder::new_current_thread()
+    ///     .enable_io()
+    ///     .build()
+    ///     .unwrap();
+    /// runtime.block_on(async {
+    ///     # let _ = std::fs::remove_file("tokio1_accept.sock");
+    ///     let listener = UnixSeqpacketListener::bind("tokio1_accept.sock").unwrap();
+    ///     let client = UnixSeqpacketConn::connect("tokio1_accept.sock").await.unwrap();
+    ///     let (server, _addr) = listener.accept().await.unwrap();
+    ///     # let _ = std::fs::remove_file("tokio1_accept.sock");
+    ///     client.send(b"hi").await.unwrap();
+    ///     assert_eq!(server.recv(&mut[0; 10]).await.unwrap(), 2);
+    /// });
+    /// ```
+    pub async fn accept(&self) -> io::Result<(UnixSeqpacketConn, UnixSocketAddr)> {
+        let (conn, addr) = self.io.async_io(
+            Interest::READABLE,
+            |listener| listener.accept_unix_addr()
+        ).await?;
+        Ok((UnixSeqpacketConn::from_nonblocking(conn)?, addr))
+    }
+    /// Tries to accept a connection without waiting.
+    pub fn try_accept(&self) -> io::Result<(UnixSeqpacketConn, UnixSocketAddr)> {
+        let (conn, addr) = self.io.try_io(
+            Interest::READABLE,
+            |listener| listener.accept_unix_addr()
+        )?;
+        Ok((UnixSeqpacketConn::from_nonblocking(conn)?, addr))
+    }
+    /// Polls for an incoming connection,
+    /// and registers the waker of `cx` if there is none.
+    pub fn poll_accept(&self,  cx: &mut Context<'_>)
+    -> Poll<io::Result<(UnixSeqpacketConn, UnixSocketAddr)>> {
+        loop {
+            let mut guard = match self.io.poll_read_ready(cx) {
+                Poll::Ready(Ok(guard)) => guard,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            };
+            if let Ok(result) = guard.try_io(|io| io.get_ref().accept_unix_addr() ) {
+                return Poll::Ready(result.and_then(|(conn, addr)| {
+                    Ok((UnixSeqpacketConn::from_nonblocking(conn)?, addr))
+                }));
+            }
+        }
+    }
+
+    /// Returns the address the socket is listening on.
+    pub fn local_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        self.io.get_ref().local_unix_addr()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    ///
+    /// This might never produce any errors for listeners. It is therefore
+    /// unlikely to be useful, but is provided for parity with
+    /// `std::unix::net::UnixListener`.
+    pub fn take_error(&self) -> Result<Option<io::Error>, io::Error> {
+        self.io.get_ref().take_error()
+    }
+}
+
+impl AsRef<nonblocking::UnixSeqpacketListener> for UnixSeqpacketListener {
+    fn as_ref(&self) -> &nonblocking::UnixSeqpacketListener {
+        self.io.get_ref()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl AsFd for UnixSeqpacketListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.io.get_ref().as_fd()
+    }
+}
+
+impl IntoRawFd for UnixSeqpacketListener {
+    fn into_raw_fd(self) -> RawFd {
+        self.io.into_inner().into_raw_fd()
+    }
+}
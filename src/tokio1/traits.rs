@@ -0,0 +1,315 @@
+use std::future::Future;
+use std::io::{self, IoSlice, IoSliceMut};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd, AsFd};
+use std::os::unix::net as std_net;
+
+use libc::{SOCK_STREAM, SOCK_DGRAM};
+use tokio_1::io::Interest;
+use tokio_1::net::{UnixStream, UnixListener, UnixDatagram};
+
+use crate::addr::UnixSocketAddr;
+use crate::helpers::*;
+use crate::ancillary::*;
+use crate::credentials::*;
+
+/// Extension trait for `tokio::net::UnixStream` from Tokio 1.x.
+///
+/// # Examples
+///
+#[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+#[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+/// use uds::UnixSocketAddr;
+/// use uds::tokio1::{UnixListenerExt, UnixStreamExt};
+/// use tokio_1::net::{UnixListener, UnixStream};
+/// use std::os::unix::io::AsRawFd;
+///
+/// let runtime = tokio_1::runtime::Builder::new_current_thread()
+///     .enable_io()
+///     .build()
+///     .unwrap();
+/// runtime.block_on(async {
+///     let addr = UnixSocketAddr::from_abstract(b"tokio1 ext example").unwrap();
+///     let listener = UnixListener::bind_unix_addr(&addr).unwrap();
+///     let client = UnixStream::connect_to_unix_addr(&addr).await.unwrap();
+///     let (server, _) = listener.accept_unix_addr().await.unwrap();
+///     client.send_fds(b"fd", &[client.as_raw_fd()]).await.unwrap();
+///     let mut fd_buf = [-1; 2];
+///     let info = server.recv_fds(&mut [0; 10], &mut fd_buf).await.unwrap();
+///     assert_eq!((info.bytes, info.fds), (2, 1));
+///     unsafe { libc::close(fd_buf[0]) };
+/// });
+/// ```
+pub trait UnixStreamExt: AsRawFd {
+    /// Returns the address of this socket, as a type that fully supports abstract addresses.
+    fn local_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        get_unix_addr(self.as_raw_fd(), GetAddr::LOCAL)
+    }
+    /// Returns the address of the other end of this stream,
+    /// as a type that fully supports abstract addresses.
+    fn peer_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        get_unix_addr(self.as_raw_fd(), GetAddr::PEER)
+    }
+
+    /// Creates a connection to a listening path-based or abstract named socket.
+    ///
+    /// Must be called from within a Tokio runtime.
+    fn connect_to_unix_addr(addr: &UnixSocketAddr)
+    -> impl Future<Output=Result<Self, io::Error>> + Send where Self: Sized;
+    /// Creates a path-based or abstract-named socket and connects to a listening socket.
+    ///
+    /// Must be called from within a Tokio runtime.
+    fn connect_from_to_unix_addr(from: &UnixSocketAddr,  to: &UnixSocketAddr)
+    -> impl Future<Output=Result<Self, io::Error>> + Send where Self: Sized;
+
+    /// Sends file descriptors in addition to bytes.
+    fn send_fds(&self,  bytes: &[u8],  fds: &[RawFd])
+    -> impl Future<Output=Result<usize, io::Error>> + Send;
+    /// Sends file descriptors that are borrowed for the duration of the call,
+    /// such as `BorrowedFd`s or `&File`s, in addition to bytes.
+    fn send_borrowed_fds<F: AsFd + Sync>(&self,  bytes: &[u8],  fds: &[F])
+    -> impl Future<Output=Result<usize, io::Error>> + Send;
+    /// Receives file descriptors in addition to bytes.
+    ///
+    /// File descriptors that don't fit in `fd_buf` are closed,
+    /// which the returned [`RecvFdsInfo`](../ancillary/struct.RecvFdsInfo.html) reports.
+    fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
+    -> impl Future<Output=Result<RecvFdsInfo, io::Error>> + Send;
+    /// Receives bytes and takes ownership of file descriptors sent with them.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    fn recv_owned_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> impl Future<Output=Result<RecvFdsInfo, io::Error>> + Send;
+
+    /// Returns the credentials of the process that created the other end of this stream.
+    fn initial_peer_credentials(&self) -> Result<ConnCredentials, io::Error> {
+        peer_credentials(self.as_raw_fd())
+    }
+}
+
+impl UnixStreamExt for UnixStream {
+    async fn connect_to_unix_addr(addr: &UnixSocketAddr) -> Result<Self, io::Error> {
+        let socket = Socket::new(SOCK_STREAM, true)?;
+        set_unix_addr(socket.as_raw_fd(), SetAddr::PEER, addr)?;
+        let stream = unsafe { std_net::UnixStream::from_raw_fd(socket.into_raw_fd()) };
+        let stream = UnixStream::from_std(stream)?;
+        stream.writable().await?;
+        match stream.take_error()? {
+            Some(e) => Err(e),
+            None => Ok(stream),
+        }
+    }
+    async fn connect_from_to_unix_addr(from: &UnixSocketAddr,  to: &UnixSocketAddr)
+    -> Result<Self, io::Error> {
+        let socket = Socket::new(SOCK_STREAM, true)?;
+        set_unix_addr(socket.as_raw_fd(), SetAddr::LOCAL, from)?;
+        set_unix_addr(socket.as_raw_fd(), SetAddr::PEER, to)?;
+        let stream = unsafe { std_net::UnixStream::from_raw_fd(socket.into_raw_fd()) };
+        let stream = UnixStream::from_std(stream)?;
+        stream.writable().await?;
+        match stream.take_error()? {
+            Some(e) => Err(e),
+            None => Ok(stream),
+        }
+    }
+
+    async fn send_fds(&self,  bytes: &[u8],  fds: &[RawFd]) -> Result<usize, io::Error> {
+        self.async_io(Interest::WRITABLE, || {
+            send_ancillary(self.as_raw_fd(), None, 0, &[IoSlice::new(bytes)], fds, None)
+        }).await
+    }
+    async fn send_borrowed_fds<F: AsFd + Sync>(&self,  bytes: &[u8],  fds: &[F])
+    -> Result<usize, io::Error> {
+        self.async_io(Interest::WRITABLE, || {
+            send_borrowed_fds(self.as_raw_fd(), None, 0, &[IoSlice::new(bytes)], fds)
+        }).await
+    }
+    async fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
+    -> Result<RecvFdsInfo, io::Error> {
+        self.async_io(Interest::READABLE, || {
+            recv_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fd_buf)
+        }).await
+    }
+    async fn recv_owned_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        self.async_io(Interest::READABLE, || {
+            recv_owned_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fds)
+        }).await
+    }
+}
+
+/// Extension trait for `tokio::net::UnixListener` from Tokio 1.x.
+pub trait UnixListenerExt: AsRawFd {
+    /// The type representing the stream connection returned by `accept_unix_addr()`.
+    type Conn: Sized;
+
+    /// Creates a socket bound to a path-based or abstract name and starts listening.
+    ///
+    /// Must be called from within a Tokio runtime.
+    fn bind_unix_addr(on: &UnixSocketAddr) -> Result<Self, io::Error> where Self: Sized;
+
+    /// Returns the address this socket is listening on.
+    fn local_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        get_unix_addr(self.as_raw_fd(), GetAddr::LOCAL)
+    }
+
+    /// Accepts a connection and returns the client's address as
+    /// an `uds::UnixSocketAddr`.
+    fn accept_unix_addr(&self)
+    -> impl Future<Output=Result<(Self::Conn, UnixSocketAddr), io::Error>> + Send;
+}
+
+impl UnixListenerExt for UnixListener {
+    type Conn = UnixStream;
+
+    fn bind_unix_addr(on: &UnixSocketAddr) -> Result<Self, io::Error> {
+        let socket = Socket::new(SOCK_STREAM, true)?;
+        set_unix_addr(socket.as_raw_fd(), SetAddr::LOCAL, on)?;
+        socket.start_listening()?;
+        let listener = unsafe { std_net::UnixListener::from_raw_fd(socket.into_raw_fd()) };
+        UnixListener::from_std(listener)
+    }
+
+    async fn accept_unix_addr(&self) -> Result<(Self::Conn, UnixSocketAddr), io::Error> {
+        let (conn, _) = self.accept().await?;
+        // the address of the accepted socket's peer is the address of the client
+        let addr = get_unix_addr(conn.as_raw_fd(), GetAddr::PEER)?;
+        Ok((conn, addr))
+    }
+}
+
+/// Extension trait for `tokio::net::UnixDatagram` from Tokio 1.x.
+pub trait UnixDatagramExt: AsRawFd {
+    /// Creates a socket bound to a path-based or abstract name.
+    ///
+    /// Must be called from within a Tokio runtime.
+    fn bind_unix_addr(addr: &UnixSocketAddr) -> Result<Self, io::Error> where Self: Sized;
+
+    /// Returns the address of this socket, as a type that fully supports abstract addresses.
+    fn local_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        get_unix_addr(self.as_raw_fd(), GetAddr::LOCAL)
+    }
+    /// Returns the address of the connected socket, as a type that fully supports abstract addresses.
+    fn peer_unix_addr(&self) -> Result<UnixSocketAddr, io::Error> {
+        get_unix_addr(self.as_raw_fd(), GetAddr::PEER)
+    }
+
+    /// Creates a path or abstract name for the socket.
+    fn bind_to_unix_addr(&self,  addr: &UnixSocketAddr) -> Result<(), io::Error> {
+        set_unix_addr(self.as_raw_fd(), SetAddr::LOCAL, addr)
+    }
+    /// Connects the socket to a path-based or abstract named socket.
+    fn connect_to_unix_addr(&self,  addr: &UnixSocketAddr) -> Result<(), io::Error> {
+        set_unix_addr(self.as_raw_fd(), SetAddr::PEER, addr)
+    }
+
+    /// Sends to the specified address, using an address type that
+    /// supports abstract addresses.
+    fn send_to_unix_addr(&self,  datagram: &[u8],  addr: &UnixSocketAddr)
+    -> impl Future<Output=Result<usize, io::Error>> + Send;
+    /// Receives from any peer, storing its address in a type that exposes
+    /// abstract addresses.
+    ///
+    /// Any file descriptors sent with the datagram are closed.
+    fn recv_from_unix_addr(&self,  buf: &mut[u8])
+    -> impl Future<Output=Result<(usize, UnixSocketAddr), io::Error>> + Send;
+
+    /// Sends file descriptors along with the datagram, on an unconnected socket.
+    fn send_fds_to(&self,  datagram: &[u8],  fds: &[RawFd],  addr: &UnixSocketAddr)
+    -> impl Future<Output=Result<usize, io::Error>> + Send;
+    /// Sends file descriptors along with the datagram, on a connected socket.
+    fn send_fds(&self,  datagram: &[u8],  fds: &[RawFd])
+    -> impl Future<Output=Result<usize, io::Error>> + Send;
+    /// Sends file descriptors that are borrowed for the duration of the call
+    /// along with the datagram, on a connected socket.
+    fn send_borrowed_fds<F: AsFd + Sync>(&self,  datagram: &[u8],  fds: &[F])
+    -> impl Future<Output=Result<usize, io::Error>> + Send;
+    /// Receives file descriptors along with the datagram, on an unconnected socket
+    ///
+    /// File descriptors that don't fit in `fd_buf` are closed,
+    /// which the returned [`RecvFdsInfo`](../ancillary/struct.RecvFdsInfo.html) reports.
+    fn recv_fds_from(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
+    -> impl Future<Output=Result<(RecvFdsInfo, UnixSocketAddr), io::Error>> + Send;
+    /// Receives file descriptors along with the datagram, on a connected socket
+    ///
+    /// File descriptors that don't fit in `fd_buf` are closed,
+    /// which the returned [`RecvFdsInfo`](../ancillary/struct.RecvFdsInfo.html) reports.
+    fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
+    -> impl Future<Output=Result<RecvFdsInfo, io::Error>> + Send;
+    /// Receives a datagram and takes ownership of file descriptors sent with
+    /// it, on a connected socket.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    fn recv_owned_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> impl Future<Output=Result<RecvFdsInfo, io::Error>> + Send;
+
+    /// Returns the credentials of the process that created a socket pair.
+    ///
+    /// This information is only available on Linux, and only for sockets that
+    /// were created with `pair()` or the underlying `socketpair()`.
+    fn initial_pair_credentials(&self) -> Result<ConnCredentials, io::Error> {
+        peer_credentials(self.as_raw_fd())
+    }
+}
+
+impl UnixDatagramExt for UnixDatagram {
+    fn bind_unix_addr(addr: &UnixSocketAddr) -> Result<Self, io::Error> {
+        let socket = Socket::new(SOCK_DGRAM, true)?;
+        set_unix_addr(socket.as_raw_fd(), SetAddr::LOCAL, addr)?;
+        let socket = unsafe { std_net::UnixDatagram::from_raw_fd(socket.into_raw_fd()) };
+        UnixDatagram::from_std(socket)
+    }
+
+    async fn send_to_unix_addr(&self,  datagram: &[u8],  addr: &UnixSocketAddr)
+    -> Result<usize, io::Error> {
+        self.send_fds_to(datagram, &[], addr).await
+    }
+    async fn recv_from_unix_addr(&self,  buf: &mut[u8])
+    -> Result<(usize, UnixSocketAddr), io::Error> {
+        let (info, addr) = self.recv_fds_from(buf, &mut[]).await?;
+        Ok((info.bytes, addr))
+    }
+
+    async fn send_fds_to(&self,  datagram: &[u8],  fds: &[RawFd],  addr: &UnixSocketAddr)
+    -> Result<usize, io::Error> {
+        self.async_io(Interest::WRITABLE, || {
+            send_ancillary(self.as_raw_fd(), Some(addr), 0, &[IoSlice::new(datagram)], fds, None)
+        }).await
+    }
+    async fn send_fds(&self,  datagram: &[u8],  fds: &[RawFd]) -> Result<usize, io::Error> {
+        self.async_io(Interest::WRITABLE, || {
+            send_ancillary(self.as_raw_fd(), None, 0, &[IoSlice::new(datagram)], fds, None)
+        }).await
+    }
+    async fn send_borrowed_fds<F: AsFd + Sync>(&self,  datagram: &[u8],  fds: &[F])
+    -> Result<usize, io::Error> {
+        self.async_io(Interest::WRITABLE, || {
+            send_borrowed_fds(self.as_raw_fd(), None, 0, &[IoSlice::new(datagram)], fds)
+        }).await
+    }
+    async fn recv_fds_from(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
+    -> Result<(RecvFdsInfo, UnixSocketAddr), io::Error> {
+        self.async_io(Interest::READABLE, || {
+            let mut addr = UnixSocketAddr::default();
+            recv_fds(self.as_raw_fd(), Some(&mut addr), &mut[IoSliceMut::new(buf)], fd_buf)
+                .map(|info| (info, addr) )
+        }).await
+    }
+    async fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
+    -> Result<RecvFdsInfo, io::Error> {
+        self.async_io(Interest::READABLE, || {
+            recv_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fd_buf)
+        }).await
+    }
+    async fn recv_owned_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        self.async_io(Interest::READABLE, || {
+            recv_owned_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fds)
+        }).await
+    }
+}
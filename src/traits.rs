@@ -1,6 +1,7 @@
-use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd, AsFd};
 use std::os::unix::net::{UnixStream, UnixListener, UnixDatagram};
 use std::io::{self, IoSlice, IoSliceMut, ErrorKind};
+use std::time::SystemTime;
 
 use libc::{SOCK_STREAM, MSG_PEEK, c_void, recvfrom, sendto};
 
@@ -8,6 +9,10 @@ use crate::addr::UnixSocketAddr;
 use crate::helpers::*;
 use crate::ancillary::*;
 use crate::credentials::*;
+use crate::pidfd::*;
+use crate::batch::*;
+use crate::many_fds::*;
+use crate::listener_builder::ListenerBuilder;
 
 /// Extension trait for `std::os::unix::net::UnixDatagram` and nonblocking equivalents.
 pub trait UnixStreamExt: AsRawFd + FromRawFd {
@@ -32,10 +37,150 @@ pub trait UnixStreamExt: AsRawFd + FromRawFd {
     fn send_fds(&self,  bytes: &[u8],  fds: &[RawFd]) -> Result<usize, io::Error> {
         send_ancillary(self.as_raw_fd(), None, 0, &[IoSlice::new(bytes)], fds, None)
     }
+    /// Sends bytes from multiple slices together with the ancillary messages
+    /// in `ancillary`.
+    ///
+    /// The messages are attached to the first byte sent,
+    /// so only a partial write can be done without them.
+    fn send_ancillary(&self,  bytes: &[IoSlice],  ancillary: &SendAncillary)
+    -> Result<usize, io::Error> {
+        send_ancillary_msgs(self.as_raw_fd(), None, 0, bytes, ancillary)
+    }
     /// Receives file descriptors in addition to bytes.
-    fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd]) -> Result<(usize, usize), io::Error> {
+    ///
+    /// File descriptors that don't fit in `fd_buf` are closed,
+    /// which the returned [`RecvFdsInfo`](ancillary/struct.RecvFdsInfo.html) reports.
+    fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd]) -> Result<RecvFdsInfo, io::Error> {
         recv_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fd_buf)
-            .map(|(bytes, _, fds)| (bytes, fds) )
+    }
+    /// Sends file descriptors that are borrowed for the duration of the call,
+    /// such as `BorrowedFd`s or `&File`s, in addition to bytes.
+    fn send_borrowed_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F]) -> Result<usize, io::Error> {
+        send_borrowed_fds(self.as_raw_fd(), None, 0, &[IoSlice::new(bytes)], fds)
+    }
+    /// Receives bytes and takes ownership of file descriptors sent with them.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    fn recv_owned_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        recv_owned_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fds)
+    }
+    /// Sends all the bytes, with the file descriptors attached to the first one.
+    ///
+    /// Unlike `send_fds()`, partial writes are retried (without the
+    /// file descriptors), so the receiver gets the file descriptors together
+    /// with the first byte of `bytes`, and can collect them with
+    /// `read_exact_with_fds()`.
+    /// Fails with `InvalidInput` if `bytes` is empty but `fds` isn't.
+    ///
+    /// The stream should be in blocking mode, as a `WouldBlock` error
+    /// would abort the call after only part of `bytes` has been sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uds::{UnixStreamExt, ancillary::ReceivedFds};
+    /// use std::os::unix::net::UnixStream;
+    /// use std::os::unix::io::AsFd;
+    ///
+    /// let (a, b) = UnixStream::pair().unwrap();
+    /// a.write_all_with_fds(b"frame one", &[a.as_fd()]).unwrap();
+    /// a.write_all_with_fds(b"frame two", &[a.as_fd(), b.as_fd()]).unwrap();
+    /// let mut frame = [0; 9];
+    /// let mut fds = ReceivedFds::with_capacity(2);
+    /// b.read_exact_with_fds(&mut frame, &mut fds).unwrap();
+    /// assert_eq!(&frame, b"frame one");
+    /// assert_eq!(fds.take().len(), 1);
+    /// b.read_exact_with_fds(&mut frame, &mut fds).unwrap();
+    /// assert_eq!(&frame, b"frame two");
+    /// assert_eq!(fds.take().len(), 2);
+    /// ```
+    fn write_all_with_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F]) -> Result<(), io::Error> {
+        let fds = fds.iter().map(|fd| fd.as_fd().as_raw_fd() ).collect::<Vec<RawFd>>();
+        write_all_with_fds(self.as_raw_fd(), 0, bytes, &fds)
+    }
+    /// Fills `buf`, and collects the file descriptors received with any of
+    /// the reads needed for it into `fds`.
+    ///
+    /// File descriptors beyond the capacity of `fds` are closed,
+    /// or make the call fail with `InvalidData` if `fds` is in strict mode.
+    /// If an error occurs, all file descriptors received by this call are closed.
+    /// Fails with `UnexpectedEof` if the stream ends before `buf` is filled.
+    ///
+    /// The stream should be in blocking mode, as a `WouldBlock` error
+    /// would abort the call with the rest of the frame still unread.
+    fn read_exact_with_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<(), io::Error> {
+        read_exact_with_fds(self.as_raw_fd(), buf, fds)
+    }
+    /// Sends bytes with any number of file descriptors,
+    /// which must be received with `recv_many_fds()`.
+    ///
+    /// Linux limits how many file descriptors can be sent with one message
+    /// (to 253), so they are split across as many messages as needed,
+    /// each with a small header.
+    /// The stream should be in blocking mode, as the messages could otherwise
+    /// be interrupted by `WouldBlock` errors.
+    fn send_many_fds<F: AsFd>(&self,  bytes: &[u8],  fds: &[F]) -> Result<(), io::Error> {
+        send_many_fds(self.as_raw_fd(), 0, bytes, fds)
+    }
+    /// Receives bytes and file descriptors sent with `send_many_fds()`.
+    ///
+    /// The file descriptors are appended to `fds` in the order they were sent,
+    /// and any beyond its capacity are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    /// Returns the number of bytes, and how many file descriptors were stored
+    /// and how many were discarded.
+    ///
+    /// Fails with `InvalidInput` if `buf` is too small for the bytes, after
+    /// receiving the rest of the message and closing all the file descriptors,
+    /// so that the next message can still be received.
+    /// Fails with `InvalidData` if the data was not sent with `send_many_fds()`,
+    /// after which the stream is likely to be in the middle of a message.
+    /// The stream should be in blocking mode, as a `WouldBlock` error would
+    /// also leave it in the middle of a message.
+    fn recv_many_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        recv_many_fds(self.as_raw_fd(), true, buf, fds)
+    }
+    /// Receives bytes and any ancillary messages sent along with them.
+    ///
+    /// `ancillary_buf` should be aligned for `cmsghdr`,
+    /// which [`AncillaryBuf`](ancillary/struct.AncillaryBuf.html) is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uds::{UnixStreamExt, ancillary::{AncillaryBuf, AncillaryItem}};
+    /// use std::os::unix::net::UnixStream;
+    /// use std::os::unix::io::AsRawFd;
+    /// use std::io::IoSliceMut;
+    ///
+    /// let (a, b) = UnixStream::pair().unwrap();
+    /// a.send_fds(b"fd", &[a.as_raw_fd()]).unwrap();
+    /// let mut buf = [0u8; 10];
+    /// let mut ancillary_buf = AncillaryBuf::with_fd_capacity(1);
+    /// let (bytes, ancillary) = b.recv_ancillary(
+    ///     &mut[IoSliceMut::new(&mut buf)],
+    ///     &mut ancillary_buf,
+    /// ).unwrap();
+    /// assert_eq!(&buf[..bytes], b"fd");
+    /// for message in ancillary {
+    ///     if let AncillaryItem::Fds(fds) = message {
+    ///         assert_eq!(fds.len(), 1);
+    ///         unsafe { libc::close(fds[0]) };
+    ///     }
+    /// }
+    /// ```
+    fn recv_ancillary<'a>(&self,
+            bufs: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>), io::Error> {
+        recv_ancillary(self.as_raw_fd(), None, 0, bufs, ancillary_buf)
     }
 
     /// Returns the credentials of the process that created the other end of this stream.
@@ -53,6 +198,77 @@ pub trait UnixStreamExt: AsRawFd + FromRawFd {
     fn initial_peer_selinux_context(&self,  buffer: &mut[u8]) -> Result<usize, io::Error> {
         selinux_context(self.as_raw_fd(), buffer)
     }
+    /// Returns a pidfd referring to the process that created the other end
+    /// of this stream.
+    ///
+    /// Unlike the pid from `initial_peer_credentials()`, the pidfd cannot
+    /// start referring to another process if the peer exits.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    fn initial_peer_pidfd(&self) -> Result<PidFd, io::Error> {
+        peer_pidfd(self.as_raw_fd())
+    }
+
+    /// Sets the size of the send buffer (`SO_SNDBUF`), which limits how many
+    /// bytes can be queued before writes block.
+    ///
+    /// The OS might adjust the size, see
+    /// [`UnixSeqpacketConn::set_send_buffer_size()`](struct.UnixSeqpacketConn.html#method.set_send_buffer_size).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::net::UnixStream;
+    /// use uds::UnixStreamExt;
+    ///
+    /// let (a, _b) = UnixStream::pair().unwrap();
+    /// a.set_send_buffer_size(64*1024).unwrap();
+    /// assert!(a.send_buffer_size().unwrap() >= 64*1024);
+    /// ```
+    fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.as_raw_fd(), BufferDirection::SEND, size)
+    }
+    /// Returns the size of the send buffer.
+    fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.as_raw_fd(), BufferDirection::SEND)
+    }
+    /// Sets the size of the receive buffer (`SO_RCVBUF`).
+    ///
+    /// This has no effect on Linux, see
+    /// [`UnixSeqpacketConn::set_recv_buffer_size()`](struct.UnixSeqpacketConn.html#method.set_recv_buffer_size).
+    fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.as_raw_fd(), BufferDirection::RECV, size)
+    }
+    /// Returns the size of the receive buffer.
+    fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.as_raw_fd(), BufferDirection::RECV)
+    }
+    /// Returns the number of bytes that can be read without blocking.
+    /// (`FIONREAD` / `SIOCINQ`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::net::UnixStream;
+    /// use std::io::Write;
+    /// use uds::UnixStreamExt;
+    ///
+    /// let (mut a, b) = UnixStream::pair().unwrap();
+    /// a.write_all(b"unread").unwrap();
+    /// assert_eq!(b.pending_incoming().unwrap(), 6);
+    /// ```
+    fn pending_incoming(&self) -> Result<usize, io::Error> {
+        pending_incoming(self.as_raw_fd())
+    }
+    /// Returns the number of bytes written but not yet read by the peer.
+    /// (`SIOCOUTQ` on Linux and Android)
+    ///
+    /// The count can include some bookkeeping overhead,
+    /// and is only available on Linux, Android, FreeBSD and macOS.
+    fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        pending_outgoing(self.as_raw_fd())
+    }
 }
 
 impl UnixStreamExt for UnixStream {
@@ -134,6 +350,34 @@ pub trait UnixListenerExt: AsRawFd + FromRawFd {
     /// Accepts a connection and returns the client's address as
     /// an `uds::UnixSocketAddr`.
     fn accept_unix_addr(&self) -> Result<(Self::Conn, UnixSocketAddr), io::Error>;
+
+    /// Creates a builder for setting the backlog, the permissions of
+    /// the socket file or removing a leftover socket file before binding.
+    ///
+    /// The default implementation creates blocking sockets,
+    /// and is overridden for nonblocking listener types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::net::UnixListener;
+    /// use std::os::unix::fs::PermissionsExt;
+    /// use uds::UnixListenerExt;
+    ///
+    /// # let _ = std::fs::remove_file("stream_builder.sock");
+    /// let listener = UnixListener::builder()
+    ///     .backlog(1)
+    ///     .mode(0o666)
+    ///     .bind("stream_builder.sock")
+    ///     .unwrap();
+    /// let permissions = std::fs::metadata("stream_builder.sock").unwrap().permissions();
+    /// assert_eq!(permissions.mode() & 0o777, 0o666);
+    /// # drop(listener);
+    /// # let _ = std::fs::remove_file("stream_builder.sock");
+    /// ```
+    fn builder() -> ListenerBuilder<Self> where Self: Sized {
+        ListenerBuilder::new(SOCK_STREAM, false)
+    }
 }
 
 impl UnixListenerExt for UnixListener {
@@ -169,6 +413,10 @@ impl UnixListenerExt for mio_uds::UnixListener {
         let conn = unsafe { Self::Conn::from_raw_fd(socket.into_raw_fd()) };
         Ok((conn, addr))
     }
+
+    fn builder() -> ListenerBuilder<Self> {
+        ListenerBuilder::new(SOCK_STREAM, true)
+    }
 }
 
 #[cfg(feature = "mio_07")]
@@ -187,6 +435,10 @@ impl UnixListenerExt for mio_07::net::UnixListener {
         let conn = unsafe { Self::Conn::from_raw_fd(socket.into_raw_fd()) };
         Ok((conn, addr))
     }
+
+    fn builder() -> ListenerBuilder<Self> {
+        ListenerBuilder::new(SOCK_STREAM, true)
+    }
 }
 
 #[cfg(feature = "mio_08")]
@@ -205,6 +457,10 @@ impl UnixListenerExt for mio_08::net::UnixListener {
         let conn = unsafe { Self::Conn::from_raw_fd(socket.into_raw_fd()) };
         Ok((conn, addr))
     }
+
+    fn builder() -> ListenerBuilder<Self> {
+        ListenerBuilder::new(SOCK_STREAM, true)
+    }
 }
 
 /// Extension trait for `std::os::unix::net::UnixDatagram` and nonblocking equivalents.
@@ -412,7 +668,7 @@ pub trait UnixDatagramExt: AsRawFd + FromRawFd {
     -> Result<(usize, UnixSocketAddr), io::Error> {
         let mut addr = UnixSocketAddr::default();
         recv_fds(self.as_raw_fd(), Some(&mut addr), bufs, &mut[])
-            .map(|(bytes, _, _)| (bytes, addr) )
+            .map(|info| (info.bytes, addr) )
     }
     /// Reads the next datagram without removing it from the queue.
     ///
@@ -504,17 +760,357 @@ pub trait UnixDatagramExt: AsRawFd + FromRawFd {
     fn send_fds(&self,  datagram: &[u8],  fds: &[RawFd]) -> Result<usize, io::Error> {
         send_ancillary(self.as_raw_fd(), None, 0, &[IoSlice::new(datagram)], fds, None)
     }
+    /// Sends a datagram assembled from multiple slices together with
+    /// the ancillary messages in `ancillary`, on an unconnected socket.
+    fn send_ancillary_to(&self,
+            datagram: &[IoSlice],
+            ancillary: &SendAncillary,
+            addr: &UnixSocketAddr,
+    ) -> Result<usize, io::Error> {
+        send_ancillary_msgs(self.as_raw_fd(), Some(addr), 0, datagram, ancillary)
+    }
+    /// Sends a datagram assembled from multiple slices together with
+    /// the ancillary messages in `ancillary`, on a connected socket.
+    fn send_ancillary(&self,  datagram: &[IoSlice],  ancillary: &SendAncillary)
+    -> Result<usize, io::Error> {
+        send_ancillary_msgs(self.as_raw_fd(), None, 0, datagram, ancillary)
+    }
+    /// Sends a datagram together with credentials, on an unconnected socket.
+    ///
+    /// Credentials are only sent explicitly on Linux and Android,
+    /// and are ignored on other operating systems.
+    /// The receiver only gets them if it has enabled `set_pass_credentials()`.
+    fn send_with_credentials_to(&self,
+            datagram: &[u8],
+            creds: SendCredentials,
+            addr: &UnixSocketAddr,
+    ) -> Result<usize, io::Error> {
+        let datagram = &[IoSlice::new(datagram)];
+        send_ancillary(self.as_raw_fd(), Some(addr), 0, datagram, &[], Some(creds))
+    }
+    /// Sends a datagram together with credentials, on a connected socket.
+    ///
+    /// Credentials are only sent explicitly on Linux and Android,
+    /// and are ignored on other operating systems.
+    /// The receiver only gets them if it has enabled `set_pass_credentials()`.
+    fn send_with_credentials(&self,  datagram: &[u8],  creds: SendCredentials)
+    -> Result<usize, io::Error> {
+        send_ancillary(self.as_raw_fd(), None, 0, &[IoSlice::new(datagram)], &[], Some(creds))
+    }
+    /// Sends multiple datagrams with one system call where supported.
+    ///
+    /// Returns the number of datagrams sent, which can be less than
+    /// `messages.len()` if an error occurs after some were sent.
+    /// Messages without an address are sent to the connected peer.
+    ///
+    /// See the [`batch`](batch/index.html) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uds::{UnixDatagramExt, UnixSocketAddr};
+    /// use uds::batch::{SendMessage, RecvMessage};
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// let _ = std::fs::remove_file("batch.sock");
+    /// let addr = UnixSocketAddr::new("batch.sock").unwrap();
+    /// let server = UnixDatagram::bind_unix_addr(&addr).unwrap();
+    /// let client = UnixDatagram::unbound().unwrap();
+    /// let sent = client.send_batch(&[
+    ///     SendMessage::new(b"hello").to(&addr),
+    ///     SendMessage::new(b"there").to(&addr),
+    /// ]).unwrap();
+    /// assert_eq!(sent, 2);
+    ///
+    /// let (mut first, mut second) = ([0; 8], [0; 8]);
+    /// let mut messages = [RecvMessage::new(&mut first), RecvMessage::new(&mut second)];
+    /// assert_eq!(server.recv_batch(&mut messages).unwrap(), 2);
+    /// assert_eq!(messages[1].data(), b"there");
+    /// assert!(messages[1].addr().is_unnamed());
+    /// # let _ = std::fs::remove_file("batch.sock");
+    /// ```
+    fn send_batch(&self,  messages: &[SendMessage]) -> Result<usize, io::Error> {
+        send_batch(self.as_raw_fd(), 0, messages)
+    }
+    /// Receives multiple datagrams with one system call where supported.
+    ///
+    /// Blocks until at least one datagram is available unless the socket is
+    /// nonblocking, and then also receives the datagrams that are immediately
+    /// available.
+    /// Returns the number of datagrams received, whose results are stored in
+    /// the first messages.
+    fn recv_batch(&self,  messages: &mut[RecvMessage]) -> Result<usize, io::Error> {
+        recv_batch(self.as_raw_fd(), messages)
+    }
     /// Receives file descriptors along with the datagram, on an unconnected socket
+    ///
+    /// File descriptors that don't fit in `fd_buf` are closed,
+    /// which the returned [`RecvFdsInfo`](ancillary/struct.RecvFdsInfo.html) reports.
     fn recv_fds_from(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd])
-    -> Result<(usize, usize, UnixSocketAddr), io::Error> {
+    -> Result<(RecvFdsInfo, UnixSocketAddr), io::Error> {
         let mut addr = UnixSocketAddr::default();
         recv_fds(self.as_raw_fd(), Some(&mut addr), &mut[IoSliceMut::new(buf)], fd_buf)
-            .map(|(bytes, _, fds)| (bytes, fds, addr) )
+            .map(|info| (info, addr) )
     }
     /// Receives file descriptors along with the datagram, on a connected socket
-    fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd]) -> Result<(usize, usize), io::Error> {
+    ///
+    /// File descriptors that don't fit in `fd_buf` are closed,
+    /// which the returned [`RecvFdsInfo`](ancillary/struct.RecvFdsInfo.html) reports.
+    fn recv_fds(&self,  buf: &mut[u8],  fd_buf: &mut[RawFd]) -> Result<RecvFdsInfo, io::Error> {
         recv_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fd_buf)
-            .map(|(bytes, _, fds)| (bytes, fds) )
+    }
+    /// Sends file descriptors that are borrowed for the duration of the call
+    /// along with the datagram, on an unconnected socket.
+    fn send_borrowed_fds_to<F: AsFd>(&self,
+            datagram: &[u8],
+            fds: &[F],
+            addr: &UnixSocketAddr,
+    ) -> Result<usize, io::Error> {
+        send_borrowed_fds(self.as_raw_fd(), Some(addr), 0, &[IoSlice::new(datagram)], fds)
+    }
+    /// Sends file descriptors that are borrowed for the duration of the call
+    /// along with the datagram, on a connected socket.
+    fn send_borrowed_fds<F: AsFd>(&self,  datagram: &[u8],  fds: &[F])
+    -> Result<usize, io::Error> {
+        send_borrowed_fds(self.as_raw_fd(), None, 0, &[IoSlice::new(datagram)], fds)
+    }
+    /// Receives a datagram and takes ownership of file descriptors sent with
+    /// it, on an unconnected socket.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    fn recv_owned_fds_from(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<(RecvFdsInfo, UnixSocketAddr), io::Error> {
+        let mut addr = UnixSocketAddr::default();
+        recv_owned_fds(self.as_raw_fd(), Some(&mut addr), &mut[IoSliceMut::new(buf)], fds)
+            .map(|info| (info, addr) )
+    }
+    /// Receives a datagram and takes ownership of file descriptors sent with
+    /// it, on a connected socket.
+    ///
+    /// The file descriptors are appended to `fds` up to its capacity,
+    /// and any extra ones are closed.
+    /// If `fds` is in strict mode, extra file descriptors instead make the call
+    /// fail with `InvalidData`, and every file descriptor it received is closed.
+    fn recv_owned_fds(&self,  buf: &mut[u8],  fds: &mut ReceivedFds)
+    -> Result<RecvFdsInfo, io::Error> {
+        recv_owned_fds(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], fds)
+    }
+    /// Receives a datagram and any ancillary messages sent with it,
+    /// on an unconnected socket.
+    ///
+    /// `ancillary_buf` should be aligned for `cmsghdr`,
+    /// which [`AncillaryBuf`](ancillary/struct.AncillaryBuf.html) is.
+    fn recv_ancillary_from<'a>(&self,
+            bufs: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>, UnixSocketAddr), io::Error> {
+        let mut addr = UnixSocketAddr::default();
+        recv_ancillary(self.as_raw_fd(), Some(&mut addr), 0, bufs, ancillary_buf)
+            .map(|(bytes, ancillary)| (bytes, ancillary, addr) )
+    }
+    /// Receives a datagram and any ancillary messages sent with it,
+    /// on a connected socket.
+    ///
+    /// `ancillary_buf` should be aligned for `cmsghdr`,
+    /// which [`AncillaryBuf`](ancillary/struct.AncillaryBuf.html) is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uds::{UnixDatagramExt, ancillary::AncillaryBuf};
+    /// use std::os::unix::net::UnixDatagram;
+    /// use std::io::IoSliceMut;
+    ///
+    /// let (a, b) = UnixDatagram::pair().unwrap();
+    /// a.send(b"too long").unwrap();
+    /// let mut buf = [0u8; 3];
+    /// let mut ancillary_buf = AncillaryBuf::with_capacity(64);
+    /// let (bytes, mut ancillary) = b.recv_ancillary(
+    ///     &mut[IoSliceMut::new(&mut buf)],
+    ///     &mut ancillary_buf,
+    /// ).unwrap();
+    /// assert_eq!(bytes, 3);
+    /// assert!(ancillary.message_truncated());
+    /// assert!(ancillary.next().is_none());
+    /// ```
+    fn recv_ancillary<'a>(&self,
+            bufs: &mut[IoSliceMut],
+            ancillary_buf: &'a mut[u8],
+    ) -> Result<(usize, Ancillary<'a>), io::Error> {
+        recv_ancillary(self.as_raw_fd(), None, 0, bufs, ancillary_buf)
+    }
+    /// Receives a datagram and the credentials of the process that sent it,
+    /// on an unconnected socket.
+    ///
+    /// See [`recv_with_credentials()`](#method.recv_with_credentials) for details.
+    fn recv_with_credentials_from(&self,  buf: &mut[u8])
+    -> Result<(usize, bool, Option<ReceivedCredentials>, UnixSocketAddr), io::Error> {
+        let mut addr = UnixSocketAddr::default();
+        recv_credentials(self.as_raw_fd(), Some(&mut addr), &mut[IoSliceMut::new(buf)])
+            .map(|(bytes, truncated, creds)| (bytes, truncated, creds, addr) )
+    }
+    /// Receives a datagram and the credentials of the process that sent it,
+    /// on a connected socket.
+    ///
+    /// The returned `bool` indicates whether the datagram was truncated due to
+    /// too short buffer.
+    /// The credentials are `None` unless `set_pass_credentials(true)` has been
+    /// called on the receiving socket, as Linux only attaches them then,
+    /// even if the sender sent some explicitly.
+    /// Any file descriptors sent with the datagram are closed.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+    #[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+    /// use uds::{UnixDatagramExt, SendCredentials};
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// let (a, b) = UnixDatagram::pair().unwrap();
+    /// a.send_with_credentials(b"explicit", SendCredentials::Effective).unwrap();
+    /// b.set_pass_credentials(true).unwrap();
+    /// let mut buf = [0u8; 16];
+    /// let (len, truncated, creds) = b.recv_with_credentials(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], b"explicit");
+    /// assert!(!truncated);
+    /// assert_eq!(creds.unwrap().pid(), Some(std::process::id()));
+    /// ```
+    fn recv_with_credentials(&self,  buf: &mut[u8])
+    -> Result<(usize, bool, Option<ReceivedCredentials>), io::Error> {
+        recv_credentials(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)])
+    }
+    /// Enables or disables receiving credentials of the sender with every datagram.
+    ///
+    /// When enabled, Linux attaches the credentials of the sending process to
+    /// every datagram, even if the sender doesn't send any explicitly.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    fn set_pass_credentials(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_credentials(self.as_raw_fd(), pass)
+    }
+    /// Receives a datagram and the security context of the process that sent it,
+    /// on an unconnected socket.
+    ///
+    /// See [`recv_with_security_context()`](#method.recv_with_security_context)
+    /// for details, including the error returned if `context_buf` is too short.
+    fn recv_with_security_context_from(&self,  buf: &mut[u8],  context_buf: &mut[u8])
+    -> Result<(usize, bool, usize, UnixSocketAddr), io::Error> {
+        let mut addr = UnixSocketAddr::default();
+        let bufs = &mut[IoSliceMut::new(buf)];
+        recv_security_context(self.as_raw_fd(), Some(&mut addr), bufs, context_buf)
+            .map(|(bytes, truncated, context_len)| (bytes, truncated, context_len, addr) )
+    }
+    /// Receives a datagram and the security context of the process that sent it,
+    /// on a connected socket.
+    ///
+    /// The returned `bool` indicates whether the datagram was truncated due to
+    /// too short buffer, and the last `usize` is the length of the context
+    /// stored in `context_buf`, which is zero if none was received.
+    /// Any file descriptors sent with the datagram are closed.
+    ///
+    /// If `context_buf` is too short for the context, the datagram is consumed
+    /// but an error of kind `InvalidInput` is returned.
+    ///
+    /// The context is only received on Linux and Android, and only if
+    /// `set_pass_security()` has been enabled and a security module which
+    /// provides labels (such as SELinux or Smack) is active.
+    /// An error is returned on other operating systems.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(any(target_os="linux", target_os="android"), doc="```")]
+    #[cfg_attr(not(any(target_os="linux", target_os="android")), doc="```no_run")]
+    /// use uds::UnixDatagramExt;
+    /// use std::os::unix::net::UnixDatagram;
+    ///
+    /// let (a, b) = UnixDatagram::pair().unwrap();
+    /// b.set_pass_security(true).unwrap();
+    /// a.send(b"labeled?").unwrap();
+    /// let mut buf = [0u8; 16];
+    /// let mut context = [0u8; 256];
+    /// let (len, truncated, context_len) = b.recv_with_security_context(&mut buf, &mut context)
+    ///     .unwrap();
+    /// assert_eq!(&buf[..len], b"labeled?");
+    /// assert!(!truncated);
+    /// if context_len > 0 {
+    ///     println!("sent by {}", String::from_utf8_lossy(&context[..context_len]));
+    /// }
+    /// ```
+    fn recv_with_security_context(&self,  buf: &mut[u8],  context_buf: &mut[u8])
+    -> Result<(usize, bool, usize), io::Error> {
+        recv_security_context(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)], context_buf)
+    }
+    /// Enables or disables receiving the security context of the sender
+    /// with every datagram.
+    ///
+    /// This is only supported on Linux and Android,
+    /// and an error is returned on other operating systems.
+    fn set_pass_security(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_security(self.as_raw_fd(), pass)
+    }
+    /// Enables or disables receiving a pidfd of the sender with every datagram,
+    /// as `AncillaryItem::Pidfd` from `recv_ancillary()`.
+    ///
+    /// Requires Linux 6.5 or later, and fails with `Unsupported` on older kernels
+    /// and other operating systems.
+    fn set_pass_pidfd(&self,  pass: bool) -> Result<(), io::Error> {
+        set_pass_pidfd(self.as_raw_fd(), pass)
+    }
+    /// Receives a datagram and the time it was received by the kernel,
+    /// on an unconnected socket.
+    ///
+    /// See [`recv_timestamped()`](#method.recv_timestamped) for details.
+    fn recv_timestamped_from(&self,  buf: &mut[u8])
+    -> Result<(usize, bool, Option<SystemTime>, UnixSocketAddr), io::Error> {
+        let mut addr = UnixSocketAddr::default();
+        recv_timestamp(self.as_raw_fd(), Some(&mut addr), &mut[IoSliceMut::new(buf)])
+            .map(|(bytes, truncated, timestamp)| (bytes, truncated, timestamp, addr) )
+    }
+    /// Receives a datagram and the time it was received by the kernel,
+    /// on a connected socket.
+    ///
+    /// The returned `bool` indicates whether the datagram was truncated due to
+    /// too short buffer.
+    /// The timestamp is `None` unless `set_receive_timestamps()` was enabled
+    /// before the datagram was sent, and any file descriptors sent with the
+    /// datagram are closed.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), doc="```")]
+    #[cfg_attr(any(target_os="illumos", target_os="solaris"), doc="```no_run")]
+    /// use uds::UnixDatagramExt;
+    /// use std::os::unix::net::UnixDatagram;
+    /// use std::time::SystemTime;
+    ///
+    /// let (a, b) = UnixDatagram::pair().unwrap();
+    /// b.set_receive_timestamps(true).unwrap();
+    /// let before = SystemTime::now();
+    /// a.send(b"when?").unwrap();
+    /// let mut buf = [0u8; 10];
+    /// let (len, truncated, timestamp) = b.recv_timestamped(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], b"when?");
+    /// assert!(!truncated);
+    /// let queued = timestamp.unwrap().duration_since(before).unwrap();
+    /// println!("spent {:?} in the queue", queued);
+    /// ```
+    fn recv_timestamped(&self,  buf: &mut[u8])
+    -> Result<(usize, bool, Option<SystemTime>), io::Error> {
+        recv_timestamp(self.as_raw_fd(), None, &mut[IoSliceMut::new(buf)])
+    }
+    /// Enables or disables timestamping of received datagrams.
+    ///
+    /// This sets `SO_TIMESTAMPNS` on Linux and Android,
+    /// and `SO_TIMESTAMP` (with microsecond precision) on other operating systems.
+    fn set_receive_timestamps(&self,  enable: bool) -> Result<(), io::Error> {
+        set_receive_timestamps(self.as_raw_fd(), enable)
     }
 
     /// Returns the credentials of the process that created a socket pair.
@@ -551,6 +1147,64 @@ pub trait UnixDatagramExt: AsRawFd + FromRawFd {
     fn initial_pair_selinux_context(&self,  buffer: &mut[u8]) -> Result<usize, io::Error> {
         selinux_context(self.as_raw_fd(), buffer)
     }
+    /// Returns a pidfd referring to the process that created a socket pair.
+    ///
+    /// Has the same limitations and gotchas as `initial_pair_credentials()`,
+    /// and requires Linux 6.5 or later.
+    fn initial_pair_pidfd(&self) -> Result<PidFd, io::Error> {
+        peer_pidfd(self.as_raw_fd())
+    }
+
+    /// Sets the size of the send buffer (`SO_SNDBUF`), which limits how many
+    /// bytes can be queued before sends block.
+    ///
+    /// The OS might adjust the size, see
+    /// [`UnixSeqpacketConn::set_send_buffer_size()`](struct.UnixSeqpacketConn.html#method.set_send_buffer_size).
+    fn set_send_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.as_raw_fd(), BufferDirection::SEND, size)
+    }
+    /// Returns the size of the send buffer.
+    fn send_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.as_raw_fd(), BufferDirection::SEND)
+    }
+    /// Sets the size of the receive buffer (`SO_RCVBUF`).
+    ///
+    /// On Linux this has no effect, as the queue of a datagram socket is
+    /// limited by the send buffers of the senders and `net.unix.max_dgram_qlen`,
+    /// see [`UnixSeqpacketConn::set_recv_buffer_size()`](struct.UnixSeqpacketConn.html#method.set_recv_buffer_size).
+    fn set_recv_buffer_size(&self,  size: usize) -> Result<(), io::Error> {
+        set_buffer_size(self.as_raw_fd(), BufferDirection::RECV, size)
+    }
+    /// Returns the size of the receive buffer.
+    fn recv_buffer_size(&self) -> Result<usize, io::Error> {
+        get_buffer_size(self.as_raw_fd(), BufferDirection::RECV)
+    }
+    /// Returns the size of the next datagram on Linux and Android,
+    /// but the number of bytes in all queued datagrams on other operating
+    /// systems. (`FIONREAD` / `SIOCINQ`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::net::UnixDatagram;
+    /// use uds::UnixDatagramExt;
+    ///
+    /// let (a, b) = UnixDatagram::pair().unwrap();
+    /// assert_eq!(b.pending_incoming().unwrap(), 0);
+    /// a.send(b"datagram").unwrap();
+    /// assert_eq!(b.pending_incoming().unwrap(), 8);
+    /// ```
+    fn pending_incoming(&self) -> Result<usize, io::Error> {
+        pending_incoming(self.as_raw_fd())
+    }
+    /// Returns the number of bytes sent but not yet received.
+    /// (`SIOCOUTQ` on Linux and Android)
+    ///
+    /// The count can include some bookkeeping overhead per datagram,
+    /// and is only available on Linux, Android, FreeBSD and macOS.
+    fn pending_outgoing(&self) -> Result<usize, io::Error> {
+        pending_outgoing(self.as_raw_fd())
+    }
 }
 
 impl UnixDatagramExt for UnixDatagram {
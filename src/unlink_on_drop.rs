@@ -0,0 +1,126 @@
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind::*};
+use std::ops::{Deref, DerefMut};
+use std::os::unix::fs::{MetadataExt, FileTypeExt};
+use std::os::unix::io::{AsRawFd, AsFd, BorrowedFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use crate::helpers::*;
+
+/// A listener that removes its socket file when dropped,
+/// unless the file has been replaced by another socket.
+///
+/// The device and inode of the socket file are remembered when this is
+/// created, and the file is only removed if they still match,
+/// so that the socket of a newer instance of the program is not removed.
+/// Create it right after binding, because relative paths are made absolute
+/// with the current directory at that time.
+///
+/// Abstract and unnamed addresses have no file, so nothing is removed then.
+///
+/// # Examples
+///
+#[cfg_attr(not(target_vendor="apple"), doc="```")]
+#[cfg_attr(target_vendor="apple", doc="```no_run")]
+/// use uds::{UnixSeqpacketListener, UnlinkOnDrop};
+/// use std::path::Path;
+///
+/// # let _ = std::fs::remove_file("unlinked_on_drop.sock");
+/// let listener = UnixSeqpacketListener::bind("unlinked_on_drop.sock").unwrap();
+/// let listener = UnlinkOnDrop::new(listener).unwrap();
+/// assert!(listener.local_unix_addr().unwrap().is_path());
+/// assert!(Path::new("unlinked_on_drop.sock").exists());
+/// drop(listener);
+/// assert!(!Path::new("unlinked_on_drop.sock").exists());
+/// ```
+#[derive(Debug)]
+pub struct UnlinkOnDrop<L: AsRawFd> {
+    listener: Option<L>,
+    file: Option<(PathBuf, u64, u64)>,
+}
+
+impl<L: AsRawFd> UnlinkOnDrop<L> {
+    /// Remembers the socket file the listener is bound to.
+    ///
+    /// Fails if the socket file can't be found.
+    pub fn new(listener: L) -> Result<Self, io::Error> {
+        let addr = get_unix_addr(listener.as_raw_fd(), GetAddr::LOCAL)?;
+        let path = match addr.as_pathname() {
+            Some(path) if path.is_relative() => env::current_dir()?.join(path),
+            Some(path) => path.to_path_buf(),
+            None => return Ok(UnlinkOnDrop { listener: Some(listener), file: None }),
+        };
+        let metadata = fs::symlink_metadata(&path)?;
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(InvalidData, "listener path is not a socket file"));
+        }
+        let file = Some((path, metadata.dev(), metadata.ino()));
+        Ok(UnlinkOnDrop { listener: Some(listener), file })
+    }
+
+    /// Returns the absolute path of the socket file that will be removed,
+    /// or `None` if the listener isn't bound to a path.
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|(path, _, _)| path.as_path() )
+    }
+
+    /// Removes the socket file now if it's still the same,
+    /// and returns whether it was.
+    pub fn unlink(&mut self) -> Result<bool, io::Error> {
+        let (path, dev, ino) = match self.file.take() {
+            Some(file) => file,
+            None => return Ok(false),
+        };
+        match fs::symlink_metadata(&path) {
+            Ok(ref metadata) if metadata.dev() == dev && metadata.ino() == ino => {
+                fs::remove_file(&path)?;
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(ref e) if e.kind() == NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the listener without removing the socket file.
+    pub fn into_inner(mut self) -> L {
+        self.file = None;
+        self.listener.take().unwrap()
+    }
+}
+
+impl<L: AsRawFd> Drop for UnlinkOnDrop<L> {
+    fn drop(&mut self) {
+        // close the listener first, so that nothing can connect between
+        // removing the file and closing the socket
+        drop(self.listener.take());
+        // ignore errors - there is nowhere to return them
+        let _ = self.unlink();
+    }
+}
+
+impl<L: AsRawFd> Deref for UnlinkOnDrop<L> {
+    type Target = L;
+    fn deref(&self) -> &L {
+        self.listener.as_ref().unwrap()
+    }
+}
+
+impl<L: AsRawFd> DerefMut for UnlinkOnDrop<L> {
+    fn deref_mut(&mut self) -> &mut L {
+        self.listener.as_mut().unwrap()
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for UnlinkOnDrop<L> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.deref().as_raw_fd()
+    }
+}
+
+impl<L: AsRawFd + AsFd> AsFd for UnlinkOnDrop<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.deref().as_fd()
+    }
+}
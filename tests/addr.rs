@@ -15,6 +15,7 @@ use uds::{UnixListenerExt, UnixStreamExt, UnixDatagramExt};
 
 #[cfg(any(target_os="linux", target_os="android"))]
 #[test]
+#[allow(clippy::expect_fun_call)]
 fn unspecified_creates_abstract() {
     let listener = UnixListener::bind_unix_addr(&UnixSocketAddr::new_unspecified())
         .expect("bind to unspecified (abstract) address");
@@ -92,6 +93,7 @@ fn abstract_not_supported() {
 }
 
 #[test]
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn max_regular_path_addr() {
     let max_regular_len = UnixSocketAddr::max_path_len()-1;
     let max_regular_path = std::iter::repeat('R').take(max_regular_len).collect::<String>();
@@ -124,6 +126,7 @@ fn max_regular_path_addr() {
 }
 
 #[test]
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn max_path_addr() {// std fails this!
     let max_len = UnixSocketAddr::max_path_len();
     let max_path = std::iter::repeat('L').take(max_len).collect::<String>();
@@ -158,6 +161,7 @@ fn too_long_abstract_name() {
 }
 
 #[test]
+#[allow(clippy::manual_repeat_n, clippy::manual_str_repeat)]
 fn too_long_path() {
     let too_long = UnixSocketAddr::max_path_len()+1;
     let path = std::iter::repeat('L').take(too_long).collect::<String>();
@@ -0,0 +1,193 @@
+#![cfg(all(feature="async-io", not(target_vendor="apple")))]
+
+use std::io::{ErrorKind::*, IoSlice, IoSliceMut, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+use std::os::unix::net::UnixStream;
+
+use async_io_2::block_on;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use futures::future::join;
+
+use uds::async_io::{UnixSeqpacketConn, UnixSeqpacketListener};
+use uds::{nonblocking, UnixSocketAddr};
+use uds::ancillary::ReceivedFds;
+use uds::batch::{SendMessage, RecvMessage};
+
+#[test]
+fn listener_accept() {
+    block_on(async {
+        let sock_path = "async-io listener.socket";
+        let _ = std::fs::remove_file(sock_path);
+        let listener = UnixSeqpacketListener::bind(sock_path).unwrap();
+
+        let server = async {
+            for i in 1u8..=3 {
+                let (socket, _) = listener.accept().await.expect("accept");
+                socket.send(&[b'h', b'i', b'0' + i]).await.expect("send");
+            }
+        };
+        let clients = async {
+            for i in 1u8..=3 {
+                let socket = UnixSeqpacketConn::connect(sock_path).await.expect("connect");
+                let mut buf = [0u8; 3];
+                assert_eq!(socket.recv(&mut buf).await.expect("receive"), 3);
+                assert_eq!(&buf, &[b'h', b'i', b'0' + i]);
+            }
+        };
+        join(server, clients).await;
+        let _ = std::fs::remove_file(sock_path);
+    });
+}
+
+#[test]
+fn addrs() {
+    block_on(async {
+        let listener_path = "async-io listener with addr.socket";
+        let client_path = "async-io named client.socket";
+        let _ = std::fs::remove_file(listener_path);
+        let _ = std::fs::remove_file(client_path);
+        let listener_addr = UnixSocketAddr::new(&listener_path).unwrap();
+        let client_addr = UnixSocketAddr::new(&client_path).unwrap();
+
+        let listener = UnixSeqpacketListener::bind_addr(&listener_addr).unwrap();
+        assert_eq!(listener.local_addr().unwrap(), listener_addr);
+        let named = UnixSeqpacketConn::connect_from_addr(&client_addr, &listener_addr)
+            .await
+            .expect("connect from address");
+        assert_eq!(named.local_addr().unwrap(), client_addr);
+        assert_eq!(named.peer_addr().unwrap(), listener_addr);
+        let (accepted, addr) = listener.accept().await.expect("accept");
+        assert_eq!(addr, client_addr);
+        assert_eq!(accepted.local_addr().unwrap(), listener_addr);
+
+        let _ = std::fs::remove_file(listener_path);
+        let _ = std::fs::remove_file(client_path);
+    });
+}
+
+#[test]
+fn send_recv_and_peek() {
+    block_on(async {
+        let (a, b) = UnixSeqpacketConn::pair().expect("create async-io seqpacket pair");
+        let mut buf = [0; 10];
+        let (received, _) = join(
+            b.recv(&mut buf),
+            async { a.send(b"waited").await.expect("send") },
+        ).await;
+        assert_eq!(received.expect("receive"), 6);
+        assert_eq!(&buf[..6], b"waited");
+
+        a.send_vectored(&[IoSlice::new(b"send"), IoSlice::new(b" one")]).await.expect("send");
+        assert_eq!(b.peek_len().await.expect("peek length"), 8);
+        assert_eq!(b.peek(&mut buf).await.expect("peek"), 8);
+        let (front, back) = buf.split_at_mut(2);
+        let received = b.recv_vectored(&mut[IoSliceMut::new(front), IoSliceMut::new(back)])
+            .await
+            .expect("receive with vectors");
+        assert_eq!(received, (8, false));
+        assert_eq!(&buf[..8], b"send one");
+
+        a.send(&[7; 70_000]).await.expect("send big packet");
+        assert_eq!(b.recv_to_vec().await.expect("receive big packet"), vec![7; 70_000]);
+    });
+}
+
+#[test]
+fn futures_io_traits() {
+    block_on(async {
+        let (mut a, b) = UnixSeqpacketConn::pair().expect("create async-io seqpacket pair");
+        a.write_all(b"first").await.expect("write");
+        assert_eq!((&a).write(b"second").await.expect("write through reference"), 6);
+        a.flush().await.expect("flush");
+
+        let mut buf = [0; 10];
+        assert_eq!((&b).read(&mut buf).await.expect("read"), 5);
+        assert_eq!(&buf[..5], b"first");
+        assert_eq!((&b).read(&mut buf[..3]).await.unwrap_err().kind(), InvalidData);
+        assert_eq!((&b).read(&mut buf).await.expect("read after too short buffer"), 6);
+        assert_eq!(&buf[..6], b"second");
+
+        a.close().await.expect("close");
+        assert_eq!((&b).read(&mut buf).await.expect("read end"), 0);
+        assert!(a.send(b"closed").await.is_err());
+    });
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+#[cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
+fn fd_passing() {
+    block_on(async {
+        let (a, b) = UnixSeqpacketConn::pair().expect("create async-io seqpacket pair");
+        let (mut to_pass, mut to_test) = UnixStream::pair().expect("create stream pair");
+
+        a.send_fds(b"a stream", &[to_pass.as_raw_fd()]).await.expect("send fd");
+        to_pass.write_all(b"once").expect("write");
+        let mut byte_buf = [0; 8];
+        let mut fd_buf = [-1; 2];
+        let info = b.recv_fds(&mut byte_buf, &mut fd_buf).await.expect("receive fd");
+        assert_eq!((info.bytes, info.fds), (8, 1));
+        assert_eq!(byte_buf, *b"a stream");
+        let mut received = unsafe { UnixStream::from_raw_fd(fd_buf[0]) };
+        received.write_all(b" and again").expect("write on received fd");
+        assert_eq!(to_test.read(&mut byte_buf).expect("read stream"), 8);
+        assert_eq!(byte_buf, *b"once and");
+
+        a.send_borrowed_fds(b"borrowed", &[&to_pass]).await.expect("send borrowed fd");
+        let mut owned = ReceivedFds::with_capacity(1);
+        let info = b.recv_owned_fds(&mut byte_buf, &mut owned).await.expect("receive owned fd");
+        assert_eq!((info.bytes, owned.len()), (8, 1));
+    });
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+#[cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
+fn batch_and_queues() {
+    block_on(async {
+        let (a, b) = UnixSeqpacketConn::pair().expect("create async-io seqpacket pair");
+        b.set_recv_buffer_size(8*1024).expect("set receive buffer size");
+        assert!(b.recv_buffer_size().expect("get receive buffer size") >= 8*1024);
+        a.set_send_buffer_size(8*1024).expect("set send buffer size");
+        assert!(a.send_buffer_size().expect("get send buffer size") >= 8*1024);
+
+        let sent = a.send_batch(&[SendMessage::new(b"first"), SendMessage::new(b"second")])
+            .await
+            .expect("send batch");
+        assert_eq!(sent, 2);
+        assert_eq!(b.pending_incoming().expect("get pending incoming"), 11);
+        #[cfg(any(target_os="linux", target_os="android"))]
+        assert!(a.pending_outgoing().expect("get pending outgoing") >= 11);
+
+        let mut bufs = [[0u8; 8]; 2];
+        let (first, second) = bufs.split_at_mut(1);
+        let mut messages = [RecvMessage::new(&mut first[0]), RecvMessage::new(&mut second[0])];
+        assert_eq!(b.recv_batch(&mut messages).await.expect("receive batch"), 2);
+        assert_eq!(messages[0].data(), b"first");
+        assert_eq!(messages[1].data(), b"second");
+        assert_eq!(b.pending_incoming().expect("get pending incoming when empty"), 0);
+    });
+}
+
+#[test]
+fn shutdown_and_conversions() {
+    block_on(async {
+        let (a_nonblocking, b_nonblocking) = nonblocking::UnixSeqpacketConn::pair()
+            .expect("create nonblocking seqpacket pair");
+        let a_fd = a_nonblocking.as_raw_fd();
+        let a = unsafe {
+            UnixSeqpacketConn::from_raw_fd(a_nonblocking.into_raw_fd())
+                .expect("create from raw fd")
+        };
+        assert_eq!(a.as_raw_fd(), a_fd);
+        a.send(b"registered").await.expect("send");
+        assert_eq!(b_nonblocking.recv(&mut[0; 16]).expect("receive"), 10);
+
+        let a = a.into_nonblocking().expect("deregister");
+        assert_eq!(a.as_raw_fd(), a_fd);
+        let a = UnixSeqpacketConn::from_nonblocking(a).expect("register again");
+        let b = UnixSeqpacketConn::from_nonblocking(b_nonblocking).expect("register");
+        a.shutdown(Shutdown::Both).expect("shut down");
+        assert!(a.send(b"hi").await.is_err());
+        assert_eq!(b.recv(&mut[0; 3]).await.expect("receive end"), 0);
+    });
+}
@@ -7,6 +7,7 @@ use std::os::unix::net::{UnixListener, UnixStream};
 use std::process::{Command, Stdio};
 
 use uds::{UnixSocketAddr, UnixListenerExt, UnixStreamExt};
+use uds::ancillary::{ReceivedFds, RecvFdsInfo};
 
 fn is_cloexec(fd: RawFd) -> bool {
     let mut exe = std::env::current_exe().expect("get directory of tests binary");
@@ -91,11 +92,42 @@ fn received() {
     let (foo, bar) = UnixStream::pair().expect("create unix stream pair");
     foo.send_fds(b"Hello Bar, it's Foo, your peer", &[foo.as_raw_fd()]).expect("send fd");
     let mut fd_buf = [-1; 10];
-    let (_, num_fds) = bar.recv_fds(&mut[b'\0'; 8], &mut fd_buf).expect("receive ancillary");
+    let RecvFdsInfo { fds: num_fds, .. } = bar.recv_fds(&mut[b'\0'; 8], &mut fd_buf)
+        .expect("receive ancillary");
     assert_eq!(num_fds, 1);
     assert!(is_cloexec(fd_buf[0]));
 }
 
+#[cfg(not(any(target_os="illumos", target_os="solaris")))]
+#[test]
+fn received_owned() {
+    let (foo, bar) = UnixStream::pair().expect("create unix stream pair");
+    foo.send_borrowed_fds(b"cloexec", &[&foo]).expect("send fd");
+    let mut fds = ReceivedFds::with_capacity(1);
+    bar.recv_owned_fds(&mut[0; 8], &mut fds).expect("receive fd");
+    assert!(is_cloexec(fds[0].as_raw_fd()));
+}
+
+#[cfg(not(any(target_os="illumos", target_os="solaris")))]
+#[test]
+fn received_inheritable() {
+    let (foo, bar) = UnixStream::pair().expect("create unix stream pair");
+    let mut fds = ReceivedFds::with_capacity(2);
+    fds.set_inheritable(true);
+    foo.send_borrowed_fds(b"inherit", &[&foo]).expect("send fd");
+    bar.recv_owned_fds(&mut[0; 7], &mut fds).expect("receive inheritable fd");
+    assert!(!is_cloexec(fds[0].as_raw_fd()));
+    foo.send_many_fds(b"many", &[&foo]).expect("send fd with send_many_fds()");
+    bar.recv_many_fds(&mut[0; 4], &mut fds).expect("receive inheritable fd");
+    assert!(!is_cloexec(fds[1].as_raw_fd()));
+
+    fds.set_inheritable(false);
+    fds.clear();
+    foo.send_borrowed_fds(b"cloexec", &[&foo]).expect("send fd");
+    bar.recv_owned_fds(&mut[0; 7], &mut fds).expect("receive fd");
+    assert!(is_cloexec(fds[0].as_raw_fd()));
+}
+
 #[test] /// tests that cloexec_tester detects a fd without cloexec
 fn raw_not_cloexec() {
     unsafe {
@@ -3,13 +3,14 @@
 use std::os::unix::net::{UnixListener, UnixStream, UnixDatagram};
 use std::io::{self, ErrorKind::*};
 use std::fs::remove_file;
+use std::os::unix::io::AsRawFd;
 
 extern crate uds;
-use uds::{ConnCredentials, UnixStreamExt, UnixDatagramExt};
+use uds::{ConnCredentials, SendCredentials, UnixStreamExt, UnixDatagramExt};
 use uds::{UnixSeqpacketListener, UnixSeqpacketConn};
 
 extern crate libc;
-use libc::{getpid, geteuid, getegid, getgid, getgroups};
+use libc::{getpid, geteuid, getegid, getuid, getgid, getgroups};
 
 #[cfg_attr(
     not(any(
@@ -26,6 +27,7 @@ fn peer_credentials_not_supported() {
     assert_eq!(error.kind(), Other);
 }
 
+#[allow(clippy::match_ref_pats, clippy::unnecessary_cast)]
 fn assert_credentials_matches_current_process(creds: &ConnCredentials,  socket_type: &str) {
     match creds {
         &ConnCredentials::LinuxLike{ pid, euid, egid } => {
@@ -244,3 +246,222 @@ fn peer_selinux_context() {
         }
     }
 }
+
+#[cfg_attr(any(target_os="linux", target_os="android"), test)]
+fn datagram_credentials_per_message() {
+    let (a, b) = UnixDatagram::pair().expect("create unix datagram socket pair");
+    let mut buf = [0u8; 16];
+
+    // not passed
+    a.send_with_credentials(b"ignored", SendCredentials::Real).expect("send credentials");
+    let (len, truncated, creds) = b.recv_with_credentials(&mut buf).expect("receive");
+    assert_eq!(&buf[..len], b"ignored");
+    assert!(!truncated);
+    assert_eq!(creds, None);
+
+    b.set_pass_credentials(true).expect("enable SO_PASSCRED");
+    a.send(b"implicit").expect("send without credentials");
+    let (len, truncated, creds) = b.recv_with_credentials(&mut buf).expect("receive");
+    assert_eq!(&buf[..len], b"implicit");
+    assert!(!truncated);
+    let creds = creds.expect("kernel attaches credentials");
+    assert_eq!(creds.pid(), Some(unsafe { getpid() } as u32));
+    assert_eq!(creds.effective_or_sent_uid(), unsafe { geteuid() } as u32);
+    assert_eq!(creds.effective_or_sent_gid(), Some(unsafe { getegid() } as u32));
+
+    let custom = SendCredentials::Custom {
+        pid: unsafe { getpid() } as u32,
+        uid: unsafe { getuid() } as u32,
+        gid: unsafe { getgid() } as u32,
+    };
+    a.send_with_credentials(b"custom", custom).expect("send custom credentials");
+    let (len, truncated, creds) = b.recv_with_credentials(&mut buf).expect("receive");
+    assert_eq!(&buf[..len], b"custom");
+    assert!(!truncated);
+    let creds = creds.expect("receive credentials");
+    assert_eq!(creds.real_or_sent_uid(), unsafe { getuid() } as u32);
+    assert_eq!(creds.real_or_sent_gid(), unsafe { getgid() } as u32);
+
+    // fds sent alongside are closed
+    a.send_fds(b"fd", &[a.as_raw_fd()]).expect("send fd");
+    let (len, truncated, creds) = b.recv_with_credentials(&mut buf).expect("receive");
+    assert_eq!(&buf[..len], b"fd");
+    assert!(!truncated);
+    assert!(creds.is_some());
+
+    b.set_pass_credentials(false).expect("disable SO_PASSCRED");
+    a.send(b"off").expect("send");
+    let (_, _, creds) = b.recv_with_credentials(&mut buf).expect("receive");
+    assert_eq!(creds, None);
+
+    a.send(b"truncated").expect("send");
+    let (len, truncated, _) = b.recv_with_credentials(&mut buf[..4]).expect("receive");
+    assert_eq!(len, 4);
+    assert!(truncated);
+}
+
+#[cfg_attr(any(target_os="linux", target_os="android"), test)]
+fn seqpacket_credentials_per_message() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create unix seqpacket pair");
+    b.set_pass_credentials(true).expect("enable SO_PASSCRED");
+    a.send_with_credentials(b"explicit", SendCredentials::Effective).expect("send credentials");
+    let mut buf = [0u8; 4];
+    let (len, truncated, creds) = b.recv_with_credentials(&mut buf).expect("receive");
+    assert_eq!(len, 4);
+    assert!(truncated);
+    assert_eq!(creds.expect("receive credentials").pid(), Some(unsafe { getpid() } as u32));
+}
+
+#[cfg_attr(not(any(target_os="linux", target_os="android")), test)]
+fn per_message_credentials_not_supported() {
+    let (a, b) = UnixDatagram::pair().expect("create unix datagram socket pair");
+    assert_eq!(b.set_pass_credentials(true).unwrap_err().kind(), Unsupported);
+    a.send(b"nothing").expect("send");
+    assert_eq!(b.recv_with_credentials(&mut[0; 16]).unwrap_err().kind(), Unsupported);
+}
+
+#[test]
+fn per_message_security_context() {
+    let (a, b) = UnixDatagram::pair().expect("create unix datagram socket pair");
+    let mut buf = [0u8; 16];
+    let mut context = [0u8; 256];
+    match b.set_pass_security(true) {
+        Ok(()) => {
+            a.send(b"labeled?").expect("send");
+            let (len, truncated, context_len) = b.recv_with_security_context(&mut buf, &mut context)
+                .expect("receive with security context");
+            assert_eq!(&buf[..len], b"labeled?");
+            assert!(!truncated);
+            let context = &context[..context_len];
+            assert!(context.iter().all(|&b| b.is_ascii() && !b.is_ascii_control() ));
+            let mut peer_context = [0u8; 256];
+            if let Ok(peer_len) = b.initial_pair_selinux_context(&mut peer_context) {
+                if context_len != 0 {
+                    assert_eq!(context, &peer_context[..peer_len]);
+                }
+            }
+
+            let (a, b) = UnixSeqpacketConn::pair().expect("create unix seqpacket pair");
+            b.set_pass_security(true).expect("enable SO_PASSSEC");
+            b.set_pass_credentials(true).expect("enable SO_PASSCRED");
+            a.send(b"labeled?").expect("send");
+            let mut seqpacket_context = [0u8; 256];
+            let (len, truncated, seqpacket_context_len)
+                = b.recv_with_security_context(&mut buf, &mut seqpacket_context)
+                .expect("receive with security context");
+            assert_eq!((len, truncated), (8, false));
+            assert_eq!(&seqpacket_context[..seqpacket_context_len], context);
+
+            if !context.is_empty() {
+                a.send(b"no room").expect("send");
+                let err = b.recv_with_security_context(&mut buf, &mut seqpacket_context[..1])
+                    .expect_err("receive with too short context buffer");
+                assert_eq!(err.kind(), InvalidInput);
+            }
+        }
+        Err(_) => {
+            if cfg!(any(target_os="linux", target_os="android")) {
+                panic!("failed to enable SO_PASSSEC on Linux");
+            }
+            a.send(b"nothing").expect("send");
+            let err = b.recv_with_security_context(&mut buf, &mut context).unwrap_err();
+            assert_eq!(err.kind(), Unsupported);
+        }
+    }
+}
+
+fn check_own_pidfd(pidfd: uds::PidFd,  socket_type: &str) {
+    let pid = pidfd.pid().expect("read pid of pidfd");
+    assert_eq!(pid.map(|pid| pid.get() ), Some(unsafe { getpid() } as u32), "{} pid matches", socket_type);
+    assert!(!pidfd.has_exited().expect("poll pidfd"), "{} peer is alive", socket_type);
+    let dir = pidfd.open_proc_dir().expect("open /proc/<pid>");
+    assert!(dir.metadata().unwrap().is_dir());
+}
+
+#[cfg_attr(any(target_os="linux", target_os="android"), test)]
+fn peer_pidfd() {
+    let (a, _b) = UnixStream::pair().expect("create unix stream pair");
+    match a.initial_peer_pidfd() {
+        Ok(pidfd) => check_own_pidfd(pidfd, "stream"),
+        Err(ref e) if e.kind() == Unsupported => return, // kernel too old
+        Err(e) => panic!("failed with unexpected error {}", e),
+    }
+    let (a, _b) = UnixSeqpacketConn::pair().expect("create unix seqpacket pair");
+    check_own_pidfd(a.initial_peer_pidfd().expect("get pidfd"), "seqpacket");
+    let (a, _b) = UnixDatagram::pair().expect("create unix datagram pair");
+    check_own_pidfd(a.initial_pair_pidfd().expect("get pidfd"), "datagram pair");
+}
+
+#[cfg_attr(any(target_os="linux", target_os="android"), test)]
+fn per_message_pidfd() {
+    use std::io::IoSliceMut;
+    use uds::ancillary::{AncillaryBuf, AncillaryItem};
+
+    let (a, b) = UnixDatagram::pair().expect("create unix datagram pair");
+    match b.set_pass_pidfd(true) {
+        Ok(()) => {}
+        Err(ref e) if e.kind() == Unsupported => return, // kernel too old
+        Err(e) => panic!("failed with unexpected error {}", e),
+    }
+    a.send(b"who").expect("send");
+    let mut buf = [0u8; 8];
+    let mut ancillary_buf = AncillaryBuf::with_capacity(128);
+    let (len, ancillary) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+        .expect("receive with ancillary");
+    assert_eq!(&buf[..len], b"who");
+    let mut pidfds = 0;
+    for message in ancillary {
+        if let AncillaryItem::Pidfd(pidfd) = message {
+            check_own_pidfd(pidfd, "datagram message");
+            pidfds += 1;
+        }
+    }
+    assert_eq!(pidfds, 1);
+}
+
+#[cfg_attr(any(target_os="linux", target_os="android"), test)]
+fn peeked_pidfd_is_skipped() {
+    use std::io::IoSliceMut;
+    use uds::ancillary::{AncillaryBuf, AncillaryItem};
+
+    let (a, b) = UnixSeqpacketConn::pair().expect("create unix seqpacket pair");
+    match b.set_pass_pidfd(true) {
+        Ok(()) => {}
+        Err(ref e) if e.kind() == Unsupported => return, // kernel too old
+        Err(e) => panic!("failed with unexpected error {}", e),
+    }
+    a.send_fds(b"peek", &[a.as_raw_fd()]).expect("send with fd");
+    let mut buf = [0u8; 8];
+    let mut ancillary_buf = AncillaryBuf::with_capacity(128);
+    for _ in 0..2 {
+        let (len, ancillary) = b.peek_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+            .expect("peek with ancillary");
+        assert_eq!(&buf[..len], b"peek");
+        let mut peeked_fds = 0;
+        for message in ancillary {
+            match message {
+                AncillaryItem::Pidfd(_) => panic!("peeked pidfd"),
+                AncillaryItem::Fds(fds) => peeked_fds += fds.len(),
+                _ => {}
+            }
+        }
+        assert_eq!(peeked_fds, 1);
+    }
+    let (len, ancillary) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+        .expect("receive with ancillary");
+    assert_eq!(&buf[..len], b"peek");
+    let mut pidfds = 0;
+    for message in ancillary {
+        if let AncillaryItem::Pidfd(pidfd) = message {
+            check_own_pidfd(pidfd, "received seqpacket");
+            pidfds += 1;
+        }
+    }
+    assert_eq!(pidfds, 1);
+}
+
+#[cfg_attr(not(any(target_os="linux", target_os="android")), test)]
+fn pidfd_not_supported() {
+    let (a, _b) = UnixStream::pair().expect("create unix stream pair");
+    assert!(a.initial_peer_pidfd().is_err());
+}
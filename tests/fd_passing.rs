@@ -1,15 +1,19 @@
 #![cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
 
 extern crate uds;
+extern crate libc;
 
-use std::io::{ErrorKind::*, Read, Write};
-use std::os::unix::io::{AsRawFd, FromRawFd};
-use std::os::unix::net::{UnixDatagram, UnixStream};
-use std::fs::remove_file;
+use std::io::{ErrorKind::*, IoSlice, IoSliceMut, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, AsFd, BorrowedFd, OwnedFd};
+use std::os::unix::net::{UnixDatagram, UnixStream, UnixListener};
+use std::net::TcpListener;
+use std::fs::{File, remove_file};
 use std::env::consts::*;
 use std::mem::ManuallyDrop;
 
-use uds::{UnixDatagramExt, UnixStreamExt, UnixSocketAddr};
+use uds::{UnixDatagramExt, UnixStreamExt, UnixSocketAddr, SendCredentials};
+use uds::{UnixSeqpacketConn, UnixSeqpacketListener, ReceivedFd, FdKind};
+use uds::ancillary::{AncillaryBuf, AncillaryItem, ReceivedFds, RecvFdsInfo, SendAncillary};
 
 #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
 fn datagram_send_no_fds() {
@@ -22,20 +26,23 @@ fn datagram_send_no_fds() {
 
     // send with empty fd slice, receive for empty fd slice
     a.send_fds(b"aa", &[]).expect("send zero file descriptors");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[]).expect("receive with empty fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[])
+        .expect("receive with empty fd buffer");
     assert_eq!(bytes, 2);
     assert_eq!(fds, 0);
 
     // send without ancillary, receive for empty fd slice
     a.send(b"aaa").expect("send normally - without ancillary");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[]).expect("receive with empty fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[])
+        .expect("receive with empty fd buffer");
     assert_eq!(bytes, 3);
     assert_eq!(fds, 0);
 
     // send with empty fd slice, receive with capacity
     a.send_fds(b"aaaa", &[]).expect("send zero file descriptors");
     let mut fd_buf = [-1; 3];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive with fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+        .expect("receive with fd buffer");
     assert_eq!(bytes, 4);
     assert_eq!(fds, 0);
     assert_eq!(fd_buf, [-1; 3]);
@@ -43,7 +50,8 @@ fn datagram_send_no_fds() {
     // send without ancillary, receive with capacity
     a.send(b"aaaaa").expect("send normally - without ancillary");
     let mut fd_buf = [-1; 3];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive with fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+        .expect("receive with fd buffer");
     assert_eq!(bytes, 5);
     assert_eq!(fds, 0);
     assert_eq!(fd_buf, [-1; 3]);
@@ -60,7 +68,8 @@ fn datagram_truncate_fds() {
 
     // send some, receive with zero-length fd slice
     a.send_fds(b"aa", &[a.as_raw_fd()]).expect("send one fd");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[]).expect("receive with empty fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[])
+        .expect("receive with empty fd buffer");
     assert_eq!((bytes, fds), (2, 0));
 
     // send four, receive two
@@ -68,7 +77,7 @@ fn datagram_truncate_fds() {
         .expect("send four fds");
     let mut fd_buf = [-1; 2];
     match b.recv_fds(&mut[0u8; 10], &mut fd_buf) {// receives to capacity or none
-        Ok((3, 2)) => {
+        Ok(RecvFdsInfo { bytes: 3, fds: 2, .. }) => {
             assert_ne!(fd_buf[0], -1);
             if fd_buf[0] != a.as_raw_fd()  &&  fd_buf[0] != b.as_raw_fd() {
                 let _ = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
@@ -78,10 +87,10 @@ fn datagram_truncate_fds() {
                 let _ = unsafe { UnixDatagram::from_raw_fd(fd_buf[1]) };
             }
         }
-        Ok((3, 0)) => assert_eq!(fd_buf, [-1; 2]),
+        Ok(RecvFdsInfo { bytes: 3, fds: 0, .. }) => assert_eq!(fd_buf, [-1; 2]),
         // OpenBSD is sensical.
         Err(ref e) if e.raw_os_error() == Some(libc::EMSGSIZE) => assert_eq!(fd_buf, [-1; 2]),
-        Ok((bytes, fds)) => {
+        Ok(RecvFdsInfo { bytes, fds, .. }) => {
             panic!("received {} bytes and {} fds but expected 3 bytes and 2 or 0 fds", bytes, fds);
         }
         Err(e) => panic!("receive with smaller fd buffer failed: {}", e),
@@ -89,6 +98,7 @@ fn datagram_truncate_fds() {
 }
 
 #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+#[allow(clippy::unused_io_amount)]
 fn stream_send_no_fds() {
     let (mut a, mut b) = UnixStream::pair().expect("create stream socket pair");
 
@@ -99,20 +109,23 @@ fn stream_send_no_fds() {
 
     // send with empty fd slice, receive for empty fd slice
     a.send_fds(b"aa", &[]).expect("send zero file descriptors");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[]).expect("receive with empty fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[])
+        .expect("receive with empty fd buffer");
     assert_eq!(bytes, 2);
     assert_eq!(fds, 0);
 
     // send without ancillary, receive for empty fd slice
     a.write(b"aaa").expect("write normally - without ancillary");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[]).expect("receive with empty fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[])
+        .expect("receive with empty fd buffer");
     assert_eq!(bytes, 3);
     assert_eq!(fds, 0);
 
     // send with empty fd slice, receive with capacity
     a.send_fds(b"aaaa", &[]).expect("send zero file descriptors");
     let mut fd_buf = [-1; 3];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive with fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+        .expect("receive with fd buffer");
     assert_eq!(bytes, 4);
     assert_eq!(fds, 0);
     assert_eq!(fd_buf, [-1; 3]);
@@ -120,13 +133,15 @@ fn stream_send_no_fds() {
     // send without ancillary, receive with capacity
     a.write(b"aaaaa").expect("write normally - without ancillary");
     let mut fd_buf = [-1; 3];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive with fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+        .expect("receive with fd buffer");
     assert_eq!(bytes, 5);
     assert_eq!(fds, 0);
     assert_eq!(fd_buf, [-1; 3]);
 }
 
 #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+#[allow(clippy::unused_io_amount)]
 fn stream_truncate_fds() {
     let (mut a, mut b) = UnixStream::pair().expect("create stream socket pair");
 
@@ -142,12 +157,14 @@ fn stream_truncate_fds() {
     assert_eq!(error.kind(), WouldBlock);
     // try to receive fds later when there is more data
     a.write(b"aa").expect("write normally - without ancillary");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[0; 2]).expect("receive with capacity");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[0; 2])
+        .expect("receive with capacity");
     assert_eq!((bytes, fds), (2, 0));
 
     // send some, receive with zero-length fd slice
     a.send_fds(b"aaa", &[a.as_raw_fd()]).expect("send one fd");
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut[]).expect("receive with empty fd buffer");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut[])
+        .expect("receive with empty fd buffer");
     assert_eq!((bytes, fds), (3, 0));
 
     // try to receive what was truncated, now that we received with ancillary buffer the first time
@@ -156,7 +173,8 @@ fn stream_truncate_fds() {
     assert_eq!(error.kind(), WouldBlock);
     a.send_fds(b"aaaa", &[]).expect("send empty fd slice");
     let mut fd_buf = [-1; 4];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive with capacity");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+        .expect("receive with capacity");
     assert_eq!((bytes, fds, fd_buf), (4, 0, [-1; 4]));
 
     // send four, receive two
@@ -164,7 +182,7 @@ fn stream_truncate_fds() {
         .expect("send four fds");
     let mut fd_buf = [-1; 2];
     match b.recv_fds(&mut[0u8; 10], &mut fd_buf) {// receives to capacity or nothing
-        Ok((5, 2)) => {
+        Ok(RecvFdsInfo { bytes: 5, fds: 2, .. }) => {
             println!("a={}, b={}, received={:?}", a.as_raw_fd(), b.as_raw_fd(), fd_buf);
             assert_ne!(fd_buf[0], -1);
             if fd_buf[0] != a.as_raw_fd()  &&  fd_buf[0] != b.as_raw_fd() {
@@ -175,7 +193,7 @@ fn stream_truncate_fds() {
                 let _ = unsafe { UnixStream::from_raw_fd(fd_buf[1]) };
             }
         },
-        Ok((5, 0)) => {
+        Ok(RecvFdsInfo { bytes: 5, fds: 0, .. }) => {
             assert_eq!(fd_buf, [-1; 2]);
             if cfg!(any(target_os="linux", target_os="android", target_vendor="apple")) {
                 panic!("all FDs were dropped, which is unexpected for {}", OS);
@@ -183,7 +201,7 @@ fn stream_truncate_fds() {
         }
         // OpenBSD is sensical.
         Err(ref e) if e.raw_os_error() == Some(libc::EMSGSIZE) => assert_eq!(fd_buf, [-1; 2]),
-        Ok((bytes, fds)) => {
+        Ok(RecvFdsInfo { bytes, fds, .. }) => {
             panic!("received {} bytes and {} fds but expected 5 bytes and 2 or 0 fds", bytes, fds);
         }
         Err(e) => panic!("receiving with too small ancillary buffer failed: {}", e),
@@ -192,7 +210,8 @@ fn stream_truncate_fds() {
         // try to receive what was truncated
         a.send_fds(b"aaaaaa", &[a.as_raw_fd()]).expect("send one more fd"); // fails on freebsd
         let mut fd_buf = [-1; 6];
-        let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive with capacity");
+        let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+            .expect("receive with capacity");
         assert_eq!((bytes, fds), (6, 1));
         assert_ne!(fd_buf[0], -1);
         let _ = unsafe { UnixStream::from_raw_fd(fd_buf[0]) };
@@ -208,7 +227,7 @@ fn datagram_pass_one_fd() {
     let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
     a.send_fds(b"", &[a.as_raw_fd()]).expect("send one file descriptor");
     let mut fd_buf = [-1; 3];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
         .expect("receive with ancillary buffer");
     assert_eq!(bytes, 0);
     assert_eq!(fds, 1);
@@ -227,7 +246,7 @@ fn datagram_pass_two_receive_one() {
     let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
     a.send_fds(b"", &[a.as_raw_fd(), b.as_raw_fd()]).expect("send one file descriptor");
     let mut fd_buf = [-1];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 10], &mut fd_buf)
         .expect("receive with ancillary buffer");
     assert_eq!(bytes, 0);
     assert_eq!(fds, 1);
@@ -235,12 +254,370 @@ fn datagram_pass_two_receive_one() {
         let _ = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
     }
     b.send_fds(b"nothing", &[]).expect("send another datagram with no fds");
-    let (bytes, fds) = a.recv_fds(&mut[0u8; 10], &mut fd_buf)
+    let RecvFdsInfo { bytes, fds, .. } = a.recv_fds(&mut[0u8; 10], &mut fd_buf)
         .expect("receive with ancillary buffer");
     assert_eq!(bytes, "nothing".len());
     assert_eq!(fds, 0);
 }
 
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn datagram_recv_ancillary() {
+    let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
+    a.send_fds(b"two", &[a.as_raw_fd(), b.as_raw_fd()]).expect("send two file descriptors");
+    let mut buf = [0u8; 10];
+    let mut ancillary_buf = AncillaryBuf::with_fd_capacity(2);
+    let (bytes, mut ancillary) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+        .expect("receive with ancillary buffer");
+    assert_eq!(bytes, 3);
+    assert!(!ancillary.message_truncated());
+    assert!(!ancillary.ancillary_truncated());
+    match ancillary.next() {
+        Some(AncillaryItem::Fds(fds)) => {
+            assert_eq!(fds.len(), 2);
+            for &fd in fds {
+                assert_ne!(fd, a.as_raw_fd());
+                assert_ne!(fd, b.as_raw_fd());
+                let _ = unsafe { UnixDatagram::from_raw_fd(fd) };
+            }
+        }
+        other => panic!("expected file descriptors, got {:?}", other),
+    }
+    assert!(ancillary.next().is_none());
+    drop(ancillary);
+
+    // no ancillary buffer
+    a.send_fds(b"zero", &[a.as_raw_fd()]).expect("send one file descriptor");
+    let (bytes, mut ancillary) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut[])
+        .expect("receive without ancillary buffer");
+    assert_eq!(bytes, 4);
+    assert!(!ancillary.message_truncated());
+    assert!(ancillary.ancillary_truncated());
+    assert!(ancillary.next().is_none());
+    drop(ancillary);
+
+    // too short byte buffer
+    a.send(b"truncated").expect("send without ancillary");
+    let (bytes, mut ancillary) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf[..3])], &mut ancillary_buf)
+        .expect("receive into short buffer");
+    assert_eq!(bytes, 3);
+    assert!(ancillary.message_truncated());
+    assert!(!ancillary.ancillary_truncated());
+    assert!(ancillary.next().is_none());
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn datagram_send_ancillary() {
+    let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
+    let mut ancillary = SendAncillary::new();
+    assert!(ancillary.is_empty());
+    a.send_ancillary(&[IoSlice::new(b"none")], &ancillary).expect("send without ancillary");
+    assert_eq!(b.recv(&mut[0u8; 10]).expect("receive"), 4);
+
+    let fd_bytes = a.as_raw_fd().to_ne_bytes();
+    ancillary.add_credentials(SendCredentials::Effective)
+        .add_raw(libc::SOL_SOCKET, libc::SCM_RIGHTS, &fd_bytes);
+    assert!(!ancillary.is_empty());
+    let sent = a.send_ancillary(&[IoSlice::new(b"so"), IoSlice::new(b"me")], &ancillary)
+        .expect("send credentials and a raw fd message");
+    assert_eq!(sent, 4);
+    let mut buf = [0u8; 10];
+    let mut ancillary_buf = AncillaryBuf::with_capacity(128);
+    let (bytes, received) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+        .expect("receive with ancillary buffer");
+    assert_eq!(&buf[..bytes], b"some");
+    let mut received_fds = 0;
+    for message in received {
+        if let AncillaryItem::Fds(fds) = message {
+            received_fds += fds.len();
+            for &fd in fds {
+                let _ = unsafe { UnixDatagram::from_raw_fd(fd) };
+            }
+        }
+    }
+    assert_eq!(received_fds, 1);
+
+    // more than fits on the stack
+    ancillary.clear();
+    ancillary.add_fds(&[a.as_raw_fd(); 50]).add_fds(&[b.as_raw_fd(); 50]);
+    a.send_ancillary(&[IoSlice::new(b"many")], &ancillary).expect("send 100 fds");
+    let mut ancillary_buf = AncillaryBuf::with_fd_capacity(100);
+    let (_, received) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+        .expect("receive 100 fds");
+    assert!(!received.ancillary_truncated());
+    let mut received_fds = 0;
+    for message in received {
+        if let AncillaryItem::Fds(fds) = message {
+            received_fds += fds.len();
+            for &fd in fds {
+                let _ = unsafe { UnixDatagram::from_raw_fd(fd) };
+            }
+        }
+    }
+    assert_eq!(received_fds, 100);
+
+    ancillary.clear();
+    assert!(ancillary.is_empty());
+    ancillary.add_raw(libc::SOL_SOCKET, -1, b"garbage");
+    a.send_ancillary(&[IoSlice::new(b"invalid")], &ancillary)
+        .expect_err("send unknown control message");
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn datagram_owned_fds() {
+    let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
+    a.send_borrowed_fds(b"two", &[a.as_fd(), b.as_fd()]).expect("send borrowed fds");
+    a.send_borrowed_fds(b"one", &[&a]).expect("send reference to socket");
+
+    let mut fds = ReceivedFds::with_capacity(2);
+    let mut buf = [0u8; 10];
+    let info = b.recv_owned_fds(&mut buf[..1], &mut fds).expect("receive two fds");
+    assert_eq!(info.bytes, 1);
+    assert!(info.message_truncated);
+    assert_eq!(info.fds, 2);
+    assert_eq!(fds.len(), 2);
+    // no capacity left, so the next one is closed
+    let info = b.recv_owned_fds(&mut buf, &mut fds).expect("receive with full container");
+    assert_eq!(&buf[..info.bytes], b"one");
+    assert_eq!((info.fds, info.fds_discarded), (0, 0));
+    assert!(info.ancillary_truncated);
+    assert_eq!(fds.len(), 2);
+
+    let mut fds = fds.into_iter();
+    let received_a = UnixDatagram::from(fds.next().unwrap());
+    let received_b = UnixDatagram::from(fds.next().unwrap());
+    assert_ne!(received_a.as_raw_fd(), a.as_raw_fd());
+    received_a.send(b"from a").expect("send from received fd");
+    assert_eq!(b.recv(&mut buf).expect("receive datagram sent from received fd"), 6);
+    received_b.send(b"from b").expect("send from received fd");
+    assert_eq!(a.recv(&mut buf).expect("receive datagram sent from received fd"), 6);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn stream_owned_fds() {
+    let (a, b) = UnixStream::pair().expect("create stream socket pair");
+    let mut fds = ReceivedFds::with_capacity(1);
+    a.send_borrowed_fds(b"none", &[] as &[BorrowedFd]).expect("send no fds");
+    let info = b.recv_owned_fds(&mut[0u8; 10], &mut fds).expect("receive without fds");
+    assert_eq!(info, RecvFdsInfo { bytes: 4, ..RecvFdsInfo::default() });
+    assert!(fds.is_empty());
+
+    a.send_borrowed_fds(b"x", &[a.as_fd(), b.as_fd()]).expect("send two fds");
+    let info = b.recv_owned_fds(&mut[0u8; 10], &mut fds).expect("receive one of two fds");
+    assert_eq!(info.bytes, 1);
+    assert_eq!((info.fds, info.fds_discarded), (1, 1));
+    assert_eq!(fds.len(), 1);
+    let taken = fds.take();
+    assert_eq!(taken.len(), 1);
+    assert!(fds.is_empty());
+    assert_eq!(fds.capacity(), 1);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn strict_owned_fds() {
+    let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
+    let mut fds = ReceivedFds::with_capacity(3);
+    fds.set_strict(true);
+    a.send_borrowed_fds(b"one", &[a.as_fd()]).expect("send one fd");
+    let info = b.recv_owned_fds(&mut[0u8; 10], &mut fds).expect("receive fd that fits");
+    assert!(!info.fds_lost());
+    assert_eq!(fds.len(), 1);
+
+    // exceeds the capacity left
+    a.send_borrowed_fds(b"three", &[a.as_fd(), a.as_fd(), a.as_fd()]).expect("send three fds");
+    let error = b.recv_owned_fds(&mut[0u8; 10], &mut fds).expect_err("receive too many fds");
+    assert_eq!(error.kind(), InvalidData);
+    assert_eq!(fds.len(), 1);
+
+    // the message was consumed
+    a.send(b"next").expect("send without fds");
+    let info = b.recv_owned_fds(&mut[0u8; 10], &mut fds).expect("receive next datagram");
+    assert_eq!(info.bytes, 4);
+
+    // the non-strict raw variant reports what was lost
+    a.send_fds(b"four", &[a.as_raw_fd(), a.as_raw_fd(), a.as_raw_fd(), a.as_raw_fd()])
+        .expect("send four fds");
+    let mut fd_buf = [-1; 2];
+    let info = b.recv_fds(&mut[0u8; 10], &mut fd_buf).expect("receive two of four fds");
+    assert_eq!(info.fds, 2);
+    assert!(info.fds_lost());
+    for &fd in &fd_buf {
+        let _ = unsafe { UnixDatagram::from_raw_fd(fd) };
+    }
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn classify_received_fds() {
+    fn kind_of<F: Into<OwnedFd>>(fd: F) -> FdKind {
+        ReceivedFd::from(fd.into()).kind().expect("get kind of fd")
+    }
+    assert_eq!(kind_of(File::open("Cargo.toml").unwrap()), FdKind::RegularFile);
+    assert_eq!(kind_of(File::open(".").unwrap()), FdKind::Directory);
+    let (stream, _) = UnixStream::pair().expect("create stream socket pair");
+    assert_eq!(kind_of(stream), FdKind::UnixStream);
+    let (datagram, _) = UnixDatagram::pair().expect("create datagram socket pair");
+    assert_eq!(kind_of(datagram), FdKind::UnixDatagram);
+    let (seqpacket, _) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    assert_eq!(kind_of(seqpacket), FdKind::UnixSeqpacket);
+    let path = "classify_listener.sock";
+    let _ = remove_file(path);
+    let listener = UnixListener::bind(path).expect("create stream listener");
+    remove_file(path).expect("delete socket file");
+    assert_eq!(kind_of(listener), FdKind::UnixListener);
+    let listener = UnixSeqpacketListener::bind(path).expect("create seqpacket listener");
+    let _ = remove_file(path);
+    assert_eq!(kind_of(listener), FdKind::UnixSeqpacketListener);
+    let mut pipe = [-1; 2];
+    assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+    let (read_end, write_end) = unsafe { (File::from_raw_fd(pipe[0]), File::from_raw_fd(pipe[1])) };
+    assert_eq!(kind_of(read_end), FdKind::Pipe);
+    assert_eq!(kind_of(write_end), FdKind::Pipe);
+    assert_eq!(kind_of(File::open("/dev/null").unwrap()), FdKind::CharacterDevice);
+    let tcp = TcpListener::bind("127.0.0.1:0").expect("create TCP listener");
+    assert_eq!(kind_of(tcp), FdKind::OtherSocket);
+
+    #[cfg(any(target_os="linux", target_os="android"))] {
+        let memfd = unsafe { libc::memfd_create(b"classify\0".as_ptr() as *const _, 0) };
+        assert!(memfd != -1, "create memfd");
+        let memfd = ReceivedFd::from(unsafe { OwnedFd::from_raw_fd(memfd) });
+        assert_eq!(memfd.kind().unwrap(), FdKind::Memfd);
+        memfd.into_file().expect("convert memfd to File");
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        assert!(eventfd != -1, "create eventfd");
+        assert_eq!(kind_of(unsafe { OwnedFd::from_raw_fd(eventfd) }), FdKind::EventFd);
+    }
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn checked_fd_conversions() {
+    let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
+    let (c, d) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    let dir = File::open(".").expect("open directory");
+    a.send_borrowed_fds(b"", &[c.as_fd(), dir.as_fd()]).expect("send seqpacket and directory");
+
+    let mut fds = ReceivedFds::with_capacity(2);
+    b.recv_owned_fds(&mut[0u8; 1], &mut fds).expect("receive fds");
+    let mut fds = fds.into_vec().into_iter().map(ReceivedFd::from);
+    let received_c = fds.next().unwrap();
+    let received_dir = fds.next().unwrap();
+
+    let error = received_dir.into_seqpacket_conn().expect_err("use directory as socket");
+    assert_eq!(error.kind(), InvalidData);
+    let received_c = received_c.into_seqpacket_conn().expect("convert to seqpacket connection");
+    received_c.send(b"checked").expect("send from received connection");
+    assert_eq!(d.recv(&mut[0u8; 10]).expect("receive from received connection"), 7);
+    let received_c = ReceivedFd::from(OwnedFd::from(received_c));
+    let error = received_c.into_unix_stream().expect_err("use seqpacket as stream");
+    assert_eq!(error.kind(), InvalidData);
+    let error = ReceivedFd::from(OwnedFd::from(d)).into_file().expect_err("use socket as file");
+    assert_eq!(error.kind(), InvalidData);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn stream_frames_with_fds() {
+    let (a, b) = UnixStream::pair().expect("create stream socket pair");
+    // bigger than the socket buffer, so the write is partial
+    let frame = (0..1_000_000u32).map(|n| n as u8 ).collect::<Vec<u8>>();
+    // not a, as receiving it would prevent end of file
+    let file = File::open("Cargo.toml").expect("open file");
+    let writer = std::thread::spawn(move || {
+        a.write_all_with_fds(&frame, &[&file]).expect("write big frame with fd");
+        a.write_all_with_fds(b"small", &[&file, &file]).expect("write small frame");
+        let error = a.write_all_with_fds(b"", &[&file]).expect_err("write only fds");
+        assert_eq!(error.kind(), InvalidInput);
+        frame
+    });
+
+    let mut fds = ReceivedFds::with_capacity(3);
+    let mut buf = vec![0u8; 1_000_000];
+    b.read_exact_with_fds(&mut buf, &mut fds).expect("read big frame");
+    assert_eq!(fds.len(), 1);
+    let mut small = [0u8; 5];
+    b.read_exact_with_fds(&mut small, &mut fds).expect("read small frame");
+    assert_eq!(&small, b"small");
+    assert_eq!(fds.len(), 3);
+    assert_eq!(buf, writer.join().unwrap());
+
+    let error = b.read_exact_with_fds(&mut small, &mut fds).expect_err("read after writer closed");
+    assert_eq!(error.kind(), UnexpectedEof);
+    assert_eq!(fds.len(), 3);
+}
+
+fn inode(fd: BorrowedFd) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    let fd = fd.try_clone_to_owned().expect("duplicate fd");
+    File::from(fd).metadata().expect("get metadata of fd").ino()
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn stream_many_fds() {
+    let (a, b) = UnixStream::pair().expect("create stream socket pair");
+    let sockets = (0..300).map(|_| UnixDatagram::unbound().expect("create datagram socket") )
+        .collect::<Vec<UnixDatagram>>();
+    a.send_many_fds(b"many", &sockets).expect("send 300 fds");
+    a.send_many_fds(b"", &[] as &[BorrowedFd]).expect("send zero fds");
+
+    let mut buf = [0u8; 10];
+    let mut fds = ReceivedFds::with_capacity(400);
+    let info = b.recv_many_fds(&mut buf, &mut fds).expect("receive 300 fds");
+    assert_eq!(&buf[..info.bytes], b"many");
+    assert_eq!((info.fds, info.fds_discarded), (300, 0));
+    assert_eq!(fds.len(), 300);
+    for (sent, received) in sockets.iter().zip(&fds) {
+        assert_eq!(inode(sent.as_fd()), inode(received.as_fd()));
+    }
+    let info = b.recv_many_fds(&mut buf, &mut fds).expect("receive zero fds");
+    assert_eq!((info.bytes, info.fds), (0, 0));
+    assert_eq!(fds.len(), 300);
+
+    a.send_many_fds(&[b'x'; 3000], &sockets).expect("send long payload with 300 fds");
+    a.send_many_fds(b"next", &sockets[..1]).expect("send another message");
+    let error = b.recv_many_fds(&mut buf, &mut fds).expect_err("receive into too small buffer");
+    assert_eq!(error.kind(), InvalidInput);
+    assert_eq!(fds.len(), 300);
+    let info = b.recv_many_fds(&mut buf, &mut fds).expect("receive message after skipped one");
+    assert_eq!(&buf[..info.bytes], b"next");
+    assert_eq!(fds.len(), 301);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn seqpacket_many_fds() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    let fds = vec![a.as_fd(); 600];
+    a.send_many_fds(b"first", &fds).expect("send 600 fds");
+    a.send_many_fds(b"second", &fds[..253]).expect("send exactly one packet worth of fds");
+    a.send_many_fds(b"third", &fds[..254]).expect("send one more than fits in a packet");
+
+    let mut buf = [0u8; 10];
+    let mut received = ReceivedFds::with_capacity(500);
+    received.set_strict(true);
+    let error = b.recv_many_fds(&mut buf, &mut received).expect_err("receive too many fds");
+    assert_eq!(error.kind(), InvalidData);
+    assert!(received.is_empty());
+    let info = b.recv_many_fds(&mut buf, &mut received).expect("receive second");
+    assert_eq!(&buf[..info.bytes], b"second");
+    assert_eq!(received.len(), 253);
+    received.set_strict(false);
+    let info = b.recv_many_fds(&mut buf, &mut received).expect("receive third");
+    assert_eq!(&buf[..info.bytes], b"third");
+    assert_eq!((info.fds, info.fds_discarded), (247, 7));
+    assert!(info.fds_lost());
+    assert_eq!(received.len(), 500);
+
+    a.send_many_fds(b"too long for buf", &fds).expect("send 600 fds");
+    a.send_many_fds(b"fourth", &fds[..1]).expect("send one fd");
+    let mut received = ReceivedFds::with_capacity(1000);
+    let error = b.recv_many_fds(&mut buf, &mut received).expect_err("receive into too small buffer");
+    assert_eq!(error.kind(), InvalidInput);
+    assert!(received.is_empty());
+    let info = b.recv_many_fds(&mut buf, &mut received).expect("receive fourth");
+    assert_eq!(&buf[..info.bytes], b"fourth");
+    assert_eq!(received.len(), 1);
+
+    a.send_fds(b"not many", &[a.as_raw_fd()]).expect("send normally");
+    let error = b.recv_many_fds(&mut buf, &mut received).expect_err("receive normal packet");
+    assert_eq!(error.kind(), InvalidData);
+}
+
 #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
 fn datagram_separate_payloads() {
     let (a, b) = UnixDatagram::pair().expect("create datagram socket pair");
@@ -249,7 +626,8 @@ fn datagram_separate_payloads() {
     a.send_fds(b"_", &[a.as_raw_fd()]).expect("send datagram with one fd");
     a.send(b"").expect("send a second datagram, wiithout fd");
     let mut fd_buf = [-1; 2];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 1], &mut fd_buf).expect("receive fds");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 1], &mut fd_buf)
+        .expect("receive fds");
     assert_eq!(bytes, 1);
     assert_eq!(fds, 1);
     assert_ne!(fd_buf[0], -1);
@@ -257,7 +635,8 @@ fn datagram_separate_payloads() {
         let _ = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
     }
     assert_eq!(fd_buf[1], -1);
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 1], &mut fd_buf).expect("receive fds");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 1], &mut fd_buf)
+        .expect("receive fds");
     assert_eq!(bytes, 0);
     assert_eq!(fds, 0);
 
@@ -266,7 +645,8 @@ fn datagram_separate_payloads() {
     a.send_fds(b"", &[b.as_raw_fd(), b.as_raw_fd()]).expect("sent two fds again");
     for _ in 0..2 {
         let mut fd_buf = [-1; 3];
-        let (bytes, fds) = b.recv_fds(&mut[0u8; 3], &mut fd_buf).expect("receive fds");
+        let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 3], &mut fd_buf)
+            .expect("receive fds");
         assert_eq!(bytes, 0);
         assert_eq!(fds, 2);
         assert!(fd_buf[..2].iter().all(|&fd| fd != -1 ));
@@ -296,7 +676,9 @@ fn unconnected_datagrams() {
     send.send_fds_to(b"next from this", &[unbound.as_raw_fd()], &addr_recv)
         .expect("send datagram to address");
     assert_eq!(
-        recv.recv_fds_from(&mut byte_buf, &mut fd_buf).expect("receive with addr"),
+        recv.recv_fds_from(&mut byte_buf, &mut fd_buf)
+            .map(|(info, addr)| (info.bytes, info.fds, addr) )
+            .expect("receive with addr"),
         (14, 1, addr_send)
     );
     assert_eq!(&byte_buf, b"next from this\0\0\0\0\0\0");
@@ -314,7 +696,9 @@ fn unconnected_datagrams() {
         &addr_recv
     ).expect("send datagram from unbound to bound");
     assert_eq!(
-        recv.recv_fds_from(&mut byte_buf, &mut fd_buf).expect("receive from unbound"),
+        recv.recv_fds_from(&mut byte_buf, &mut fd_buf)
+            .map(|(info, addr)| (info.bytes, info.fds, addr) )
+            .expect("receive from unbound"),
         (17, 2, addr_unbound)
     );
     assert_eq!(&byte_buf, b"where I came from\0\0\0");
@@ -326,11 +710,13 @@ fn unconnected_datagrams() {
 
 #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
 /// a just-to-be-absolutely-sure test
+#[allow(clippy::unused_io_amount)]
 fn stream_fd_order() {
     let (mut a, mut b) = UnixStream::pair().expect("create stream socket pair");
     a.send_fds(b"2", &[a.as_raw_fd(), b.as_raw_fd()]).expect("send two fds");
     let mut fd_buf = [0; 2];
-    let (bytes, fds) = b.recv_fds(&mut[0u8; 3], &mut fd_buf).expect("receive fds");
+    let RecvFdsInfo { bytes, fds, .. } = b.recv_fds(&mut[0u8; 3], &mut fd_buf)
+        .expect("receive fds");
     assert_eq!(bytes, 1);
     assert_eq!(fds, 2);
     let mut received_a = unsafe { ManuallyDrop::new(UnixStream::from_raw_fd(fd_buf[0])) };
@@ -366,7 +752,8 @@ fn closed_before_received() {
     a.send_fds(&[], &[a.as_raw_fd()]).expect("send fd");
     let _ = a; // drop a
     let mut fd_buf = [-1];
-    let (_, fds) = b.recv_fds(&mut[], &mut fd_buf).expect("receive fd that is already closed");
+    let RecvFdsInfo { fds, .. } = b.recv_fds(&mut[], &mut fd_buf)
+        .expect("receive fd that is already closed");
     assert_eq!(fds, 1);
     assert_ne!(fd_buf[0], -1);
     let a = unsafe { UnixDatagram::from_raw_fd(fd_buf[0]) };
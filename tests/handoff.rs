@@ -0,0 +1,85 @@
+#![cfg(not(target_vendor="apple"))]
+
+extern crate uds;
+
+use std::fs::{File, remove_file};
+use std::io::ErrorKind::*;
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::io::{AsFd, BorrowedFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use uds::{UnixSeqpacketConn, UnixSeqpacketListener, handoff};
+
+#[test]
+fn handed_off_listeners_accept() {
+    let path = "handoff_accept.sock";
+    let seqpacket_path = "handoff_accept_seqpacket.sock";
+    let _ = remove_file(path);
+    let _ = remove_file(seqpacket_path);
+    let unix = UnixListener::bind(path).expect("create unix listener");
+    let seqpacket = UnixSeqpacketListener::bind(seqpacket_path)
+        .expect("create seqpacket listener");
+    let tcp = TcpListener::bind("127.0.0.1:0").expect("create TCP listener");
+    let tcp_addr = tcp.local_addr().unwrap();
+
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    handoff::send_listeners(&a, &[
+        ("unix", unix.as_fd()),
+        ("tcp", tcp.as_fd()),
+        ("seqpacket", seqpacket.as_fd()),
+    ]).expect("send listeners");
+    drop((unix, tcp, seqpacket));
+
+    let mut listeners = handoff::recv_listeners(&b).expect("receive listeners");
+    assert_eq!(listeners.len(), 3);
+    let unix = listeners.take_unix_listener("unix").expect("take unix listener");
+    let _client = UnixStream::connect(path).expect("connect to handed off unix listener");
+    let _ = remove_file(path);
+    unix.accept().expect("accept on handed off unix listener");
+    let tcp = listeners.take_tcp_listener("tcp").expect("take TCP listener");
+    let _client = TcpStream::connect(tcp_addr).expect("connect to handed off TCP listener");
+    tcp.accept().expect("accept on handed off TCP listener");
+    let seqpacket = listeners.take_seqpacket_listener("seqpacket")
+        .expect("take seqpacket listener");
+    let _client = UnixSeqpacketConn::connect(seqpacket_path)
+        .expect("connect to handed off seqpacket listener");
+    let _ = remove_file(seqpacket_path);
+    seqpacket.accept_unix_addr().expect("accept on handed off seqpacket listener");
+    assert!(listeners.is_empty());
+}
+
+#[test]
+fn wrong_kinds_are_not_taken() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    let (c, _d) = UnixStream::pair().expect("create stream pair");
+    let dir = File::open(".").expect("open directory");
+    handoff::send_listeners(&a, &[("connection", c.as_fd()), ("dir", dir.as_fd())])
+        .expect("send non-listeners");
+
+    let mut listeners = handoff::recv_listeners(&b).expect("receive non-listeners");
+    let error = listeners.take_unix_listener("connection").expect_err("take connection");
+    assert_eq!(error.kind(), InvalidData);
+    let error = listeners.take_tcp_listener("dir").expect_err("take directory");
+    assert_eq!(error.kind(), InvalidData);
+    let error = listeners.take_seqpacket_listener("missing").expect_err("take missing");
+    assert_eq!(error.kind(), NotFound);
+    assert_eq!(listeners.names().collect::<Vec<_>>(), ["connection", "dir"]);
+    assert!(listeners.take("dir").is_some());
+    assert_eq!(listeners.into_vec().len(), 1);
+}
+
+#[test]
+fn invalid_tables() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    let fd = a.as_fd();
+    let error = handoff::send_listeners(&a, &[("same", fd), ("same", fd)])
+        .expect_err("send duplicate names");
+    assert_eq!(error.kind(), InvalidInput);
+    handoff::send_listeners(&a, &[] as &[(&str, BorrowedFd)]).expect("send no listeners");
+    assert!(handoff::recv_listeners(&b).expect("receive no listeners").is_empty());
+
+    // a name without a file descriptor
+    a.send_many_fds(b"\x03\x00abc", &[] as &[BorrowedFd]).expect("send bad table");
+    let error = handoff::recv_listeners(&b).expect_err("receive bad table");
+    assert_eq!(error.kind(), InvalidData);
+}
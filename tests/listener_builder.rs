@@ -0,0 +1,108 @@
+#![cfg(not(target_vendor="apple"))]
+
+extern crate uds;
+
+use std::fs::{self, File, remove_file};
+use std::io::ErrorKind::*;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use uds::{UnixListenerExt, UnixSeqpacketConn, UnixSeqpacketListener, UnixSocketAddr};
+use uds::nonblocking::UnixSeqpacketConn as NonblockingUnixSeqpacketConn;
+
+#[test]
+fn stale_files_are_removed() {
+    let path = "builder_stale.sock";
+    let _ = remove_file(path);
+    drop(UnixListener::bind(path).expect("create listener"));
+    let error = UnixListener::bind(path).expect_err("bind to leftover file");
+    assert_eq!(error.kind(), AddrInUse);
+    let error = UnixListener::builder().bind(path).expect_err("bind without unlinking");
+    assert_eq!(error.kind(), AddrInUse);
+
+    let listener = UnixListener::builder()
+        .unlink_if_stale(true)
+        .bind(path)
+        .expect("replace stale stream socket");
+    let _conn = UnixStream::connect(path).expect("connect to new listener");
+    drop(listener);
+
+    let _listener = UnixSeqpacketListener::builder()
+        .unlink_if_stale(true)
+        .bind(path)
+        .expect("replace stale socket with seqpacket listener");
+    let _conn = UnixSeqpacketConn::connect(path).expect("connect to seqpacket listener");
+    let _ = remove_file(path);
+}
+
+#[test]
+fn live_listeners_are_not_removed() {
+    let path = "builder_live.sock";
+    let _ = remove_file(path);
+    let _listener = UnixListener::bind(path).expect("create listener");
+    let error = UnixListener::builder()
+        .unlink_if_stale(true)
+        .bind(path)
+        .expect_err("replace live listener");
+    assert_eq!(error.kind(), AddrInUse);
+    let error = UnixSeqpacketListener::builder()
+        .unlink_if_stale(true)
+        .bind(path)
+        .expect_err("replace live listener of other type");
+    assert_eq!(error.kind(), AddrInUse);
+    let _conn = UnixStream::connect(path).expect("connect to original listener");
+    let _ = remove_file(path);
+
+    let path = "builder_regular_file.sock";
+    File::create(path).expect("create regular file");
+    let error = UnixListener::builder()
+        .unlink_if_stale(true)
+        .bind(path)
+        .expect_err("replace regular file");
+    assert_eq!(error.kind(), AddrInUse);
+    assert!(fs::metadata(path).expect("get metadata").is_file());
+    let _ = remove_file(path);
+}
+
+#[test]
+fn permissions_and_group() {
+    let path = "builder_permissions.sock";
+    let _ = remove_file(path);
+    let gid = fs::metadata(".").expect("get metadata of current directory").gid();
+    let _listener = UnixSeqpacketListener::builder()
+        .mode(0o604)
+        .group(gid)
+        .bind(path)
+        .expect("create listener with permissions");
+    let metadata = fs::metadata(path).expect("get metadata of socket");
+    assert_eq!(metadata.permissions().mode() & 0o7777, 0o604);
+    assert_eq!(metadata.gid(), gid);
+    let _ = remove_file(path);
+
+    let addr = UnixSocketAddr::new_unspecified();
+    let error = UnixListener::builder()
+        .mode(0o600)
+        .bind_unix_addr(&addr)
+        .expect_err("set permissions of unnamed socket");
+    assert_eq!(error.kind(), InvalidInput);
+}
+
+#[test]
+fn backlog() {
+    let path = "builder_backlog.sock";
+    let _ = remove_file(path);
+    let _listener = UnixSeqpacketListener::builder()
+        .backlog(1)
+        .bind(path)
+        .expect("create listener with small backlog");
+    let mut connected = Vec::new();
+    loop {
+        match NonblockingUnixSeqpacketConn::connect(path) {
+            Ok(conn) => connected.push(conn),
+            Err(ref e) if e.kind() == WouldBlock => break,
+            Err(e) => panic!("connect failed with {}", e),
+        }
+        assert!(connected.len() < 10, "backlog was not applied");
+    }
+    let _ = remove_file(path);
+}
@@ -16,6 +16,7 @@ use uds::{UnixSocketAddr, UnixSocketAddrRef, UnixStreamExt, UnixListenerExt};
 
 #[cfg(all(feature="mio-uds", feature="mio"))]
 #[test]
+#[allow(clippy::unused_io_amount)]
 fn mio_uds_stream() {
     use mio_uds::{UnixListener, UnixStream};
     use mio::{Poll, Events, Ready, PollOpt, Token};
@@ -127,12 +128,14 @@ macro_rules! mio_streams {
 
 #[cfg(feature = "mio_07")]
 #[test]
+#[allow(clippy::unused_io_amount)]
 fn mio_07_stream() {
     mio_streams!(mio_07);
 }
 
 #[cfg(feature = "mio_08")]
 #[test]
+#[allow(clippy::unused_io_amount)]
 fn mio_08_stream() {
     mio_streams!(mio_08);
 }
@@ -3,11 +3,13 @@
 use std::io::ErrorKind::*;
 use std::io::{IoSlice, IoSliceMut};
 use std::net::Shutdown;
-use std::os::unix::io::AsRawFd;
-use std::time::{Duration, Instant};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::time::{Duration, Instant, SystemTime};
 
 use uds::nonblocking::UnixSeqpacketConn as NonblockingUnixSeqpacketConn;
 use uds::{UnixSeqpacketConn, UnixSeqpacketListener};
+use uds::batch::{SendMessage, RecvMessage};
+use uds::ancillary::{AncillaryBuf, AncillaryItem};
 
 #[test]
 fn seqpacket_is_supported() {
@@ -183,6 +185,7 @@ fn vectored() {
 }
 
 #[test]
+#[allow(clippy::byte_char_slices)]
 fn shutdown() {
     // Blocking
     {
@@ -301,3 +304,275 @@ fn accept_timeout() {
         assert!(elapsed < 2*timeout, "elapsed: {:?}, timeout: {:?}", elapsed, timeout);
     }
 }
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn receive_timestamps() {
+    let (a, b) = NonblockingUnixSeqpacketConn::pair().unwrap();
+    let mut buf = [0; 10];
+    a.send(b"untimed").unwrap();
+    assert_eq!(b.recv_timestamped(&mut buf).unwrap(), (7, false, None));
+
+    b.set_receive_timestamps(true).unwrap();
+    let before = SystemTime::now();
+    a.send(b"timed").unwrap();
+    let after = SystemTime::now();
+    let (len, truncated, timestamp) = b.recv_timestamped(&mut buf[..3]).unwrap();
+    assert_eq!((len, truncated), (3, true));
+    let timestamp = timestamp.expect("get timestamp");
+    assert!(timestamp >= before - Duration::from_millis(1));
+    assert!(timestamp <= after + Duration::from_millis(1));
+
+    b.set_receive_timestamps(false).unwrap();
+    a.send(b"untimed").unwrap();
+    assert_eq!(b.recv_timestamped(&mut buf).unwrap(), (7, false, None));
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn batch() {
+    let (a, b) = NonblockingUnixSeqpacketConn::pair().unwrap();
+    let mut bufs = [[0u8; 8]; 3];
+    let mut fd_buf = [-1; 2];
+    let (first, rest) = bufs.split_at_mut(1);
+    let (second, third) = rest.split_at_mut(1);
+    let mut messages = [
+        RecvMessage::new(&mut first[0]),
+        RecvMessage::new(&mut second[0]).with_fds(&mut fd_buf),
+        RecvMessage::new(&mut third[0]),
+    ];
+    assert_eq!(b.recv_batch(&mut messages).unwrap_err().kind(), WouldBlock);
+
+    let fds = [a.as_raw_fd(), b.as_raw_fd()];
+    let sent = a.send_batch(&[
+        SendMessage::new(b"first"),
+        SendMessage::new(b"with fds").with_fds(&fds),
+    ]).unwrap();
+    assert_eq!(sent, 2);
+    assert_eq!(a.send_batch(&[]).unwrap(), 0);
+
+    assert_eq!(b.recv_batch(&mut messages).unwrap(), 2);
+    assert_eq!(messages[0].data(), b"first");
+    assert_eq!(messages[0].fds(), &[]);
+    assert_eq!(messages[1].data(), b"with fds");
+    assert_eq!(messages[1].fds().len(), 2);
+    assert!(!messages[1].info().fds_lost());
+    for &fd in messages[1].fds() {
+        assert_ne!(fd, -1);
+        let _ = unsafe { UnixSeqpacketConn::from_raw_fd(fd) };
+    }
+
+    // truncation is reported per message
+    a.send(b"too long for the buffer").unwrap();
+    assert_eq!(b.recv_batch(&mut messages[2..]).unwrap(), 1);
+    assert_eq!(messages[2].data(), b"too long");
+    assert!(messages[2].info().message_truncated);
+}
+
+#[test]
+fn blocking_batch() {
+    let (a, b) = UnixSeqpacketConn::pair().unwrap();
+    let messages = (0..100u8).map(|n| [n; 3] ).collect::<Vec<_>>();
+    let messages = messages.iter().map(|bytes| SendMessage::new(bytes) ).collect::<Vec<_>>();
+    assert_eq!(a.send_batch(&messages).unwrap(), 100);
+
+    let mut bufs = [[0u8; 4]; 64];
+    let mut received = 0;
+    while received < 100 {
+        let mut batch = bufs.iter_mut().map(|buf| RecvMessage::new(buf) ).collect::<Vec<_>>();
+        let count = b.recv_batch(&mut batch).unwrap();
+        assert!(count > 0);
+        for message in &batch[..count] {
+            assert_eq!(message.data(), &[received as u8; 3]);
+            received += 1;
+        }
+    }
+    assert_eq!(received, 100);
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[test]
+fn unsupported_ancillary_is_exposed() {
+    let (a, b) = NonblockingUnixSeqpacketConn::pair().unwrap();
+    // SO_TIMESTAMPING isn't parsed by uds, and only adds a message
+    // if ordinary timestamps are also enabled.
+    b.set_receive_timestamps(true).unwrap();
+    let flags = (libc::SOF_TIMESTAMPING_RX_SOFTWARE | libc::SOF_TIMESTAMPING_SOFTWARE) as libc::c_int;
+    let ret = unsafe {
+        libc::setsockopt(
+            b.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_TIMESTAMPING,
+            &flags as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    assert_eq!(ret, 0, "enable SO_TIMESTAMPING");
+
+    a.send(b"raw").unwrap();
+    let mut buf = [0; 10];
+    let mut ancillary_buf = AncillaryBuf::with_capacity(256);
+    let (_, ancillary) = b.recv_ancillary(&mut[IoSliceMut::new(&mut buf)], &mut ancillary_buf)
+        .unwrap();
+    let mut found = false;
+    for item in ancillary {
+        if let AncillaryItem::Unsupported { level, kind, data } = item {
+            assert_eq!((level, kind), (libc::SOL_SOCKET, libc::SCM_TIMESTAMPING));
+            // three timespecs, of which the first is the software timestamp
+            assert_eq!(data.len(), 3 * std::mem::size_of::<libc::timespec>());
+            assert!(data.iter().any(|&b| b != 0 ));
+            found = true;
+        }
+    }
+    assert!(found, "SCM_TIMESTAMPING was not received");
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[test]
+fn large_payloads() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    let big = (0..1_000_000u32).map(|n| n as u8 ).collect::<Vec<u8>>();
+    a.send(&big).expect_err("send too big packet normally");
+    a.send_large(&big).expect("send big payload");
+    a.send_large(&big[..32*1024]).expect("send payload with maximum inline size");
+    a.send_large(b"").expect("send empty payload");
+    assert_eq!(b.recv_large(big.len()).expect("receive big payload"), big);
+    assert_eq!(b.recv_large(32*1024).expect("receive inline payload"), &big[..32*1024]);
+    assert_eq!(b.recv_large(0).expect("receive empty payload"), b"");
+
+    let (a, b) = NonblockingUnixSeqpacketConn::pair().expect("create nonblocking seqpacket pair");
+    assert_eq!(b.recv_large(usize::MAX).expect_err("receive when nothing sent").kind(), WouldBlock);
+    a.send_large(&big).expect("send big payload");
+    assert_eq!(b.recv_large(big.len()).expect("receive big payload"), big);
+
+    a.send_large(&big).expect("send big payload");
+    a.send_large(b"too long").expect("send small payload");
+    a.send_large(b"ok").expect("send small payload");
+    assert_eq!(b.recv_large(big.len()-1).expect_err("receive too long payload").kind(), InvalidData);
+    assert_eq!(b.recv_large(7).expect_err("receive too long inline payload").kind(), InvalidData);
+    assert_eq!(b.recv_large(2).expect("receive next payload"), b"ok");
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[test]
+fn large_payload_must_be_sealed() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    a.send(b"").expect("send empty packet");
+    assert_eq!(b.recv_large(usize::MAX).expect_err("receive empty packet").kind(), InvalidData);
+
+    let memfd = unsafe { libc::memfd_create(b"unsealed\0".as_ptr() as *const _, 0) };
+    assert!(memfd != -1, "create memfd");
+    let memfd = unsafe { std::fs::File::from_raw_fd(memfd) };
+    memfd.set_len(4).expect("set memfd length");
+    let mut header = vec![1u8];
+    header.extend_from_slice(&4u64.to_le_bytes());
+    a.send_fds(&header, &[memfd.as_raw_fd()]).expect("send unsealed memfd");
+    assert_eq!(b.recv_large(usize::MAX).expect_err("receive unsealed memfd").kind(), InvalidData);
+
+    let (c, _) = UnixSeqpacketConn::pair().expect("create another seqpacket pair");
+    a.send_fds(&header, &[c.as_raw_fd()]).expect("send socket instead of memfd");
+    assert_eq!(b.recv_large(usize::MAX).expect_err("receive socket instead of memfd").kind(), InvalidData);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), test)]
+fn peek_ancillary() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    #[cfg(any(target_os="linux", target_os="android"))]
+    b.set_pass_credentials(true).expect("enable receiving credentials");
+    a.send_fds(b"dispatch me", &[a.as_raw_fd(), b.as_raw_fd(), a.as_raw_fd()])
+        .expect("send packet with fds");
+
+    for _ in 0..2 {
+        let mut header = [0u8; 8];
+        let mut ancillary_buf = AncillaryBuf::with_capacity(200);
+        let (bytes, ancillary) = b.peek_ancillary(
+            &mut[IoSliceMut::new(&mut header)],
+            &mut ancillary_buf,
+        ).expect("peek packet with fds");
+        assert_eq!(bytes, 8);
+        assert!(ancillary.message_truncated());
+        let mut peeked_fds = 0;
+        let mut peeked_credentials = false;
+        for item in ancillary {
+            match item {
+                AncillaryItem::Fds(fds) => {
+                    assert!(fds.iter().all(|&fd| fd == -1 ), "peeked fds are closed");
+                    peeked_fds += fds.len();
+                }
+                AncillaryItem::Credentials(creds) => {
+                    assert_eq!(creds.pid(), Some(std::process::id()));
+                    peeked_credentials = true;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(peeked_fds, 3);
+        assert_eq!(peeked_credentials, cfg!(any(target_os="linux", target_os="android")));
+    }
+
+    let mut ancillary_buf = AncillaryBuf::with_capacity(200);
+    let (bytes, ancillary) = b.recv_ancillary(
+        &mut[IoSliceMut::new(&mut[0u8; 16])],
+        &mut ancillary_buf,
+    ).expect("receive peeked packet");
+    assert_eq!(bytes, 11);
+    let mut received_fds = 0;
+    for item in ancillary {
+        if let AncillaryItem::Fds(fds) = item {
+            for &fd in fds {
+                assert_ne!(fd, -1);
+                let _ = unsafe { UnixSeqpacketConn::from_raw_fd(fd) };
+            }
+            received_fds += fds.len();
+        }
+    }
+    assert_eq!(received_fds, 3);
+
+    let (_a, b) = NonblockingUnixSeqpacketConn::pair().expect("create nonblocking seqpacket pair");
+    let error = b.peek_ancillary(&mut[IoSliceMut::new(&mut[0u8; 1])], &mut[0u8; 0])
+        .expect_err("peek empty queue");
+    assert_eq!(error.kind(), WouldBlock);
+}
+
+#[test]
+fn recv_to_vec() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create seqpacket pair");
+    let big = (0..100_000u32).map(|n| n as u8 ).collect::<Vec<u8>>();
+    a.send(&big).expect("send big packet");
+    a.send_fds(b"fds", &[a.as_raw_fd()]).expect("send packet with fd");
+    assert_eq!(b.peek_len().expect("peek length of big packet"), big.len());
+    assert_eq!(b.peek_len().expect("peek length again"), big.len());
+    assert_eq!(b.recv_to_vec().expect("receive big packet"), big);
+    assert_eq!(b.recv_to_vec().expect("receive packet with fd"), b"fds");
+
+    let (a, b) = NonblockingUnixSeqpacketConn::pair().expect("create nonblocking seqpacket pair");
+    assert_eq!(b.peek_len().expect_err("peek length when empty").kind(), WouldBlock);
+    assert_eq!(b.recv_to_vec().expect_err("receive when empty").kind(), WouldBlock);
+    a.send(b"one").expect("send packet");
+    a.send(b"two").expect("send packet");
+    assert_eq!(b.peek_len().expect("peek length"), 3);
+    assert_eq!(b.recv_to_vec().expect("receive first packet"), b"one");
+    assert_eq!(b.recv_to_vec().expect("receive second packet"), b"two");
+}
+
+#[test]
+fn buffer_sizes_and_queues() {
+    let (a, b) = NonblockingUnixSeqpacketConn::pair().expect("create nonblocking seqpacket pair");
+    a.set_send_buffer_size(8*1024).expect("set send buffer size");
+    let send_size = a.send_buffer_size().expect("get send buffer size");
+    assert!((8*1024..64*1024).contains(&send_size), "send buffer is {} bytes", send_size);
+    b.set_recv_buffer_size(8*1024).expect("set receive buffer size");
+    assert!(b.recv_buffer_size().expect("get receive buffer size") >= 8*1024);
+
+    let mut sent = 0;
+    while a.send(&[7; 1000]).is_ok() {
+        sent += 1000;
+    }
+    assert!(sent > 0 && sent < send_size, "sent {} bytes", sent);
+    assert_eq!(b.pending_incoming().expect("get pending incoming"), sent);
+    #[cfg(any(target_os="linux", target_os="android"))]
+    assert!(a.pending_outgoing().expect("get pending outgoing") >= sent);
+
+    while b.recv(&mut[0; 1000]).is_ok() {}
+    assert_eq!(b.pending_incoming().expect("get pending incoming when empty"), 0);
+    #[cfg(any(target_os="linux", target_os="android"))]
+    assert_eq!(a.pending_outgoing().expect("get pending outgoing when empty"), 0);
+}
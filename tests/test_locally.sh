@@ -1,5 +1,5 @@
 #!/bin/sh
-MSRV="1.39.0"
+MSRV="1.63.0"
 CAFLAGS="-j1"
 export RUST_BACKTRACE=1
 
@@ -0,0 +1,224 @@
+#![cfg(all(feature="tokio1", not(target_vendor="apple")))]
+
+use std::io::{self, ErrorKind::*, IoSlice, IoSliceMut, Read, Write};
+use std::future::poll_fn;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+use std::os::unix::net::UnixStream;
+
+use libc::{getpid, geteuid, getegid};
+
+use tokio_1 as tokio;
+use tokio::io::{Interest, Ready};
+
+use uds::tokio1::{UnixSeqpacketConn, UnixSeqpacketListener};
+use uds::{nonblocking, UnixSocketAddr};
+use uds::ancillary::{ReceivedFds, RecvFdsInfo};
+
+#[tokio::test(crate="tokio_1")]
+async fn test_listener_accept() {
+    let sock_path = "tokio1 listener.socket";
+    let _ = std::fs::remove_file(sock_path);
+    let listener = UnixSeqpacketListener::bind(sock_path).unwrap();
+
+    let listener_handle = tokio::task::spawn(async move {
+        for i in 1usize..=3 {
+            let (socket, _) = listener.accept().await?;
+            tokio::task::spawn(async move {
+                socket.send(&[b'h', b'i', b'0' + (i as u8)]).await.unwrap();
+            });
+        }
+        Ok::<(), io::Error>(())
+    });
+
+    for i in 1usize..=3 {
+        let socket = UnixSeqpacketConn::connect(sock_path).await.unwrap();
+        let mut buf = [0u8; 3];
+        let read = socket.recv(&mut buf).await.unwrap();
+        assert_eq!(read, 3);
+        assert_eq!(&buf, &[b'h', b'i', b'0' + (i as u8)]);
+    }
+
+    assert!(listener_handle.await.unwrap().is_ok());
+    let _ = std::fs::remove_file(sock_path);
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn test_addr() {
+    let listener_path = "tokio1 listener with addr.socket";
+    let _ = std::fs::remove_file(listener_path);
+    let listener_addr = UnixSocketAddr::new(&listener_path).unwrap();
+
+    let client_path = "tokio1 named client.socket";
+    let _ = std::fs::remove_file(client_path);
+    let client_addr = UnixSocketAddr::new(&client_path).unwrap();
+
+    let listener = UnixSeqpacketListener::bind_addr(&listener_addr).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), listener_addr);
+    assert_eq!(listener.try_accept().unwrap_err().kind(), WouldBlock);
+
+    let named = UnixSeqpacketConn::connect_from_addr(&client_addr, &listener_addr)
+        .await
+        .unwrap();
+    assert_eq!(named.local_addr().unwrap(), client_addr);
+    assert_eq!(named.peer_addr().unwrap(), listener_addr);
+    let (accepted, addr) = poll_fn(|cx| listener.poll_accept(cx) ).await.unwrap();
+    assert_eq!(addr, client_addr);
+    accepted.send(b"hello").await.unwrap();
+    assert_eq!(named.recv(&mut[0; 10]).await.unwrap(), 5);
+
+    let _ = std::fs::remove_file(listener_path);
+    let _ = std::fs::remove_file(client_path);
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn test_try_and_ready() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+    let mut buf = [0u8; 10];
+    assert_eq!(b.try_recv(&mut buf).unwrap_err().kind(), WouldBlock);
+    assert_eq!(b.try_peek(&mut buf).unwrap_err().kind(), WouldBlock);
+
+    let ready = a.ready(Interest::WRITABLE).await.expect("wait for writable");
+    assert!(ready.is_writable());
+    assert_eq!(a.try_send(b"first").expect("try send"), 5);
+    a.writable().await.expect("wait for writable again");
+    assert_eq!(a.try_send_vectored(&[IoSlice::new(b"sec"), IoSlice::new(b"ond")])
+        .expect("try send vectored"), 6);
+
+    let ready = b.ready(Interest::READABLE | Interest::WRITABLE).await.expect("wait for any");
+    assert_ne!(ready & (Ready::READABLE | Ready::WRITABLE), Ready::EMPTY);
+    assert!(b.ready(Interest::READABLE).await.expect("wait for readable").is_readable());
+    assert_eq!(b.try_peek(&mut buf).expect("try peek"), 5);
+    assert_eq!(b.try_recv(&mut buf).expect("try receive"), 5);
+    assert_eq!(&buf[..5], b"first");
+    b.readable().await.expect("wait for readable again");
+    let (front, back) = buf.split_at_mut(3);
+    let received = b.try_recv_vectored(&mut[IoSliceMut::new(front), IoSliceMut::new(back)])
+        .expect("try receive vectored");
+    assert_eq!(received, (6, false));
+    assert_eq!(&buf[..6], b"second");
+    assert_eq!(b.try_recv(&mut buf).unwrap_err().kind(), WouldBlock);
+
+    let mut sent = 0;
+    loop {
+        match a.try_send(&[0; 1000]) {
+            Ok(_) => sent += 1,
+            Err(e) => {
+                assert_eq!(e.kind(), WouldBlock);
+                break;
+            }
+        }
+    }
+    assert!(sent > 0);
+    poll_fn(|cx| b.poll_recv_ready(cx) ).await.expect("poll receive ready");
+    for _ in 0..sent {
+        b.recv(&mut buf).await.expect("receive queued packet");
+    }
+    poll_fn(|cx| a.poll_send_ready(cx) ).await.expect("poll send ready");
+    a.send(b"more").await.expect("send after receive");
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn test_vectored_and_peek() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+    a.send_vectored(&[IoSlice::new(b"send"), IoSlice::new(b" one")]).await.expect("send");
+
+    let mut buf = [0; 10];
+    assert_eq!(b.peek(&mut buf).await.expect("peek"), 8);
+    assert_eq!(&buf, b"send one\0\0");
+    let (front, back) = buf[2..].split_at_mut(4);
+    let peeked = b.peek_vectored(&mut[IoSliceMut::new(front), IoSliceMut::new(back)])
+        .await
+        .expect("peek with vectors");
+    assert_eq!(peeked, (8, false));
+    assert_eq!(&buf, b"sesend one");
+    assert_eq!(b.peek_len().await.expect("peek length"), 8);
+
+    let (front, back) = buf.split_at_mut(2);
+    let received = b.recv_vectored(&mut[IoSliceMut::new(front), IoSliceMut::new(back)])
+        .await
+        .expect("receive with vectors");
+    assert_eq!(received, (8, false));
+    a.send(&[7; 70_000]).await.expect("send big packet");
+    assert_eq!(b.recv_to_vec().await.expect("receive big packet"), vec![7; 70_000]);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), tokio::test(crate="tokio_1"))]
+#[cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
+async fn test_fd_passing() {
+    let (a, b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+    let (mut to_pass, mut to_test) = UnixStream::pair().expect("create blocking stream pair");
+
+    a.send_fds(b"a stream", &[to_pass.as_raw_fd()]).await.expect("send fd");
+    to_pass.write_all(b"once").expect("write");
+
+    let mut byte_buf = [0; 8];
+    let mut fd_buf = [-1; 2];
+    let RecvFdsInfo { bytes, message_truncated, fds, .. } = b
+        .recv_fds(&mut byte_buf, &mut fd_buf)
+        .await
+        .expect("receive fd");
+    assert_eq!((bytes, message_truncated, fds), (8, false, 1));
+    assert_eq!(byte_buf, *b"a stream");
+
+    let mut received = unsafe { UnixStream::from_raw_fd(fd_buf[0]) };
+    received.write_all(b" and again").expect("write on received fd");
+    assert_eq!(to_test.read(&mut byte_buf).expect("read stream"), 8);
+    assert_eq!(byte_buf, *b"once and");
+
+    a.try_send_fds(b"try", &[to_pass.as_raw_fd()]).expect("try send fd");
+    a.send_borrowed_fds(b"borrowed", &[&to_pass]).await.expect("send borrowed fd");
+    let info = b.try_recv_fds(&mut byte_buf, &mut fd_buf).expect("try receive fd");
+    assert_eq!((info.bytes, info.fds), (3, 1));
+    unsafe { libc::close(fd_buf[0]) };
+    let mut owned = ReceivedFds::with_capacity(1);
+    let info = b.recv_owned_fds(&mut byte_buf, &mut owned).await.expect("receive owned fd");
+    assert_eq!((info.bytes, owned.len()), (8, 1));
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn test_shutdown() {
+    let (sock_tx, sock_rx) = UnixSeqpacketConn::pair().unwrap();
+
+    sock_tx.shutdown(Shutdown::Both).unwrap();
+    assert!(sock_tx.send(b"hi0").await.is_err());
+    assert_eq!(sock_rx.recv(&mut [0u8; 3]).await.unwrap(), 0);
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn test_peer_credentials() {
+    let (a, _b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+    match a.initial_peer_credentials() {
+        Ok(creds) => {
+            if let Some(pid) = creds.pid() {
+                assert_eq!(pid.get(), unsafe { getpid() } as u32);
+            }
+            assert_eq!(creds.euid(), unsafe { geteuid() } as u32);
+            if let Some(egid) = creds.egid() {
+                assert_eq!(egid, unsafe { getegid() } as u32);
+            }
+        }
+        Err(e) => assert_ne!(e.kind(), WouldBlock)
+    }
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn test_raw_fd_conversions() {
+    let (a_nonblocking, b_nonblocking) = nonblocking::UnixSeqpacketConn::pair()
+        .expect("create nonblocking seqpacket pair");
+
+    let a_fd = a_nonblocking.as_raw_fd();
+    let a = unsafe {
+        UnixSeqpacketConn::from_raw_fd(a_nonblocking.into_raw_fd())
+            .expect("create from raw fd")
+    };
+    assert_eq!(a.as_raw_fd(), a_fd);
+    a.send(b"I'm registered").await.expect("send from constructed");
+    let len = b_nonblocking.recv(&mut[0; 24]).expect("receive on un-registered");
+    assert_eq!(len, 14);
+
+    let a_nonblocking = a.into_nonblocking();
+    assert_eq!(a_nonblocking.as_raw_fd(), a_fd);
+    a_nonblocking.send(b"hi").expect("send from deregistered socket");
+    assert_eq!(b_nonblocking.recv(&mut[0; 10]).expect("receive"), 2);
+}
@@ -0,0 +1,182 @@
+#![cfg(all(feature="tokio1", not(target_vendor="apple")))]
+#![cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
+
+use std::io::{Read, Write};
+use std::fs::remove_file;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net as std_net;
+
+use libc::geteuid;
+
+use tokio_1 as tokio;
+use tokio::net::{UnixStream, UnixListener, UnixDatagram};
+
+use uds::tokio1::{UnixStreamExt, UnixListenerExt, UnixDatagramExt};
+use uds::UnixSocketAddr;
+use uds::ancillary::ReceivedFds;
+
+#[tokio::test(crate="tokio_1")]
+async fn stream_path_addrs() {
+    let listener_path = "tokio1 ext listener.sock";
+    let client_path = "tokio1 ext client.sock";
+    let _ = remove_file(listener_path);
+    let _ = remove_file(client_path);
+    let listener_addr = UnixSocketAddr::new(listener_path).unwrap();
+    let client_addr = UnixSocketAddr::new(client_path).unwrap();
+
+    let listener = UnixListener::bind_unix_addr(&listener_addr).expect("bind listener");
+    assert_eq!(listener.local_unix_addr().unwrap(), listener_addr);
+    let client = UnixStream::connect_from_to_unix_addr(&client_addr, &listener_addr)
+        .await
+        .expect("connect from path");
+    let (accepted, addr) = listener.accept_unix_addr().await.expect("accept");
+    assert_eq!(addr, client_addr);
+    assert_eq!(client.local_unix_addr().unwrap(), client_addr);
+    assert_eq!(client.peer_unix_addr().unwrap(), listener_addr);
+    assert_eq!(accepted.local_unix_addr().unwrap(), listener_addr);
+    assert_eq!(accepted.peer_unix_addr().unwrap(), client_addr);
+    assert_eq!(accepted.initial_peer_credentials().unwrap().euid(), unsafe { geteuid() } as u32);
+
+    client.writable().await.unwrap();
+    assert_eq!(client.try_write(b"plain").expect("write"), 5);
+    accepted.readable().await.unwrap();
+    let mut buf = [0; 10];
+    assert_eq!(accepted.try_read(&mut buf).expect("read"), 5);
+    assert_eq!(&buf[..5], b"plain");
+
+    let _ = remove_file(listener_path);
+    let _ = remove_file(client_path);
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[tokio::test(crate="tokio_1")]
+async fn stream_abstract_addrs() {
+    let addr = UnixSocketAddr::from_abstract(b"tokio1 ext abstract stream").unwrap();
+    let listener = UnixListener::bind_unix_addr(&addr).expect("bind abstract listener");
+    assert_eq!(listener.local_unix_addr().unwrap(), addr);
+    let client = UnixStream::connect_to_unix_addr(&addr).await.expect("connect to abstract");
+    let (_accepted, client_addr) = listener.accept_unix_addr().await.expect("accept");
+    assert!(client_addr.is_unnamed());
+    assert_eq!(client.peer_unix_addr().unwrap(), addr);
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn stream_connect_to_nothing() {
+    let addr = UnixSocketAddr::new("tokio1 ext nothing.sock").unwrap();
+    let _ = remove_file("tokio1 ext nothing.sock");
+    assert!(UnixStream::connect_to_unix_addr(&addr).await.is_err());
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), tokio::test(crate="tokio_1"))]
+async fn stream_fd_passing() {
+    let (a, b) = UnixStream::pair().expect("create tokio stream pair");
+    let (mut to_pass, mut to_test) = std_net::UnixStream::pair().expect("create std stream pair");
+
+    let mut buf = [0; 10];
+    let mut fd_buf = [-1; 2];
+    let (sent, received) = tokio::join!(
+        async {
+            tokio::task::yield_now().await;
+            a.send_fds(b"fd", &[to_pass.as_raw_fd()]).await
+        },
+        b.recv_fds(&mut buf, &mut fd_buf),
+    );
+    assert_eq!(sent.expect("send fd"), 2);
+    let info = received.expect("receive fd");
+    assert_eq!((info.bytes, info.fds), (2, 1));
+    assert_eq!(&buf[..2], b"fd");
+
+    let mut received = unsafe { std_net::UnixStream::from_raw_fd(fd_buf[0]) };
+    received.write_all(b"hello").expect("write to received fd");
+    to_test.read_exact(&mut buf[..5]).expect("read");
+    assert_eq!(&buf[..5], b"hello");
+
+    b.send_borrowed_fds(b"back", &[&to_pass]).await.expect("send borrowed fd");
+    let mut fds = ReceivedFds::with_capacity(1);
+    let info = a.recv_owned_fds(&mut buf, &mut fds).await.expect("receive owned fd");
+    assert_eq!((info.bytes, fds.len()), (4, 1));
+    to_pass.write_all(b"x").unwrap();
+}
+
+fn spawn_generic_send<S>(stream: S,  fd: RawFd) -> tokio::task::JoinHandle<std::io::Result<usize>>
+where S: UnixStreamExt + Send + Sync + 'static {
+    tokio::spawn(async move { stream.send_fds(b"generic", &[fd]).await })
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), tokio::test(crate="tokio_1"))]
+async fn futures_are_send_in_generic_code() {
+    let (a, b) = UnixStream::pair().expect("create tokio stream pair");
+    let fd = a.as_raw_fd();
+    let sent = spawn_generic_send(a, fd).await.unwrap();
+    assert_eq!(sent.expect("send fd from spawned task"), 7);
+    let mut fds = ReceivedFds::with_capacity(1);
+    let info = b.recv_owned_fds(&mut [0; 10], &mut fds).await.expect("receive fd");
+    assert_eq!((info.bytes, fds.len()), (7, 1));
+}
+
+#[tokio::test(crate="tokio_1")]
+async fn datagram_addrs() {
+    let path = "tokio1 ext datagram.sock";
+    let _ = remove_file(path);
+    let addr = UnixSocketAddr::new(path).unwrap();
+    let server = UnixDatagram::bind_unix_addr(&addr).expect("bind datagram socket");
+    assert_eq!(server.local_unix_addr().unwrap(), addr);
+
+    let client = UnixDatagram::unbound().expect("create unbound socket");
+    let mut buf = [0; 10];
+    let (sent, received) = tokio::join!(
+        async {
+            tokio::task::yield_now().await;
+            client.send_to_unix_addr(b"to path", &addr).await
+        },
+        server.recv_from_unix_addr(&mut buf),
+    );
+    assert_eq!(sent.expect("send to path"), 7);
+    let (len, from) = received.expect("receive");
+    assert_eq!(len, 7);
+    assert!(from.is_unnamed());
+
+    let client_path = "tokio1 ext datagram client.sock";
+    let _ = remove_file(client_path);
+    let client_addr = UnixSocketAddr::new(client_path).unwrap();
+    client.bind_to_unix_addr(&client_addr).expect("bind client");
+    client.connect_to_unix_addr(&addr).expect("connect client");
+    assert_eq!(client.peer_unix_addr().unwrap(), addr);
+    client.send(b"connected").await.expect("send on connected socket");
+    let (len, from) = server.recv_from_unix_addr(&mut buf).await.expect("receive");
+    assert_eq!(&buf[..len], b"connected");
+    assert_eq!(from, client_addr);
+
+    let _ = remove_file(path);
+    let _ = remove_file(client_path);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), tokio::test(crate="tokio_1"))]
+async fn datagram_fd_passing() {
+    let (a, b) = UnixDatagram::pair().expect("create tokio datagram pair");
+    let (to_pass, _) = std_net::UnixStream::pair().expect("create std stream pair");
+
+    a.send_fds(b"one", &[to_pass.as_raw_fd()]).await.expect("send fd");
+    a.send_borrowed_fds(b"two", &[&to_pass]).await.expect("send borrowed fd");
+    let mut buf = [0; 10];
+    let mut fd_buf = [-1; 2];
+    let info = b.recv_fds(&mut buf, &mut fd_buf).await.expect("receive fd");
+    assert_eq!((info.bytes, info.fds), (3, 1));
+    unsafe { libc::close(fd_buf[0]) };
+    let mut fds = ReceivedFds::with_capacity(2);
+    let info = b.recv_owned_fds(&mut buf, &mut fds).await.expect("receive owned fd");
+    assert_eq!((info.bytes, fds.len()), (3, 1));
+    assert_eq!(&buf[..3], b"two");
+
+    let path = "tokio1 ext datagram fds.sock";
+    let _ = remove_file(path);
+    let addr = UnixSocketAddr::new(path).unwrap();
+    let server = UnixDatagram::bind_unix_addr(&addr).expect("bind datagram socket");
+    let client = UnixDatagram::unbound().unwrap();
+    client.send_fds_to(b"to", &[to_pass.as_raw_fd()], &addr).await.expect("send fd to path");
+    let (info, from) = server.recv_fds_from(&mut buf, &mut fd_buf).await.expect("receive fd");
+    assert_eq!((info.bytes, info.fds), (2, 1));
+    assert!(from.is_unnamed());
+    unsafe { libc::close(fd_buf[0]) };
+    let _ = remove_file(path);
+}
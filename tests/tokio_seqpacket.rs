@@ -11,6 +11,8 @@ use tokio_02 as tokio;
 
 use uds::tokio::{UnixSeqpacketConn, UnixSeqpacketListener};
 use uds::{nonblocking, UnixSocketAddr};
+use uds::ancillary::RecvFdsInfo;
+use uds::batch::{SendMessage, RecvMessage};
 
 #[tokio::test]
 async fn test_listener_accept() {
@@ -93,6 +95,7 @@ async fn test_addr() {
 }
 
 #[tokio::test]
+#[allow(clippy::byte_char_slices)]
 async fn test_conn_pair() {
     let (mut sock_tx, mut sock_rx) = UnixSeqpacketConn::pair()
         .expect("create tokio seqpacket pair");
@@ -154,6 +157,7 @@ async fn test_peek() {
 
 #[cfg_attr(not(any(target_os="illumos", target_os="solaris")), tokio::test)]
 #[cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
+#[allow(clippy::bool_assert_comparison, clippy::unused_io_amount)]
 async fn test_fd_passing() {
     let (mut a, mut b) = UnixSeqpacketConn::pair()
         .expect("create tokio seqpacket pair");
@@ -167,7 +171,8 @@ async fn test_fd_passing() {
 
     let mut byte_buf = [0; 8];
     let mut fd_buf = [-1; 2];
-    let (bytes, truncated, fds) = b.recv_fds(&mut byte_buf, &mut fd_buf)
+    let RecvFdsInfo { bytes, message_truncated: truncated, fds, .. } = b
+        .recv_fds(&mut byte_buf, &mut fd_buf)
         .await
         .expect("receive fd");
     assert_eq!(bytes, 8);
@@ -185,6 +190,7 @@ async fn test_fd_passing() {
 }
 
 #[tokio::test]
+#[allow(clippy::byte_char_slices)]
 async fn test_shutdown() {
     let (mut sock_tx, mut sock_rx) = UnixSeqpacketConn::pair().unwrap();
 
@@ -260,3 +266,96 @@ async fn test_conn_into_raw_fd() {
     assert_eq!(b.recv(&mut buf).await.expect("receive"), 2);
     assert_eq!(&buf[..2], b"hi");
 }
+
+#[tokio::test]
+async fn test_batch() {
+    let (mut a, mut b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+
+    tokio::task::spawn(async move {
+        let messages = [SendMessage::new(b"one"), SendMessage::new(b"two")];
+        a.send_batch(&messages).await.expect("send batch");
+    });
+
+    let (mut first, mut second) = ([0u8; 4], [0u8; 4]);
+    let mut messages = [RecvMessage::new(&mut first), RecvMessage::new(&mut second)];
+    let mut received = b.recv_batch(&mut messages).await.expect("receive batch");
+    assert_eq!(messages[0].data(), b"one");
+    if received == 1 {
+        received += b.recv_batch(&mut messages[1..]).await.expect("receive rest of batch");
+    }
+    assert_eq!(received, 2);
+    assert_eq!(messages[1].data(), b"two");
+}
+
+#[tokio::test]
+async fn test_recv_to_vec() {
+    let (mut a, mut b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+
+    tokio::task::spawn(async move {
+        a.send(&[7; 70_000]).await.expect("send big packet");
+        a.send(b"small").await.expect("send small packet");
+    });
+
+    assert_eq!(b.peek_len().await.expect("peek length"), 70_000);
+    assert_eq!(b.recv_to_vec().await.expect("receive big packet"), vec![7; 70_000]);
+    assert_eq!(b.recv_to_vec().await.expect("receive small packet"), b"small");
+}
+
+#[tokio::test]
+async fn test_split() {
+    let (mut a, mut b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+    let (mut read, mut write) = a.split();
+    let mut buf = [0; 10];
+    let (sent, received) = tokio::join!(
+        write.send(b"to b"),
+        async {
+            b.send(b"to a").await.expect("send to a");
+            read.recv(&mut buf).await
+        }
+    );
+    assert_eq!(sent.expect("send from write half"), 4);
+    assert_eq!(received.expect("receive on read half"), 4);
+    assert_eq!(&buf[..4], b"to a");
+    assert_eq!(read.local_addr().unwrap(), write.local_addr().unwrap());
+    write.shutdown().expect("shut down write half");
+    assert_eq!(b.recv(&mut buf).await.expect("receive"), 4);
+    assert_eq!(b.recv(&mut buf).await.expect("receive end"), 0);
+}
+
+#[cfg_attr(not(any(target_os="illumos", target_os="solaris")), tokio::test)]
+#[cfg_attr(any(target_os="illumos", target_os="solaris"), allow(unused))]
+async fn test_into_split() {
+    let (a, mut b) = UnixSeqpacketConn::pair().expect("create tokio seqpacket pair");
+    let (mut read, mut write) = a.into_split();
+
+    let reader = tokio::task::spawn(async move {
+        assert_eq!(read.peek_len().await.expect("peek length"), 4);
+        let mut buf = [0; 10];
+        let mut fd_buf = [-1; 2];
+        let info = read.recv_fds(&mut buf, &mut fd_buf).await.expect("receive fd");
+        assert_eq!((info.bytes, info.fds), (4, 1));
+        unsafe { libc::close(fd_buf[0]) };
+        read
+    });
+    let (to_pass, _) = UnixStream::pair().expect("create stream pair");
+    b.send_fds(b"a fd", &[to_pass.as_raw_fd()]).await.expect("send fd");
+    write.send_borrowed_fds(b"back", &[&to_pass]).await.expect("send fd from write half");
+    let mut fds = uds::ancillary::ReceivedFds::with_capacity(1);
+    let info = b.recv_owned_fds(&mut[0; 10], &mut fds).await.expect("receive fd");
+    assert_eq!((info.bytes, fds.len()), (4, 1));
+    let read = reader.await.unwrap();
+
+    let (other, _c) = UnixSeqpacketConn::pair().expect("create another pair");
+    let (other_read, other_write) = other.into_split();
+    let (read, _other_write) = match read.reunite(other_write) {
+        Ok(_) => panic!("reunited halves of different connections"),
+        Err(error) => (error.0, error.1),
+    };
+    let write = match other_read.reunite(write) {
+        Ok(_) => panic!("reunited halves of different connections"),
+        Err(error) => error.1,
+    };
+    let mut a = write.reunite(read).expect("reunite halves of the same connection");
+    a.send(b"whole").await.expect("send after reuniting");
+    assert_eq!(b.recv(&mut[0; 10]).await.expect("receive"), 5);
+}
@@ -0,0 +1,58 @@
+#![cfg(not(target_vendor="apple"))]
+
+extern crate uds;
+
+use std::fs::remove_file;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+
+use uds::{UnixSeqpacketListener, UnlinkOnDrop};
+
+#[test]
+fn removes_own_socket_file() {
+    let path = "unlink_own.sock";
+    let _ = remove_file(path);
+    let listener = UnlinkOnDrop::new(UnixListener::bind(path).expect("create listener"))
+        .expect("remember socket file");
+    let absolute = std::env::current_dir().expect("get current directory").join(path);
+    assert_eq!(listener.path(), Some(absolute.as_path()));
+    drop(listener);
+    assert!(!Path::new(path).exists());
+
+    let listener = UnixSeqpacketListener::bind(path).expect("create seqpacket listener");
+    let listener = UnlinkOnDrop::new(listener).expect("remember socket file");
+    let listener = listener.into_inner();
+    assert!(Path::new(path).exists());
+    drop(listener);
+    assert!(Path::new(path).exists());
+    let _ = remove_file(path);
+}
+
+#[test]
+fn keeps_replaced_socket_file() {
+    let path = "unlink_replaced.sock";
+    let _ = remove_file(path);
+    let mut old = UnlinkOnDrop::new(UnixSeqpacketListener::bind(path).expect("create listener"))
+        .expect("remember socket file");
+    remove_file(path).expect("remove socket file");
+    let new = UnixSeqpacketListener::bind(path).expect("create replacement listener");
+    assert!(!old.unlink().expect("try to unlink replaced file"));
+    drop(old);
+    assert!(Path::new(path).exists());
+
+    let new = UnlinkOnDrop::new(new).expect("remember replacement file");
+    remove_file(path).expect("remove replacement socket file");
+    drop(new);
+    assert!(!Path::new(path).exists());
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+#[test]
+fn abstract_addresses_have_no_file() {
+    use uds::{UnixSocketAddr, UnixListenerExt};
+    let addr = UnixSocketAddr::from_abstract("unlink_on_drop_abstract").expect("create address");
+    let listener = UnixListener::bind_unix_addr(&addr).expect("create abstract listener");
+    let mut listener = UnlinkOnDrop::new(listener).expect("wrap abstract listener");
+    assert_eq!(listener.path(), None);
+    assert!(!listener.unlink().expect("unlink nothing"));
+}